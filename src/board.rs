@@ -3,17 +3,66 @@ use std::str::FromStr;
 
 use crate::cell::Cell;
 use crate::cell::ParseCellError;
+use crate::cell_id::CellId;
 use crate::cells_are_valid::{cells_are_valid, CellsImpossibleError};
+use crate::player::Player;
 
 pub const NUM_COLS: usize = 3;
 pub const NUM_ROWS: usize = 3;
-pub const NUM_CELLS: usize = NUM_ROWS * NUM_COLS;
 
-#[derive(Debug, Default)]
+// columns are lettered, so this is as wide as a board can get
+pub const MAX_SIZE: usize = 26;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
+    pub num_rows: usize,
+    pub num_cols: usize,
     // we will use iterators to turn cells into
     // coherent rows and columns
-    pub cells: [Cell; NUM_CELLS],
+    pub cells: Vec<Cell>,
+}
+
+impl Board {
+    pub fn new(num_rows: usize, num_cols: usize) -> Board {
+        Board {
+            num_rows,
+            num_cols,
+            cells: vec![Cell::Unmarked; num_rows * num_cols],
+        }
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cell(&self, cell_id: &CellId) -> Cell {
+        self.cells[cell_id.to_idx(self.num_cols)]
+    }
+
+    pub fn num_marked(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|cell| **cell != Cell::Unmarked)
+            .count()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.num_marked() == self.num_cells()
+    }
+
+    pub fn unmarked_cell_ids(&self) -> impl Iterator<Item = CellId> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell == Cell::Unmarked)
+            .map(|(idx, _)| CellId::from_idx(idx, self.num_cols))
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(NUM_ROWS, NUM_COLS)
+    }
 }
 
 impl fmt::Display for Board {
@@ -31,38 +80,56 @@ pub enum ParseBoardError {
     ImpossibleCells(CellsImpossibleError),
 }
 
-impl FromStr for Board {
-    type Err = ParseBoardError;
-    fn from_str(board_str: &str) -> Result<Self, Self::Err> {
-        match board_str {
-            "" => Err(Self::Err::Empty),
-            g if g.len() != NUM_CELLS => Err(Self::Err::BadLen),
-            _ => {
-                let mut cells: [Cell; NUM_CELLS] = [Cell::Unmarked; NUM_CELLS];
+// boards are written as one square of cells, so the side is the
+// square root of the number of cells
+fn side_len(num_cells: usize) -> Option<usize> {
+    (1..=MAX_SIZE).find(|side| side * side == num_cells)
+}
+
+impl Board {
+    // reads a board from a game that `first` started
+    pub fn parse_for(board_str: &str, first: Player) -> Result<Board, ParseBoardError> {
+        let board = Board::parse_cells(board_str)?;
+        cells_are_valid(&board, first).map_err(ParseBoardError::ImpossibleCells)?;
+        Ok(board)
+    }
+
+    // reads the cells without checking they could come up in a two player game
+    pub fn parse_cells(board_str: &str) -> Result<Board, ParseBoardError> {
+        let num_cells = board_str.chars().count();
+        match (board_str, side_len(num_cells)) {
+            ("", _) => Err(ParseBoardError::Empty),
+            (_, None) => Err(ParseBoardError::BadLen),
+            (_, Some(side)) => {
+                let mut board = Board::new(side, side);
                 let mut char_errs: Vec<(usize, char)> = vec![];
 
                 board_str.chars().enumerate().for_each(|(i, cell_char)| {
                     let cell_maybe = cell_char.to_string().parse::<Cell>();
                     match cell_maybe {
                         Ok(cell) => {
-                            cells[i] = cell;
+                            board.cells[i] = cell;
                         }
                         Err(ParseCellError::BadChar(c)) => char_errs.push((i, c)),
                         _ => panic!("unexpected unknown error"),
                     }
                 });
                 match &char_errs[..] {
-                    [] => match cells_are_valid(&cells) {
-                        Ok(_) => Ok(Board { cells }),
-                        Err(err) => Err(Self::Err::ImpossibleCells(err)),
-                    },
-                    [..] => Err(Self::Err::BadChars(char_errs)),
+                    [] => Ok(board),
+                    [..] => Err(ParseBoardError::BadChars(char_errs)),
                 }
             }
         }
     }
 }
 
+impl FromStr for Board {
+    type Err = ParseBoardError;
+    fn from_str(board_str: &str) -> Result<Self, Self::Err> {
+        Board::parse_for(board_str, Player::X)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -84,11 +151,8 @@ mod test {
         {
             let board = "XXOOODXXO".to_string().parse::<Board>();
             match board {
-                Err(ParseBoardError::BadChars(a)) => match &a[..] {
-                    [(5, 'D')] => assert!(true),
-                    _ => assert!(false),
-                },
-                _ => assert!(false),
+                Err(ParseBoardError::BadChars(a)) => assert!(matches!(&a[..], [(5, 'D')])),
+                _ => panic!("expected BadChars"),
             }
         }
         {
@@ -99,12 +163,22 @@ mod test {
 
     #[test]
     fn test_board_to_from_string() {
-        let good_boards = ["XOXXOXOXO"];
+        let good_boards = ["XOXXOXOXO", "XO  O X         "];
         for expected_board in good_boards {
             let board = expected_board.to_string().parse::<Board>();
-            assert!(matches!(board, Ok(_)));
+            assert!(board.is_ok());
             let result_board = board.unwrap().to_string();
             assert_eq!(expected_board, result_board);
         }
     }
+
+    #[test]
+    fn test_board_size() {
+        let board = "XO  O X         ".to_string().parse::<Board>().unwrap();
+        assert_eq!(board.num_rows, 4);
+        assert_eq!(board.num_cols, 4);
+        assert_eq!(board.num_marked(), 4);
+        assert_eq!(board.unmarked_cell_ids().count(), 12);
+        assert!(!board.is_full());
+    }
 }
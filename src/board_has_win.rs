@@ -1,3 +1,4 @@
+use crate::diagonal_iterator::DiagonalIterator;
 use crate::{board::Board, cell::Cell, cell_iterator::CellIterator, player::Player};
 
 pub fn board_has_win(board: &Board) -> Cell {
@@ -20,6 +21,42 @@ pub fn board_has_win(board: &Board) -> Cell {
     potential_winner
 }
 
+// every line that can be completed. `wrap` joins the edges up like a torus,
+// which adds the diagonals that carry on from the other side
+pub fn tracks(board: &Board, wrap: bool) -> impl Iterator<Item = CellIterator<'_>> {
+    let diagonals = match wrap {
+        true => DiagonalIterator::wrapping(board),
+        false => board.diagonals(),
+    };
+    board.rows().chain(board.columns()).chain(diagonals)
+}
+
+pub fn board_has_win_on_torus(board: &Board) -> Cell {
+    tracks(board, true)
+        .map(iter_has_win)
+        .find(|cell| *cell != Cell::Unmarked)
+        .unwrap_or(Cell::Unmarked)
+}
+
+// the cells of a completed line, so it can be highlighted
+pub fn winning_line(board: &Board) -> Option<Vec<usize>> {
+    find_winning_line(board, false)
+}
+
+pub fn winning_line_on_torus(board: &Board) -> Option<Vec<usize>> {
+    find_winning_line(board, true)
+}
+
+fn find_winning_line(board: &Board, wrap: bool) -> Option<Vec<usize>> {
+    for track in tracks(board, wrap) {
+        let cell_idxs = track.cell_idxs().to_vec();
+        if let Cell::Player(_) = iter_has_win(track) {
+            return Some(cell_idxs);
+        }
+    }
+    None
+}
+
 pub fn iter_has_win(mut iter: CellIterator) -> Cell {
     let first = *iter.next().unwrap();
     for cell in iter {
@@ -54,4 +91,24 @@ mod tests {
         let cell = board_has_win(&board);
         assert!(matches!(cell, Cell::Unmarked));
     }
+
+    #[test]
+    fn test_winning_line() {
+        let board = "XOOX OXX ".to_string().parse::<Board>().unwrap();
+        assert_eq!(winning_line(&board), Some(vec![0, 3, 6]));
+        let board = "OXX O X O".to_string().parse::<Board>().unwrap();
+        assert_eq!(winning_line(&board), Some(vec![0, 4, 8]));
+        let board = "XOXXOXOXO".to_string().parse::<Board>().unwrap();
+        assert_eq!(winning_line(&board), None);
+    }
+
+    #[test]
+    fn test_win_on_torus() {
+        // b1 c2 a3 only lines up once the diagonal wraps around
+        let board = " X OOXX  ".to_string().parse::<Board>().unwrap();
+        assert_eq!(board_has_win(&board), Cell::Unmarked);
+        assert_eq!(board_has_win_on_torus(&board), Cell::Player(Player::X));
+        assert_eq!(winning_line_on_torus(&board), Some(vec![1, 5, 6]));
+        assert_eq!(winning_line(&board), None);
+    }
 }
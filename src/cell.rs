@@ -2,7 +2,7 @@ use crate::player::Player;
 use core::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Cell {
     #[default]
     Unmarked,
@@ -30,11 +30,14 @@ impl FromStr for Cell {
     fn from_str(cell_str: &str) -> Result<Self, Self::Err> {
         match cell_str {
             "" => Err(Self::Err::Empty),
-            c if c.len() >= 2 => Err(Self::Err::BadLen),
-            "X" => Ok(Cell::Player(Player::X)),
-            "O" => Ok(Cell::Player(Player::O)),
+            c if c.chars().count() >= 2 => Err(Self::Err::BadLen),
             " " => Ok(Cell::Unmarked),
-            c => Err(Self::Err::BadChar(c.chars().next().unwrap())),
+            c => {
+                let c = c.chars().next().unwrap();
+                Player::from_symbol(c)
+                    .map(Cell::Player)
+                    .ok_or(Self::Err::BadChar(c))
+            }
         }
     }
 }
@@ -65,7 +68,7 @@ mod tests {
         let good_cells = ["X", "O", " "];
         for expected_cell in good_cells {
             let cell = expected_cell.to_string().parse::<Cell>();
-            assert!(matches!(cell, Ok(_)));
+            assert!(cell.is_ok());
             let result_cell = cell.unwrap().to_string();
             assert_eq!(expected_cell, result_cell);
         }
@@ -3,15 +3,46 @@ use crate::board::{NUM_COLS, NUM_ROWS};
 use core::fmt;
 use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct CellId {
     pub row: usize,
     pub column: usize,
 }
 
 impl CellId {
-    pub fn to_idx(&self) -> usize {
-        self.row * NUM_COLS + self.column
+    pub fn to_idx(self, num_cols: usize) -> usize {
+        self.row * num_cols + self.column
+    }
+
+    pub fn from_idx(idx: usize, num_cols: usize) -> CellId {
+        CellId {
+            row: idx / num_cols,
+            column: idx % num_cols,
+        }
+    }
+
+    // like `parse`, but bounded by a board of the given size instead of the default one.
+    // the column letter may be either case and come before or after the row number
+    pub fn parse_for(input: &str, num_rows: usize, num_cols: usize) -> Result<CellId, CellIdError> {
+        if input.is_empty() {
+            return Err(CellIdError::Empty);
+        }
+        let (column_char, row_str) = match (input.chars().next(), input.chars().last()) {
+            (Some(c), _) if c.is_ascii_alphabetic() => (c, &input[1..]),
+            (_, Some(c)) if c.is_ascii_alphabetic() => (c, &input[..input.len() - 1]),
+            _ => return Err(CellIdError::UnparseableInput),
+        };
+        if row_str.is_empty() || !row_str.chars().all(|c| c.is_ascii_digit()) {
+            return Err(CellIdError::UnparseableInput);
+        }
+        let column = column_char.to_ascii_lowercase() as usize - 'a' as usize;
+        match row_str.parse::<usize>() {
+            Ok(row_num) if row_num >= 1 && row_num <= num_rows && column < num_cols => Ok(CellId {
+                row: row_num - 1,
+                column,
+            }),
+            _ => Err(CellIdError::OutOfBounds),
+        }
     }
 }
 
@@ -24,36 +55,15 @@ pub enum CellIdError {
 
 impl fmt::Display for CellId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}", self.row, self.column)
+        let column_char = (b'a' + self.column as u8) as char;
+        write!(f, "{}{}", column_char, self.row + 1)
     }
 }
 
-const MIN_ROW_CHAR: char = '1';
-const MAX_ROW_CHAR: char = ('1' as u8 + (NUM_ROWS - 1) as u8) as char;
-
-const MIN_COLUMN_CHAR: char = 'a';
-const MAX_COLUMN_CHAR: char = ('a' as u8 + (NUM_COLS - 1) as u8) as char;
-
 impl FromStr for CellId {
     type Err = CellIdError;
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        match input.chars().collect::<Vec<char>>()[..] {
-            [] => Err(Self::Err::Empty),
-            [column_char, row_char] => {
-                if row_char >= MIN_ROW_CHAR
-                    && row_char <= MAX_ROW_CHAR
-                    && column_char >= MIN_COLUMN_CHAR
-                    && column_char <= MAX_COLUMN_CHAR
-                {
-                    let row = row_char as usize - '1' as usize;
-                    let column = column_char as usize - 'a' as usize;
-                    Ok(CellId { row, column })
-                } else {
-                    Err(Self::Err::OutOfBounds)
-                }
-            }
-            _ => Err(Self::Err::UnparseableInput),
-        }
+        CellId::parse_for(input, NUM_ROWS, NUM_COLS)
     }
 }
 
@@ -77,6 +87,36 @@ mod test {
             let result = input.parse::<CellId>();
             assert!(matches!(result, Err(CellIdError::OutOfBounds)));
         }
+
+        let result = CellId::parse_for("e5", 4, 4);
+        assert!(matches!(result, Err(CellIdError::OutOfBounds)));
+    }
+
+    #[test]
+    fn test_either_case_and_order() {
+        for input in ["b3", "B3", "3b", "3B"] {
+            let result: CellId = input.parse().unwrap();
+            assert_eq!(result, CellId { row: 2, column: 1 });
+        }
+        let result = "3b3".parse::<CellId>();
+        assert!(matches!(result, Err(CellIdError::UnparseableInput)));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for idx in 0..(15 * 15) {
+            let cell_id = CellId::from_idx(idx, 15);
+            let result = CellId::parse_for(&cell_id.to_string(), 15, 15).unwrap();
+            assert_eq!(result, cell_id);
+        }
+    }
+
+    #[test]
+    fn test_good_inputs_for_larger_board() {
+        let result = CellId::parse_for("d4", 4, 4).unwrap();
+        assert_eq!(result, CellId { row: 3, column: 3 });
+        let result = CellId::parse_for("b12", 15, 15).unwrap();
+        assert_eq!(result, CellId { row: 11, column: 1 });
     }
 
     #[test]
@@ -101,15 +141,16 @@ mod test {
 
     #[test]
     fn test_to_idx() {
-        let cell_idx = "a1".to_string().parse::<CellId>().unwrap().to_idx();
+        let cell_idx = "a1".to_string().parse::<CellId>().unwrap().to_idx(NUM_COLS);
         assert_eq!(cell_idx, 0);
-        let cell_idx = "a3".to_string().parse::<CellId>().unwrap().to_idx();
+        let cell_idx = "a3".to_string().parse::<CellId>().unwrap().to_idx(NUM_COLS);
         assert_eq!(cell_idx, 6);
-        let cell_idx = "b2".to_string().parse::<CellId>().unwrap().to_idx();
+        let cell_idx = "b2".to_string().parse::<CellId>().unwrap().to_idx(NUM_COLS);
         assert_eq!(cell_idx, 4);
-        let cell_idx = "c1".to_string().parse::<CellId>().unwrap().to_idx();
+        let cell_idx = "c1".to_string().parse::<CellId>().unwrap().to_idx(NUM_COLS);
         assert_eq!(cell_idx, 2);
-        let cell_idx = "c3".to_string().parse::<CellId>().unwrap().to_idx();
+        let cell_idx = "c3".to_string().parse::<CellId>().unwrap().to_idx(NUM_COLS);
         assert_eq!(cell_idx, 8);
+        assert_eq!(CellId::from_idx(8, NUM_COLS), CellId { row: 2, column: 2 });
     }
 }
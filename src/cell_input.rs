@@ -0,0 +1,272 @@
+use crate::cell_id::{CellId, CellIdError};
+
+// which corner the digit 1 is in when cells are picked by a single digit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeypadLayout {
+    // 1 2 3 along the top, like a phone
+    Phone,
+    // 7 8 9 along the top, like a keyboard's number pad
+    Numpad,
+}
+
+// the ways a player may name a cell on top of the usual `b2`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InputScheme {
+    // only used on 3x3 boards, where there is one digit per cell
+    pub keypad: Option<KeypadLayout>,
+    // `row,column` counting from 1, e.g. `2,3`
+    pub row_column_pairs: bool,
+    // e.g. `center` or `top left`
+    pub words: bool,
+}
+
+impl Default for InputScheme {
+    fn default() -> Self {
+        InputScheme {
+            keypad: Some(KeypadLayout::Phone),
+            row_column_pairs: true,
+            words: true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Position {
+    Start,
+    Middle,
+    End,
+}
+
+fn position_idx(position: Position, len: usize) -> Result<usize, CellIdError> {
+    match position {
+        Position::Start => Ok(0),
+        Position::End => Ok(len - 1),
+        Position::Middle if len % 2 == 1 => Ok(len / 2),
+        // an even row or column has no single middle cell
+        Position::Middle => Err(CellIdError::OutOfBounds),
+    }
+}
+
+fn parse_keypad(
+    layout: KeypadLayout,
+    input: &str,
+    num_rows: usize,
+    num_cols: usize,
+) -> Option<Result<CellId, CellIdError>> {
+    let digit = match input.chars().collect::<Vec<char>>()[..] {
+        [c] => c.to_digit(10)? as usize,
+        _ => return None,
+    };
+    if (num_rows, num_cols) != (3, 3) {
+        return None;
+    }
+    if digit == 0 {
+        return Some(Err(CellIdError::OutOfBounds));
+    }
+    let phone_row = (digit - 1) / 3;
+    let row = match layout {
+        KeypadLayout::Phone => phone_row,
+        KeypadLayout::Numpad => 2 - phone_row,
+    };
+    let column = (digit - 1) % 3;
+    Some(Ok(CellId { row, column }))
+}
+
+fn parse_pair(
+    input: &str,
+    num_rows: usize,
+    num_cols: usize,
+) -> Option<Result<CellId, CellIdError>> {
+    let (row_str, column_str) = input.split_once(',')?;
+    let row_num = row_str.trim().parse::<usize>().ok()?;
+    let column_num = column_str.trim().parse::<usize>().ok()?;
+    if (1..=num_rows).contains(&row_num) && (1..=num_cols).contains(&column_num) {
+        Some(Ok(CellId {
+            row: row_num - 1,
+            column: column_num - 1,
+        }))
+    } else {
+        Some(Err(CellIdError::OutOfBounds))
+    }
+}
+
+fn parse_words(
+    input: &str,
+    num_rows: usize,
+    num_cols: usize,
+) -> Option<Result<CellId, CellIdError>> {
+    let input = input.to_lowercase().replace(['-', '_'], " ");
+    let mut vertical: Option<Position> = None;
+    let mut horizontal: Option<Position> = None;
+    let mut either: Vec<Position> = vec![];
+    for word in input.split_whitespace() {
+        match word {
+            "top" | "upper" => vertical = Some(Position::Start),
+            "bottom" | "lower" => vertical = Some(Position::End),
+            "left" => horizontal = Some(Position::Start),
+            "right" => horizontal = Some(Position::End),
+            "center" | "centre" | "middle" => either.push(Position::Middle),
+            _ => return None,
+        }
+    }
+    // `middle` fills in whichever direction was not named
+    for position in either {
+        if vertical.is_none() {
+            vertical = Some(position);
+        } else if horizontal.is_none() {
+            horizontal = Some(position);
+        } else {
+            return None;
+        }
+    }
+    if vertical.is_none() && horizontal.is_none() {
+        return None;
+    }
+    let row = position_idx(vertical.unwrap_or(Position::Middle), num_rows);
+    let column = position_idx(horizontal.unwrap_or(Position::Middle), num_cols);
+    Some(row.and_then(|row| column.map(|column| CellId { row, column })))
+}
+
+impl InputScheme {
+    pub fn parse(
+        &self,
+        input: &str,
+        num_rows: usize,
+        num_cols: usize,
+    ) -> Result<CellId, CellIdError> {
+        let input = input.trim();
+        let alternatives = [
+            self.keypad
+                .and_then(|layout| parse_keypad(layout, input, num_rows, num_cols)),
+            self.row_column_pairs
+                .then(|| parse_pair(input, num_rows, num_cols))
+                .flatten(),
+            self.words
+                .then(|| parse_words(input, num_rows, num_cols))
+                .flatten(),
+        ];
+        match alternatives.into_iter().flatten().next() {
+            Some(result) => result,
+            None => CellId::parse_for(input, num_rows, num_cols),
+        }
+    }
+
+    // how to name a cell on a board of the given size, for the help text
+    pub fn help(&self, num_rows: usize, num_cols: usize) -> String {
+        let last_column = (b'a' + (num_cols - 1) as u8) as char;
+        let mut other_inputs: Vec<&str> = vec!["B2", "2b"];
+        match self.keypad {
+            Some(_) if (num_rows, num_cols) != (3, 3) => {}
+            Some(KeypadLayout::Phone) => other_inputs.push("1-9 with 1 top left"),
+            Some(KeypadLayout::Numpad) => other_inputs.push("1-9 with 7 top left"),
+            None => {}
+        }
+        if self.row_column_pairs {
+            other_inputs.push("2,2 for row 2 column 2");
+        }
+        if self.words {
+            other_inputs.push("center");
+            other_inputs.push("top left");
+        }
+        format!(
+            "Type a cell to play there, using a-{} and 1-{} e.g. b2\nor {}",
+            last_column,
+            num_rows,
+            other_inputs.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn cell(input: &str) -> CellId {
+        input.parse::<CellId>().unwrap()
+    }
+
+    #[test]
+    fn test_keypad_layouts() {
+        let phone = InputScheme::default();
+        assert_eq!(phone.parse("1", 3, 3).unwrap(), cell("a1"));
+        assert_eq!(phone.parse("6", 3, 3).unwrap(), cell("c2"));
+        assert_eq!(phone.parse("9", 3, 3).unwrap(), cell("c3"));
+
+        let numpad = InputScheme {
+            keypad: Some(KeypadLayout::Numpad),
+            ..InputScheme::default()
+        };
+        assert_eq!(numpad.parse("7", 3, 3).unwrap(), cell("a1"));
+        assert_eq!(numpad.parse("5", 3, 3).unwrap(), cell("b2"));
+        assert_eq!(numpad.parse("3", 3, 3).unwrap(), cell("c3"));
+
+        assert!(matches!(
+            phone.parse("0", 3, 3),
+            Err(CellIdError::OutOfBounds)
+        ));
+        assert!(phone.parse("5", 4, 4).is_err());
+        let no_keypad = InputScheme {
+            keypad: None,
+            ..InputScheme::default()
+        };
+        assert!(no_keypad.parse("5", 3, 3).is_err());
+    }
+
+    #[test]
+    fn test_row_column_pairs() {
+        let scheme = InputScheme::default();
+        assert_eq!(scheme.parse("1,3", 3, 3).unwrap(), cell("c1"));
+        assert_eq!(scheme.parse(" 3 , 1 ", 3, 3).unwrap(), cell("a3"));
+        assert!(matches!(
+            scheme.parse("4,1", 3, 3),
+            Err(CellIdError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_words() {
+        let scheme = InputScheme::default();
+        let words = [
+            ("center", "b2"),
+            ("Middle", "b2"),
+            ("top left", "a1"),
+            ("top-right", "c1"),
+            ("top", "b1"),
+            ("left", "a2"),
+            ("bottom middle", "b3"),
+            ("middle right", "c2"),
+            ("lower right", "c3"),
+        ];
+        for (input, expected) in words {
+            assert_eq!(scheme.parse(input, 3, 3).unwrap(), cell(expected));
+        }
+        assert_eq!(
+            scheme.parse("bottom right", 5, 5).unwrap(),
+            CellId { row: 4, column: 4 }
+        );
+        assert!(matches!(
+            scheme.parse("center", 4, 4),
+            Err(CellIdError::OutOfBounds)
+        ));
+        assert!(scheme.parse("top sideways", 3, 3).is_err());
+    }
+
+    #[test]
+    fn test_help_uses_board_size() {
+        let scheme = InputScheme::default();
+        let help = scheme.help(3, 3);
+        assert!(help.starts_with("Type a cell to play there, using a-c and 1-3"));
+        assert!(help.contains("1-9 with 1 top left"));
+        let help = scheme.help(5, 4);
+        assert!(help.starts_with("Type a cell to play there, using a-d and 1-5"));
+        assert!(!help.contains("1-9"));
+    }
+
+    #[test]
+    fn test_usual_notation_still_works() {
+        let scheme = InputScheme::default();
+        assert_eq!(scheme.parse("B2", 3, 3).unwrap(), cell("b2"));
+        assert_eq!(scheme.parse("2b", 3, 3).unwrap(), cell("b2"));
+    }
+}
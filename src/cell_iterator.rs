@@ -1,35 +1,33 @@
-use crate::board::{NUM_CELLS, NUM_COLS as ROW_SIZE};
 use crate::cell::Cell;
 
 pub struct CellIterator<'a> {
     count: usize,
-    cells: &'a [Cell; NUM_CELLS],
-    dimension_index: usize,
-    next_cell: fn(usize, usize) -> usize,
+    cells: &'a [Cell],
+    // the cells of one track (a row, column, diagonal...) in order
+    cell_idxs: Vec<usize>,
 }
 
 impl<'a> CellIterator<'a> {
-    pub fn new(
-        cells: &'a [Cell; NUM_CELLS],
-        dimension_index: usize,
-        next_cell: fn(usize, usize) -> usize,
-    ) -> CellIterator<'a> {
+    pub fn new(cells: &'a [Cell], cell_idxs: Vec<usize>) -> CellIterator<'a> {
         CellIterator {
             count: 0,
             cells,
-            dimension_index,
-            next_cell,
+            cell_idxs,
         }
     }
+
+    pub fn cell_idxs(&self) -> &[usize] {
+        &self.cell_idxs
+    }
 }
 
 impl<'a> Iterator for CellIterator<'a> {
     type Item = &'a Cell;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.count >= ROW_SIZE {
+        if self.count >= self.cell_idxs.len() {
             None
         } else {
-            let idx = (self.next_cell)(self.dimension_index, self.count);
+            let idx = self.cell_idxs[self.count];
             let result = &self.cells[idx];
             self.count += 1;
             Some(result)
@@ -47,14 +45,11 @@ mod tests {
     #[test]
     fn test_board_iter() {
         let board = "XOXOXOXXO".to_string().parse::<Board>().unwrap();
-        fn next_cell_idx(_: usize, count: usize) -> usize {
-            count
-        }
 
-        let mut board = CellIterator::new(&board.cells, 0, next_cell_idx);
+        let mut board = CellIterator::new(&board.cells, vec![0, 1, 2]);
         assert!(matches!(board.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(board.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(board.next().unwrap(), Cell::Player(Player::X)));
-        assert!(matches!(board.next(), None));
+        assert!(board.next().is_none());
     }
 }
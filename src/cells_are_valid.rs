@@ -1,10 +1,10 @@
-use crate::board::NUM_CELLS;
-use crate::board_has_win::iter_has_win;
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::{iter_has_win, tracks};
 use crate::cell::Cell;
-use crate::column_iterator::ColumnIterator;
-use crate::diagonal_iterator::DiagonalIterator;
+use crate::game_update::{next_in_turn, next_player};
 use crate::player::Player;
-use crate::row_iterator::RowIterator;
 
 #[derive(Debug)]
 pub enum CellsImpossibleError {
@@ -12,50 +12,129 @@ pub enum CellsImpossibleError {
     TooManyOs,
     XPlayAfterOWin,
     OPlayAfterXWin,
+    // the same for games with more than two players
+    TooManyMarks(Player),
+    PlayAfterWin(Player),
+    NotPlaying(Player),
+}
+
+impl fmt::Display for CellsImpossibleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellsImpossibleError::TooManyXs => write!(f, "X has too many marks"),
+            CellsImpossibleError::TooManyOs => write!(f, "O has too many marks"),
+            CellsImpossibleError::XPlayAfterOWin => write!(f, "X played after O had won"),
+            CellsImpossibleError::OPlayAfterXWin => write!(f, "O played after X had won"),
+            CellsImpossibleError::TooManyMarks(p) => write!(f, "{} has too many marks", p),
+            CellsImpossibleError::PlayAfterWin(p) => {
+                write!(f, "{} had already won when someone else played", p)
+            }
+            CellsImpossibleError::NotPlaying(p) => write!(f, "{} is not playing", p),
+        }
+    }
 }
 
-fn count_xs_and_os(cells: &[Cell; NUM_CELLS]) -> (usize, usize) {
-    let mut num_xs: usize = 0;
-    let mut num_os: usize = 0;
+// how many marks each of the first `num_players` players has, in turn order
+fn count_marks(cells: &[Cell], num_players: usize) -> Result<Vec<usize>, CellsImpossibleError> {
+    let mut counts = vec![0; num_players];
     for cell in cells {
         if let Cell::Player(p) = cell {
-            match p {
-                Player::X => num_xs += 1,
-                Player::O => num_os += 1,
+            match counts.get_mut(p.idx()) {
+                Some(count) => *count += 1,
+                None => return Err(CellsImpossibleError::NotPlaying(*p)),
             }
         }
     }
-    (num_xs, num_os)
+    Ok(counts)
 }
 
-pub fn cells_are_valid(cells: &[Cell; NUM_CELLS]) -> Result<(), CellsImpossibleError> {
-    let (num_xs, num_os) = count_xs_and_os(cells);
-    if num_xs > num_os + 1 {
-        return Err(CellsImpossibleError::TooManyXs);
-    } else if num_os > num_xs {
-        return Err(CellsImpossibleError::TooManyOs);
+impl CellsImpossibleError {
+    fn too_many(player: Player) -> CellsImpossibleError {
+        match player {
+            Player::X => CellsImpossibleError::TooManyXs,
+            Player::O => CellsImpossibleError::TooManyOs,
+            _ => CellsImpossibleError::TooManyMarks(player),
+        }
+    }
+
+    fn play_after_win(winner: Player) -> CellsImpossibleError {
+        match winner {
+            Player::X => CellsImpossibleError::OPlayAfterXWin,
+            Player::O => CellsImpossibleError::XPlayAfterOWin,
+            _ => CellsImpossibleError::PlayAfterWin(winner),
+        }
     }
-    let mut x_win = false;
-    let mut o_win = false;
-
-    let tracks = RowIterator::new(cells)
-        .chain(ColumnIterator::new(cells))
-        .chain(DiagonalIterator::new(cells));
-    for track in tracks {
-        let cell = iter_has_win(track);
-        if let Cell::Player(Player::X) = cell {
-            x_win = true;
+}
+
+// whether the marks could have been made taking turns round `num_players`
+// players from X, i.e. each player has the same number of marks as the one
+// before them or one fewer. returns who made the last mark, if anyone has
+pub fn mark_counts_are_valid(
+    board: &Board,
+    num_players: usize,
+) -> Result<Option<Player>, CellsImpossibleError> {
+    let counts = count_marks(&board.cells, num_players)?;
+    let mut last = None;
+    for player in Player::all(num_players) {
+        let next = next_in_turn(player, num_players);
+        let (count, next_count) = (counts[player.idx()], counts[next.idx()]);
+        if next == Player::X {
+            if next_count > count + 1 {
+                return Err(CellsImpossibleError::too_many(next));
+            }
+        } else if next_count > count {
+            return Err(CellsImpossibleError::too_many(next));
         }
-        if let Cell::Player(Player::O) = cell {
-            o_win = true;
+        if count > next_count || (next == Player::X && count > 0 && count == next_count) {
+            last = Some(player);
         }
     }
+    Ok(last)
+}
+
+// whether the cells could come up in a game that `first` started
+pub fn cells_are_valid(board: &Board, first: Player) -> Result<(), CellsImpossibleError> {
+    check_cells(board, false, first)
+}
+
+// the same checks with lines that wrap around the edges
+pub fn cells_are_valid_on_torus(board: &Board, first: Player) -> Result<(), CellsImpossibleError> {
+    check_cells(board, true, first)
+}
+
+fn check_cells(board: &Board, wrap: bool, first: Player) -> Result<(), CellsImpossibleError> {
+    let winners: Vec<Player> = tracks(board, wrap)
+        .filter_map(|track| match iter_has_win(track) {
+            Cell::Player(p) => Some(p),
+            Cell::Unmarked => None,
+        })
+        .collect();
+    marks_are_valid(board, first, &winners)
+}
+
+// the checks for any way of winning, `winners` are the players who have
+// completed one
+pub fn marks_are_valid(
+    board: &Board,
+    first: Player,
+    winners: &[Player],
+) -> Result<(), CellsImpossibleError> {
+    let second = next_player(first);
+    let counts = count_marks(&board.cells, 2)?;
+    let (num_firsts, num_seconds) = (counts[first.idx()], counts[second.idx()]);
+    if num_firsts > num_seconds + 1 {
+        return Err(CellsImpossibleError::too_many(first));
+    } else if num_seconds > num_firsts {
+        return Err(CellsImpossibleError::too_many(second));
+    }
+    let first_win = winners.contains(&first);
+    let second_win = winners.contains(&second);
 
-    if x_win && num_os == num_xs {
-        return Err(CellsImpossibleError::OPlayAfterXWin);
+    if first_win && num_seconds == num_firsts {
+        return Err(CellsImpossibleError::play_after_win(first));
     }
-    if o_win && num_xs == num_os + 1 {
-        return Err(CellsImpossibleError::XPlayAfterOWin);
+    if second_win && num_firsts == num_seconds + 1 {
+        return Err(CellsImpossibleError::play_after_win(second));
     }
     Ok(())
 }
@@ -65,15 +144,16 @@ mod tests {
 
     use super::*;
 
-    fn make_cells(cells_str: &str) -> [Cell; NUM_CELLS] {
-        assert_eq!(cells_str.len(), NUM_CELLS);
-        let mut cells = [Cell::Unmarked; NUM_CELLS];
+    // bypasses `Board::from_str`, which would itself reject impossible cells
+    fn make_cells(cells_str: &str) -> Board {
+        let mut cells = Board::default();
+        assert_eq!(cells_str.len(), cells.num_cells());
         cells_str
             .chars()
             .enumerate()
             .for_each(
                 |(cell_idx, cell_char)| match cell_char.to_string().parse::<Cell>() {
-                    Ok(cell) => cells[cell_idx] = cell,
+                    Ok(cell) => cells.cells[cell_idx] = cell,
                     _ => panic!("oops"),
                 },
             );
@@ -83,17 +163,17 @@ mod tests {
     #[test]
     fn test_cells_are_possible() {
         let cells = make_cells("         ");
-        assert!(matches!(cells_are_valid(&cells), Ok(_)));
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
         let cells = make_cells("X        ");
-        assert!(matches!(cells_are_valid(&cells), Ok(_)));
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
         let cells = make_cells("O     X  ");
-        assert!(matches!(cells_are_valid(&cells), Ok(_)));
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
         let cells = make_cells("XOXXOXOXO");
-        assert!(matches!(cells_are_valid(&cells), Ok(_)));
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
         let cells = make_cells(" OX X XO ");
-        assert!(matches!(cells_are_valid(&cells), Ok(_)));
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
         let cells = make_cells("OXOXOXOX ");
-        assert!(matches!(cells_are_valid(&cells), Ok(_)));
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
     }
 
     #[test]
@@ -105,7 +185,7 @@ mod tests {
         ];
         for cells in too_many_xs {
             assert!(matches!(
-                cells_are_valid(&cells),
+                cells_are_valid(&cells, Player::X),
                 Err(CellsImpossibleError::TooManyXs)
             ));
         }
@@ -117,7 +197,7 @@ mod tests {
         ];
         for cells in too_many_os {
             assert!(matches!(
-                cells_are_valid(&cells),
+                cells_are_valid(&cells, Player::X),
                 Err(CellsImpossibleError::TooManyOs)
             ));
         }
@@ -130,7 +210,7 @@ mod tests {
         ];
         for cells in o_play_after_x_win {
             assert!(matches!(
-                cells_are_valid(&cells),
+                cells_are_valid(&cells, Player::X),
                 Err(CellsImpossibleError::OPlayAfterXWin)
             ));
         }
@@ -143,9 +223,69 @@ mod tests {
         ];
         for cells in x_play_after_o_win {
             assert!(matches!(
-                cells_are_valid(&cells),
+                cells_are_valid(&cells, Player::X),
                 Err(CellsImpossibleError::XPlayAfterOWin)
             ));
         }
     }
+
+    #[test]
+    fn test_cells_on_torus() {
+        // X's wrapped diagonal b1 c2 a3 ended the game before O's last move
+        let cells = make_cells(" X OOXXO ");
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
+        assert!(matches!(
+            cells_are_valid_on_torus(&cells, Player::X),
+            Err(CellsImpossibleError::OPlayAfterXWin)
+        ));
+    }
+
+    #[test]
+    fn test_mark_counts() {
+        let board = Board::parse_cells("XOΔX").unwrap();
+        assert_eq!(mark_counts_are_valid(&board, 3).unwrap(), Some(Player::X));
+        let board = Board::parse_cells("XOΔXOΔ   ").unwrap();
+        assert_eq!(
+            mark_counts_are_valid(&board, 3).unwrap(),
+            Some(Player::nth(2))
+        );
+        assert_eq!(mark_counts_are_valid(&Board::new(3, 3), 3).unwrap(), None);
+        let board = Board::parse_cells("XOO ").unwrap();
+        assert!(matches!(
+            mark_counts_are_valid(&board, 3),
+            Err(CellsImpossibleError::TooManyOs)
+        ));
+        let board = Board::parse_cells("XXX ").unwrap();
+        assert!(matches!(
+            mark_counts_are_valid(&board, 3),
+            Err(CellsImpossibleError::TooManyXs)
+        ));
+        let board = Board::parse_cells("XOΔ□").unwrap();
+        assert!(matches!(
+            mark_counts_are_valid(&board, 3),
+            Err(CellsImpossibleError::NotPlaying(_))
+        ));
+    }
+
+    #[test]
+    fn test_o_moved_first() {
+        let cells = make_cells("O        ");
+        assert!(cells_are_valid(&cells, Player::O).is_ok());
+        let cells = make_cells("X        ");
+        assert!(matches!(
+            cells_are_valid(&cells, Player::O),
+            Err(CellsImpossibleError::TooManyXs)
+        ));
+        let cells = make_cells("OO XX    ");
+        assert!(cells_are_valid(&cells, Player::O).is_ok());
+        // with as many Xs as Os, O moved last if X started, but X did if O started
+        let cells = make_cells("OOOXX X  ");
+        assert!(cells_are_valid(&cells, Player::X).is_ok());
+        assert!(matches!(
+            cells_are_valid(&cells, Player::O),
+            Err(CellsImpossibleError::XPlayAfterOWin)
+        ));
+        let cells = make_cells("OOOXX    ");
+        assert!(cells_are_valid(&cells, Player::O).is_ok());
+    }
 }
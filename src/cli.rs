@@ -0,0 +1,1782 @@
+use core::fmt;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::board::{Board, ParseBoardError, MAX_SIZE, NUM_ROWS};
+use crate::cell_id::CellId;
+use crate::cell_input::{InputScheme, KeypadLayout};
+use crate::enumerate_boards::enumerate_boards;
+use crate::game::Game;
+use crate::game_record::{GameRecord, ParseRecordError};
+use crate::gomoku::{Gomoku, Overline, Viewport, GOMOKU_SIZE};
+use crate::gravity::{Gravity, DEFAULT_COLUMNS, DEFAULT_CONNECT, DEFAULT_ROWS};
+use crate::hypergraph::{Hypergraph, Positional, Preset};
+use crate::maker_breaker::{MakerBreaker, DEFAULT_BIAS};
+use crate::morris::{Adjacency, Morris};
+use crate::multiplayer::{Multiplayer, DEFAULT_NUM_PLAYERS};
+use crate::notakto::{Notakto, DEFAULT_NUM_BOARDS};
+use crate::numerical::Numerical;
+use crate::order_chaos::OrderChaos;
+use crate::patterns::{ParsePatternsError, WinPatterns};
+use crate::phantom::Phantom;
+use crate::player::{Player, MAX_PLAYERS};
+use crate::position::Position;
+use crate::quantum::Quantum;
+use crate::qubic::Qubic;
+use crate::rules_board::RulesBoard;
+use crate::ruleset::{Outcome, Ruleset};
+use crate::theme::{AnsiColor, Borders, ColorChoice, Theme};
+use crate::ultimate::Ultimate;
+use crate::vanishing::Vanishing;
+use crate::variant::Variant;
+use crate::wild::Wild;
+
+pub const USAGE: &str = "\
+usage: tictactoe [command] [flags]
+
+commands:
+  play                 play a game (the default)
+  solve <board>        find the best move, e.g. solve \"X   O    \"
+  analyze <record>     grade every move of a game, e.g. analyze \"b2 a1 c3\"
+  validate <board>     check that a board could happen in a real game
+  enumerate            count every reachable board
+  replay <file>        show every position of a saved game
+  help                 show this message
+
+flags:
+  --size <n>           board size (default 3)
+  --rules <mode>       the game to play, one of these with the commands and
+                       flags it takes:
+    classic        every command; --size --first --pie --patterns
+    misere         every command, a line loses; --size --first --pie
+    toroidal       every command, lines wrap; --size --first --pie
+    ultimate       play, validate \"B2/a1 A1/c3\"
+    notakto        play, validate; --boards
+    qubic          play, validate
+    gomoku         play, validate; --overline --viewport
+    gravity        play, validate; --grid --connect
+    wild           play, validate, solve \"X b2, O a1\"; --size
+    order-chaos    play, validate \"X b2, O a1\"
+    numerical      play, validate, solve \"5 b2, 4 a1\"; --size
+    quantum        play, validate \"a1 b2, b2 a1, collapse a1\"
+    morris         play, validate, solve \"b2 a1 c3 a3 a2 c2 c3-b3\"; --adjacency
+    vanishing      play, validate, solve \"b2 a1 c3\"
+    multiplayer    play, validate; --size --players --connect --computer
+    positional     play, validate, solve; --hypergraph --size --patterns
+    maker-breaker  play, validate, solve; --hypergraph --size --bias --patterns
+    phantom        play, validate \"b2 b2 a1\"; --size
+                       the quoted moves are examples of what validate and solve
+                       take, multiplayer, positional and maker-breaker take a
+                       board instead, e.g. validate \"XOOXX  \"
+  --boards <n>         how many boards notakto is played on (default 3)
+  --overline <win|no-win>
+                       whether six or more in a row wins at gomoku (default win)
+  --viewport <columns>x<rows>
+                       how many gomoku cells to show at once (default as many as
+                       fit the terminal)
+  --grid <columns>x<rows>
+                       the gravity board (default 7x6)
+  --connect <n>        how many in a row win at gravity and multiplayer (default 4,
+                       or the board size if smaller)
+  --adjacency <orthogonal|diagonal>
+                       where morris marks can move to (default orthogonal)
+  --hypergraph <board|affine|fano>
+                       the points and edges of positional and maker-breaker:
+                       the lines of a
+                       --size board, the affine plane AG(2,3) or the Fano plane
+                       (default board)
+  --bias <n>           how many points O claims a turn at maker-breaker (default 1)
+  --players <n>        how many take turns at multiplayer (default 3, at most 4)
+  --computer <players> who the computer plays at multiplayer, by symbol or by
+                       turn, e.g. --computer 2,3 for O and Δ
+  --first <x|o>        who moves first (default x)
+  --pie <on|off>       let the second player swap sides after the first move
+                       (default off)
+  --patterns <file>    win by the cell patterns in a rule file instead of by
+                       lines, one per line e.g. \"pattern a1 c1 a3 c3\" for the
+                       corners, \"shape a1 b1 a2 b2\" for any 2x2 square or
+                       \"lines\" for the usual rows, columns and diagonals.
+                       with positional and maker-breaker they are the edges
+  --x <human|computer> who plays X (default human)
+  --o <human|computer> who plays O (default human)
+  --format <text|json> output format (default text)
+  --ui <text|tui>      how to show the game (tui needs the tui feature)
+  --color <auto|always|never>
+                       colour the board (default auto, only on a terminal),
+                       without it the winning line is shown [X] and the last
+                       move (X)
+  --borders <ascii|unicode>
+                       how to draw the grid (default ascii)
+  --glyphs <x>,<o>     what to draw for each player, e.g. --glyphs ❌,⭕
+  --colors <x>,<o>     colour per player, e.g. --colors red,blue
+  --keypad <phone|numpad|off>
+                       how single digits pick cells on 3x3 boards (default phone)
+";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlayerKind {
+    Human,
+    Computer,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ui {
+    Text,
+    Tui,
+}
+
+// games played on something other than a single board
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Board,
+    Ultimate,
+    Notakto,
+    Qubic,
+    Gomoku,
+    Gravity,
+    Wild,
+    OrderChaos,
+    Numerical,
+    Quantum,
+    Morris,
+    Vanishing,
+    Multiplayer,
+    Positional,
+    MakerBreaker,
+    Phantom,
+}
+
+// the name each mode is picked by with --rules. the board variants have
+// names of their own, and classic is the one picked without the flag
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mode::Board => "classic",
+            Mode::Ultimate => "ultimate",
+            Mode::Notakto => "notakto",
+            Mode::Qubic => "qubic",
+            Mode::Gomoku => "gomoku",
+            Mode::Gravity => "gravity",
+            Mode::Wild => "wild",
+            Mode::OrderChaos => "order-chaos",
+            Mode::Numerical => "numerical",
+            Mode::Quantum => "quantum",
+            Mode::Morris => "morris",
+            Mode::Vanishing => "vanishing",
+            Mode::Multiplayer => "multiplayer",
+            Mode::Positional => "positional",
+            Mode::MakerBreaker => "maker-breaker",
+            Mode::Phantom => "phantom",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Options {
+    pub size: usize,
+    pub mode: Mode,
+    pub variant: Variant,
+    // how many boards multi-board modes start with
+    pub num_boards: usize,
+    pub overline: Overline,
+    // how much of the gomoku board to show, None to fit the terminal
+    pub viewport: Option<Viewport>,
+    // columns and rows of the gravity board
+    pub grid: (usize, usize),
+    pub connect: usize,
+    pub adjacency: Adjacency,
+    pub hypergraph: Preset,
+    pub bias: usize,
+    pub first: Player,
+    pub pie_rule: bool,
+    // the ways to win from a --patterns rule file, for classic games or as
+    // the edges of positional and maker-breaker games
+    pub patterns: Option<WinPatterns>,
+    pub num_players: usize,
+    pub x: PlayerKind,
+    pub o: PlayerKind,
+    // any other players the computer plays, for multiplayer
+    pub computers: Vec<Player>,
+    pub format: OutputFormat,
+    pub ui: Ui,
+    pub input_scheme: InputScheme,
+    pub color: ColorChoice,
+    pub theme: Theme,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            size: NUM_ROWS,
+            mode: Mode::Board,
+            variant: Variant::Classic,
+            num_boards: DEFAULT_NUM_BOARDS,
+            overline: Overline::default(),
+            viewport: None,
+            grid: (DEFAULT_COLUMNS, DEFAULT_ROWS),
+            connect: DEFAULT_CONNECT,
+            adjacency: Adjacency::default(),
+            hypergraph: Preset::default(),
+            bias: DEFAULT_BIAS,
+            first: Player::X,
+            pie_rule: false,
+            patterns: None,
+            num_players: DEFAULT_NUM_PLAYERS,
+            x: PlayerKind::Human,
+            o: PlayerKind::Human,
+            computers: vec![],
+            format: OutputFormat::Text,
+            ui: Ui::Text,
+            input_scheme: InputScheme::default(),
+            color: ColorChoice::Auto,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Options {
+    pub fn ruleset(&self) -> Box<dyn Ruleset> {
+        match &self.patterns {
+            Some(patterns) => patterns.ruleset_for(self.size, self.first),
+            None => self.variant.ruleset_for(self.first),
+        }
+    }
+
+    // the points and edges of positional and maker-breaker games
+    pub fn hypergraph(&self) -> Hypergraph {
+        match &self.patterns {
+            Some(patterns) => {
+                Hypergraph::from_patterns(patterns, self.size).expect("patterns fit the board")
+            }
+            None => self.hypergraph.hypergraph(self.size),
+        }
+    }
+
+    // the theme with colour switched on or off for where the output is going
+    pub fn theme(&self) -> Theme {
+        Theme {
+            color: self.color.enabled(),
+            ..self.theme.clone()
+        }
+    }
+
+    pub fn computer_players(&self) -> Vec<Player> {
+        let mut players = vec![];
+        if self.x == PlayerKind::Computer {
+            players.push(Player::X);
+        }
+        if self.o == PlayerKind::Computer {
+            players.push(Player::O);
+        }
+        players.extend(&self.computers);
+        players
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Play,
+    Solve(String),
+    Analyze(String),
+    Validate(String),
+    Enumerate,
+    Replay(String),
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    UnknownCommand(String),
+    UnknownFlag(String),
+    MissingValue(String),
+    BadValue(String, String),
+    MissingArgument(&'static str),
+    UnexpectedArgument(String),
+    BadBoard(ParseBoardError),
+    BadRecord(ParseRecordError),
+    BadPatterns(ParsePatternsError),
+    // a list of moves that cannot be played, and why
+    BadMoves(String),
+    // the command cannot be used with the chosen mode
+    Unsupported(&'static str, Mode),
+    // the board has too many cells to solve to the end
+    TooLarge,
+    Io(io::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::UnknownCommand(c) => write!(f, "unknown command {:?}", c),
+            CliError::UnknownFlag(flag) => write!(f, "unknown flag {}", flag),
+            CliError::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            CliError::BadValue(flag, value) => write!(f, "bad value {:?} for {}", value, flag),
+            CliError::MissingArgument(name) => write!(f, "missing <{}>", name),
+            CliError::UnexpectedArgument(arg) => write!(f, "unexpected argument {:?}", arg),
+            CliError::BadBoard(ParseBoardError::ImpossibleCells(reason)) => {
+                write!(f, "impossible board: {:?}", reason)
+            }
+            CliError::BadBoard(ParseBoardError::BadChars(chars)) => {
+                let chars: Vec<String> = chars
+                    .iter()
+                    .map(|(i, c)| format!("{:?} at {}", c, i))
+                    .collect();
+                write!(f, "bad board: unexpected {}", chars.join(", "))
+            }
+            CliError::BadBoard(err) => write!(f, "bad board: {:?}", err),
+            CliError::BadRecord(ParseRecordError::BadSize(size)) => {
+                write!(f, "bad record: bad size {:?}", size)
+            }
+            CliError::BadRecord(ParseRecordError::BadRules(rules)) => {
+                write!(f, "bad record: unknown rules {:?}", rules)
+            }
+            CliError::BadRecord(ParseRecordError::BadFirst(first)) => {
+                write!(f, "bad record: unknown first player {:?}", first)
+            }
+            CliError::BadRecord(ParseRecordError::BadPie(pie)) => {
+                write!(f, "bad record: pie should be on or off, not {:?}", pie)
+            }
+            CliError::BadRecord(ParseRecordError::BadPatterns(err)) => {
+                write!(f, "bad record: {}", err)
+            }
+            CliError::BadRecord(ParseRecordError::RulesWithPatterns) => {
+                write!(f, "bad record: has both rules and patterns to win by")
+            }
+            CliError::BadRecord(ParseRecordError::UnknownHeader(header)) => {
+                write!(f, "bad record: unknown header {:?}", header)
+            }
+            CliError::BadRecord(ParseRecordError::BadMove(m)) => {
+                write!(f, "bad record: cannot read move {:?}", m)
+            }
+            CliError::BadRecord(ParseRecordError::IllegalMove(cell_id)) => {
+                write!(f, "bad record: {} is not a legal move", cell_id)
+            }
+            CliError::BadRecord(ParseRecordError::IllegalSwap) => {
+                write!(
+                    f,
+                    "bad record: swap only comes straight after the first move, with the pie rule"
+                )
+            }
+            CliError::BadPatterns(err) => write!(f, "bad patterns: {}", err),
+            CliError::BadMoves(reason) => write!(f, "bad moves: {}", reason),
+            CliError::Unsupported(command, mode) => {
+                write!(f, "{} does not work with {} rules", command, mode)
+            }
+            CliError::TooLarge => write!(f, "board too large to solve"),
+            CliError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(err: io::Error) -> Self {
+        CliError::Io(err)
+    }
+}
+
+fn parse_player_kind(flag: &str, value: &str) -> Result<PlayerKind, CliError> {
+    match value {
+        "human" => Ok(PlayerKind::Human),
+        "computer" => Ok(PlayerKind::Computer),
+        _ => Err(CliError::BadValue(flag.to_string(), value.to_string())),
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<(Command, Options), CliError> {
+    let mut options = Options::default();
+    let mut positionals: Vec<&str> = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positionals.push(arg);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| CliError::MissingValue(arg.to_string()))?;
+        let bad_value = || CliError::BadValue(arg.to_string(), value.to_string());
+        match arg.as_str() {
+            "--size" => {
+                options.size = match value.parse::<usize>() {
+                    Ok(size) if (1..=MAX_SIZE).contains(&size) => size,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--rules" => match value.as_str() {
+                "ultimate" => options.mode = Mode::Ultimate,
+                "notakto" => options.mode = Mode::Notakto,
+                "qubic" => options.mode = Mode::Qubic,
+                "gomoku" => options.mode = Mode::Gomoku,
+                "gravity" => options.mode = Mode::Gravity,
+                "wild" => options.mode = Mode::Wild,
+                "order-chaos" => options.mode = Mode::OrderChaos,
+                "numerical" => options.mode = Mode::Numerical,
+                "quantum" => options.mode = Mode::Quantum,
+                "morris" => options.mode = Mode::Morris,
+                "vanishing" => options.mode = Mode::Vanishing,
+                "multiplayer" => options.mode = Mode::Multiplayer,
+                "positional" => options.mode = Mode::Positional,
+                "maker-breaker" => options.mode = Mode::MakerBreaker,
+                "phantom" => options.mode = Mode::Phantom,
+                _ => {
+                    options.mode = Mode::Board;
+                    options.variant = value.parse().map_err(|_| bad_value())?
+                }
+            },
+            "--boards" => {
+                options.num_boards = match value.parse::<usize>() {
+                    Ok(num_boards) if num_boards >= 1 => num_boards,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--overline" => {
+                options.overline = match value.as_str() {
+                    "win" => Overline::Wins,
+                    "no-win" => Overline::DoesNotWin,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--viewport" => {
+                let cells = |n: &str| n.parse::<usize>().ok().filter(|n| *n >= 1);
+                let viewport = value
+                    .split_once('x')
+                    .and_then(|(cols, rows)| {
+                        Some(Viewport {
+                            rows: cells(rows)?,
+                            cols: cells(cols)?,
+                        })
+                    })
+                    .ok_or_else(bad_value)?;
+                options.viewport = Some(viewport);
+            }
+            "--grid" => {
+                let size = |n: &str| {
+                    n.parse::<usize>()
+                        .ok()
+                        .filter(|n| (1..=MAX_SIZE).contains(n))
+                };
+                options.grid = value
+                    .split_once('x')
+                    .and_then(|(columns, rows)| Some((size(columns)?, size(rows)?)))
+                    .ok_or_else(bad_value)?
+            }
+            "--connect" => {
+                options.connect = match value.parse::<usize>() {
+                    Ok(connect) if connect >= 1 => connect,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--adjacency" => {
+                options.adjacency = match value.as_str() {
+                    "orthogonal" => Adjacency::Orthogonal,
+                    "diagonal" => Adjacency::Diagonal,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--hypergraph" => {
+                options.hypergraph = match value.as_str() {
+                    "board" => Preset::Board,
+                    "affine" => Preset::Affine,
+                    "fano" => Preset::Fano,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--bias" => {
+                options.bias = match value.parse::<usize>() {
+                    Ok(bias) if bias >= 1 => bias,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--first" => {
+                options.first = match value.parse() {
+                    Ok(p @ (Player::X | Player::O)) => p,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--pie" => {
+                options.pie_rule = match value.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--patterns" => {
+                let patterns = std::fs::read_to_string(value)?
+                    .parse()
+                    .map_err(CliError::BadPatterns)?;
+                options.patterns = Some(patterns);
+            }
+            "--x" => options.x = parse_player_kind(arg, value)?,
+            "--o" => options.o = parse_player_kind(arg, value)?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--ui" => {
+                options.ui = match value.as_str() {
+                    "text" => Ui::Text,
+                    "tui" if cfg!(feature = "tui") => Ui::Tui,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--color" => {
+                options.color = match value.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--borders" => {
+                options.theme.borders = match value.as_str() {
+                    "ascii" => Borders::Ascii,
+                    "unicode" => Borders::Unicode,
+                    _ => return Err(bad_value()),
+                }
+            }
+            // one per player in turn order, any players left out keep theirs
+            "--glyphs" => match value.split(',').collect::<Vec<&str>>()[..] {
+                [x, o, ref others @ ..]
+                    if others.len() <= MAX_PLAYERS - 2
+                        && !x.is_empty()
+                        && !o.is_empty()
+                        && others.iter().all(|glyph| !glyph.is_empty()) =>
+                {
+                    options.theme.x_glyph = x.to_string();
+                    options.theme.o_glyph = o.to_string();
+                    for (idx, glyph) in others.iter().enumerate() {
+                        options.theme.other_glyphs[idx] = glyph.to_string();
+                    }
+                }
+                _ => return Err(bad_value()),
+            },
+            "--colors" => {
+                let colors: Option<Vec<AnsiColor>> =
+                    value.split(',').map(|color| color.parse().ok()).collect();
+                match colors.as_deref() {
+                    Some([x, o, others @ ..]) if others.len() <= MAX_PLAYERS - 2 => {
+                        options.theme.x_color = *x;
+                        options.theme.o_color = *o;
+                        for (idx, color) in others.iter().enumerate() {
+                            options.theme.other_colors[idx] = *color;
+                        }
+                    }
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--players" => {
+                options.num_players = match value.parse::<usize>() {
+                    Ok(num_players) if (2..=MAX_PLAYERS).contains(&num_players) => num_players,
+                    _ => return Err(bad_value()),
+                }
+            }
+            "--computer" => {
+                options.computers = value
+                    .split(',')
+                    .map(|player| player.parse().ok())
+                    .collect::<Option<Vec<Player>>>()
+                    .ok_or_else(bad_value)?
+            }
+            "--keypad" => {
+                options.input_scheme.keypad = match value.as_str() {
+                    "phone" => Some(KeypadLayout::Phone),
+                    "numpad" => Some(KeypadLayout::Numpad),
+                    "off" => None,
+                    _ => return Err(bad_value()),
+                }
+            }
+            _ => return Err(CliError::UnknownFlag(arg.to_string())),
+        }
+    }
+
+    // patterns replace the lines of the classic game, or the edges of a
+    // hypergraph, on whatever size board
+    if let Some(patterns) = &options.patterns {
+        let takes_patterns = match options.mode {
+            Mode::Board => true,
+            // in place of a ready made hypergraph, not as well as one
+            Mode::Positional | Mode::MakerBreaker => options.hypergraph == Preset::Board,
+            _ => false,
+        };
+        if !takes_patterns {
+            return Err(CliError::Unsupported("--patterns", options.mode));
+        }
+        if options.variant != Variant::Classic {
+            return Err(CliError::BadValue(
+                "--rules".to_string(),
+                options.variant.to_string(),
+            ));
+        }
+        patterns
+            .cell_sets(options.size, options.size)
+            .map_err(CliError::BadPatterns)?;
+    }
+
+    let mut positionals = positionals.into_iter();
+    let command_name = positionals.next().unwrap_or("play");
+    let mut argument = |name: &'static str| {
+        positionals
+            .next()
+            .map(|a| a.to_string())
+            .ok_or(CliError::MissingArgument(name))
+    };
+    let command = match command_name {
+        "help" => Command::Help,
+        "play" => Command::Play,
+        "solve" => Command::Solve(argument("board")?),
+        "analyze" => Command::Analyze(argument("record")?),
+        "validate" => Command::Validate(argument("board")?),
+        "enumerate" => Command::Enumerate,
+        "replay" => Command::Replay(argument("file")?),
+        _ => return Err(CliError::UnknownCommand(command_name.to_string())),
+    };
+    if let Some(extra) = positionals.next() {
+        return Err(CliError::UnexpectedArgument(extra.to_string()));
+    }
+    Ok((command, options))
+}
+
+fn json_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// how good an outcome is for the given player, higher is better
+fn outcome_rank(outcome: Outcome, player: Player) -> usize {
+    match outcome {
+        Outcome::Win(p) if p == player => 2,
+        Outcome::Draw => 1,
+        Outcome::Win(_) => 0,
+    }
+}
+
+fn parse_record(record_str: &str, options: &Options) -> Result<GameRecord, CliError> {
+    // a bare list of moves is played on the board size and rules given by the flags
+    let has_header = |header: &str| record_str.lines().any(|l| l.starts_with(header));
+    let mut full_record_str = if has_header("size") {
+        record_str.to_string()
+    } else {
+        format!("size {}\nmoves {}", options.size, record_str)
+    };
+    if !has_header("first") {
+        full_record_str = format!(
+            "first {}\n{}",
+            options.first.to_string().to_lowercase(),
+            full_record_str
+        );
+    }
+    if !has_header("pie") && options.pie_rule {
+        full_record_str = format!("pie on\n{}", full_record_str);
+    }
+    // the moves are checked under the variant's rules or the patterns as they
+    // are read, so whichever the record leaves out comes from the flags. a
+    // record has one or the other, never both
+    let has_patterns = ["lines", "pattern", "shape"].iter().any(|h| has_header(h));
+    if !has_header("rules") && !has_patterns {
+        full_record_str = match &options.patterns {
+            Some(patterns) => format!("{}{}", patterns, full_record_str),
+            None => format!("rules {}\n{}", options.variant, full_record_str),
+        };
+    }
+    full_record_str
+        .parse::<GameRecord>()
+        .map_err(CliError::BadRecord)
+}
+
+// the result with best play and a move that gets it, for boards small enough to solve
+fn solve_board(
+    board: &Board,
+    rules: &Rc<dyn Ruleset>,
+) -> Result<(Outcome, Option<CellId>), CliError> {
+    RulesBoard::new(board.clone(), rules.clone())
+        .solve()
+        .ok_or(CliError::TooLarge)
+}
+
+fn solve<W: Write>(board_str: &str, options: &Options, out: &mut W) -> Result<(), CliError> {
+    let rules: Rc<dyn Ruleset> = options.ruleset().into();
+    // the rules rather than the board decide which boards are possible, as
+    // custom patterns change who has won
+    let board = Board::parse_cells(board_str)
+        .and_then(|board| {
+            rules
+                .validate(&board)
+                .map(|_| board)
+                .map_err(ParseBoardError::ImpossibleCells)
+        })
+        .map_err(CliError::BadBoard)?;
+    let (outcome, best_move) = solve_board(&board, &rules)?;
+    let best_move = best_move.map(|m| m.to_string());
+    match options.format {
+        OutputFormat::Text => {
+            if let Some(best_move) = best_move {
+                writeln!(out, "best move: {}", best_move)?;
+            }
+            writeln!(out, "outcome: {}", outcome)?;
+        }
+        OutputFormat::Json => writeln!(
+            out,
+            "{{\"board\": {}, \"best_move\": {}, \"outcome\": {}}}",
+            json_string(board_str),
+            best_move.map_or("null".to_string(), |m| json_string(&m)),
+            json_string(&outcome.to_string())
+        )?,
+    }
+    Ok(())
+}
+
+fn analyze<W: Write>(record_str: &str, options: &Options, out: &mut W) -> Result<(), CliError> {
+    let record = parse_record(record_str, options)?;
+    let rules: Rc<dyn Ruleset> = record.ruleset().into();
+    let boards = record.boards();
+    let mut json_moves: Vec<String> = vec![];
+    for (move_idx, cell_id) in record.moves.iter().enumerate() {
+        let player = rules.player_to_move(&boards[move_idx]);
+        let (before, _) = solve_board(&boards[move_idx], &rules)?;
+        let (after, _) = solve_board(&boards[move_idx + 1], &rules)?;
+        let mistake = outcome_rank(after, player) < outcome_rank(before, player);
+        match options.format {
+            OutputFormat::Text => writeln!(
+                out,
+                "{}. {} {} {}{}",
+                move_idx + 1,
+                player,
+                cell_id,
+                after,
+                if mistake { " (mistake)" } else { "" }
+            )?,
+            OutputFormat::Json => json_moves.push(format!(
+                "{{\"player\": {}, \"move\": {}, \"outcome\": {}, \"mistake\": {}}}",
+                json_string(&player.to_string()),
+                json_string(&cell_id.to_string()),
+                json_string(&after.to_string()),
+                mistake
+            )),
+        }
+    }
+    if options.format == OutputFormat::Json {
+        writeln!(out, "[{}]", json_moves.join(", "))?;
+    }
+    Ok(())
+}
+
+fn validate<W: Write>(board_str: &str, options: &Options, out: &mut W) -> Result<(), CliError> {
+    let rules = options.ruleset();
+    let result = Board::parse_cells(board_str).and_then(|board| {
+        rules
+            .validate(&board)
+            .map(|_| rules.outcome(&board))
+            .map_err(ParseBoardError::ImpossibleCells)
+    });
+    match (options.format, result) {
+        (OutputFormat::Text, Ok(None)) => writeln!(out, "valid")?,
+        (OutputFormat::Text, Ok(Some(outcome))) => writeln!(out, "valid, {}", outcome)?,
+        (OutputFormat::Text, Err(err)) => writeln!(out, "invalid: {:?}", err)?,
+        (OutputFormat::Json, Ok(outcome)) => writeln!(
+            out,
+            "{{\"valid\": true, \"outcome\": {}}}",
+            outcome.map_or("null".to_string(), |o| json_string(&o.to_string()))
+        )?,
+        (OutputFormat::Json, Err(err)) => writeln!(
+            out,
+            "{{\"valid\": false, \"error\": {}}}",
+            json_string(&format!("{:?}", err))
+        )?,
+    }
+    Ok(())
+}
+
+// multiplayer boards are checked for marks the players could have made in turn
+fn validate_multiplayer<W: Write>(
+    board_str: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), CliError> {
+    let result = Board::parse_cells(board_str)
+        .map_err(|err| format!("{:?}", err))
+        .and_then(|board| {
+            Multiplayer::with_board(board, options.num_players, options.connect)
+                .map_err(|err| err.to_string())
+        });
+    write_validity(
+        result.map(|multiplayer| multiplayer.outcome()),
+        options,
+        out,
+    )
+}
+
+// positional boards are checked against the edges of the hypergraph
+fn validate_positional<W: Write>(
+    board_str: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), CliError> {
+    let graph = options.hypergraph();
+    let result = graph
+        .parse_marks(board_str)
+        .map_err(|err| format!("{:?}", err))
+        .and_then(|board| Positional::with_board(graph, board).map_err(|err| err.to_string()));
+    write_validity(result.map(|positional| positional.outcome()), options, out)
+}
+
+// maker-breaker boards are checked for the points Breaker claims each turn
+fn validate_maker_breaker<W: Write>(
+    board_str: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), CliError> {
+    let graph = options.hypergraph();
+    let result = graph
+        .parse_marks(board_str)
+        .map_err(|err| format!("{:?}", err))
+        .and_then(|board| {
+            MakerBreaker::with_board(graph, board, options.bias).map_err(|err| err.to_string())
+        });
+    write_validity(
+        result.map(|maker_breaker| maker_breaker.outcome()),
+        options,
+        out,
+    )
+}
+
+fn enumerate<W: Write>(options: &Options, out: &mut W) -> Result<(), CliError> {
+    let counts = enumerate_boards(options.size, &*options.ruleset());
+    let fields = [
+        ("total", counts.total),
+        ("x_wins", counts.x_wins),
+        ("o_wins", counts.o_wins),
+        ("draws", counts.draws),
+        ("in_progress", counts.in_progress),
+    ];
+    match options.format {
+        OutputFormat::Text => {
+            for (name, count) in fields {
+                writeln!(out, "{}: {}", name, count)?;
+            }
+        }
+        OutputFormat::Json => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, count)| format!("{}: {}", json_string(name), count))
+                .collect();
+            writeln!(out, "{{{}}}", fields.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+fn replay<W: Write>(path: &str, options: &Options, out: &mut W) -> Result<(), CliError> {
+    let record = parse_record(&std::fs::read_to_string(path)?, options)?;
+    match options.format {
+        OutputFormat::Text => {
+            let board = Board::new(record.size, record.size);
+            let mut game = Game::new(RulesBoard {
+                theme: options.theme(),
+                ..RulesBoard::new(board, record.ruleset().into())
+            });
+            write!(out, "{}", game)?;
+            for (move_idx, cell_id) in record.moves.iter().enumerate() {
+                writeln!(out, "{}. {}", move_idx + 1, cell_id)?;
+                game.update(*cell_id);
+                write!(out, "{}", game)?;
+                if move_idx == 0 && record.swapped {
+                    writeln!(out, "sides swapped")?;
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let boards: Vec<String> = record
+                .boards()
+                .iter()
+                .map(|b| json_string(&b.to_string()))
+                .collect();
+            writeln!(out, "[{}]", boards.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+// moves are split on commas when there are any e.g. "X b2, O a1", otherwise on
+// spaces, keeping a mark or number with the cell after it e.g. "X b2 O a1"
+fn split_moves(moves_str: &str) -> Vec<String> {
+    if moves_str.contains(',') {
+        return moves_str
+            .split(',')
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect();
+    }
+    let is_prefix = |word: &str| {
+        word.eq_ignore_ascii_case("x")
+            || word.eq_ignore_ascii_case("o")
+            || word.chars().all(|c| c.is_ascii_digit())
+    };
+    let mut moves: Vec<String> = vec![];
+    let mut words = moves_str.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        match words.next_if(|_| is_prefix(word)) {
+            Some(cell) => moves.push(format!("{} {}", word, cell)),
+            None => moves.push(word.to_string()),
+        }
+    }
+    moves
+}
+
+// replays a list of moves from `start`, checking each one
+fn play_moves<P: Position>(start: P, moves_str: &str) -> Result<P, String> {
+    let mut position = start;
+    for (move_idx, move_str) in split_moves(moves_str).into_iter().enumerate() {
+        let checked = position
+            .parse_move(&move_str)
+            .and_then(|mv| position.check_move(&mv).map(|_| mv));
+        match checked {
+            Ok(mv) => position.play(mv),
+            Err(err) => return Err(format!("move {} {}: {}", move_idx + 1, move_str, err)),
+        }
+    }
+    Ok(position)
+}
+
+fn validate_moves<P: Position, W: Write>(
+    start: P,
+    moves_str: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), CliError> {
+    let result = play_moves(start, moves_str).map(|position| position.outcome());
+    write_validity(result, options, out)
+}
+
+// whether a position could come up, and how the game stands if so
+fn write_validity<W: Write>(
+    result: Result<Option<Outcome>, String>,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), CliError> {
+    match (options.format, result) {
+        (OutputFormat::Text, Ok(None)) => writeln!(out, "valid")?,
+        (OutputFormat::Text, Ok(Some(outcome))) => writeln!(out, "valid, {}", outcome)?,
+        (OutputFormat::Text, Err(err)) => writeln!(out, "invalid: {}", err)?,
+        (OutputFormat::Json, Ok(outcome)) => writeln!(
+            out,
+            "{{\"valid\": true, \"outcome\": {}}}",
+            outcome.map_or("null".to_string(), |o| json_string(&o.to_string()))
+        )?,
+        (OutputFormat::Json, Err(err)) => writeln!(
+            out,
+            "{{\"valid\": false, \"error\": {}}}",
+            json_string(&err)
+        )?,
+    }
+    Ok(())
+}
+
+fn solve_moves<P: Position, W: Write>(
+    start: P,
+    moves_str: &str,
+    options: &Options,
+    out: &mut W,
+) -> Result<(), CliError> {
+    let position = play_moves(start, moves_str).map_err(CliError::BadMoves)?;
+    let (outcome, best_move) = position.solve().ok_or(match position.solvable() {
+        true => CliError::TooLarge,
+        false => CliError::Unsupported("solve", options.mode),
+    })?;
+    let best_move = best_move.map(|m| m.to_string());
+    match options.format {
+        OutputFormat::Text => {
+            if let Some(best_move) = best_move {
+                writeln!(out, "best move: {}", best_move)?;
+            }
+            writeln!(out, "outcome: {}", outcome)?;
+        }
+        OutputFormat::Json => writeln!(
+            out,
+            "{{\"moves\": {}, \"best_move\": {}, \"outcome\": {}}}",
+            json_string(moves_str),
+            best_move.map_or("null".to_string(), |m| json_string(&m)),
+            json_string(&outcome.to_string())
+        )?,
+    }
+    Ok(())
+}
+
+// a game from `start` with the players and pie rule from the flags
+fn new_game<P: Position>(start: P, options: &Options) -> Game<P> {
+    let mut game = Game::new(start);
+    game.computer_players = options.computer_players();
+    game.pie_rule = options.pie_rule;
+    game
+}
+
+// the commands for every mode, each played on a `Position` starting from `start`
+fn run_position<P: Position, Input: BufRead, W: Write>(
+    start: P,
+    command: Command,
+    options: &Options,
+    input: &mut Input,
+    out: &mut W,
+) -> Result<(), CliError> {
+    match command {
+        // only boards are drawn in the terminal ui, which comes in before here
+        Command::Play if options.ui == Ui::Tui => {
+            return Err(CliError::Unsupported("--ui tui", options.mode))
+        }
+        Command::Play => new_game(start, options).run(input, out)?,
+        Command::Validate(moves_str) => validate_moves(start, &moves_str, options, out)?,
+        Command::Help => write!(out, "{}", USAGE)?,
+        Command::Solve(moves_str) => solve_moves(start, &moves_str, options, out)?,
+        Command::Analyze(_) => return Err(CliError::Unsupported("analyze", options.mode)),
+        Command::Enumerate => return Err(CliError::Unsupported("enumerate", options.mode)),
+        Command::Replay(_) => return Err(CliError::Unsupported("replay", options.mode)),
+    }
+    Ok(())
+}
+
+pub fn run_command<Input: BufRead, W: Write>(
+    command: Command,
+    options: Options,
+    input: &mut Input,
+    out: &mut W,
+) -> Result<(), CliError> {
+    match (options.mode, command) {
+        (_, Command::Help) => write!(out, "{}", USAGE)?,
+        (Mode::Ultimate, command) => {
+            run_position(Ultimate::default(), command, &options, input, out)?
+        }
+        (Mode::Notakto, command) => {
+            let notakto = Notakto::new(options.num_boards);
+            run_position(notakto, command, &options, input, out)?
+        }
+        (Mode::Qubic, command) => run_position(Qubic::default(), command, &options, input, out)?,
+        (Mode::Gomoku, command) => {
+            let gomoku = Gomoku {
+                viewport: options.viewport.or_else(Viewport::from_terminal),
+                ..Gomoku::new(GOMOKU_SIZE, options.overline)
+            };
+            run_position(gomoku, command, &options, input, out)?
+        }
+        (Mode::Gravity, command) => {
+            let (columns, rows) = options.grid;
+            let gravity = Gravity::new(columns, rows, options.connect);
+            run_position(gravity, command, &options, input, out)?
+        }
+        (Mode::Wild, command) => {
+            let wild = Wild {
+                theme: options.theme(),
+                ..Wild::new(options.size)
+            };
+            run_position(wild, command, &options, input, out)?
+        }
+        (Mode::OrderChaos, command) => {
+            let order_chaos = OrderChaos {
+                theme: options.theme(),
+                ..OrderChaos::default()
+            };
+            run_position(order_chaos, command, &options, input, out)?
+        }
+        (Mode::Numerical, command) => {
+            let numerical = Numerical::new(options.size);
+            run_position(numerical, command, &options, input, out)?
+        }
+        (Mode::Quantum, command) => {
+            run_position(Quantum::default(), command, &options, input, out)?
+        }
+        (Mode::Morris, command) => {
+            let morris = Morris {
+                theme: options.theme(),
+                ..Morris::new(options.adjacency)
+            };
+            run_position(morris, command, &options, input, out)?
+        }
+        (Mode::Vanishing, command) => {
+            let vanishing = Vanishing {
+                theme: options.theme(),
+                ..Vanishing::default()
+            };
+            run_position(vanishing, command, &options, input, out)?
+        }
+        (Mode::Multiplayer, Command::Validate(board_str)) => {
+            validate_multiplayer(&board_str, &options, out)?
+        }
+        (Mode::Multiplayer, command) => {
+            let multiplayer = Multiplayer {
+                theme: options.theme(),
+                ..Multiplayer::new(options.size, options.num_players, options.connect)
+            };
+            run_position(multiplayer, command, &options, input, out)?
+        }
+        (Mode::Positional, Command::Validate(board_str)) => {
+            validate_positional(&board_str, &options, out)?
+        }
+        (Mode::Positional, command) => {
+            let positional = Positional {
+                theme: options.theme(),
+                ..Positional::new(options.hypergraph())
+            };
+            run_position(positional, command, &options, input, out)?
+        }
+        (Mode::MakerBreaker, Command::Validate(board_str)) => {
+            validate_maker_breaker(&board_str, &options, out)?
+        }
+        (Mode::MakerBreaker, command) => {
+            let maker_breaker = MakerBreaker {
+                theme: options.theme(),
+                ..MakerBreaker::new(options.hypergraph(), options.bias)
+            };
+            run_position(maker_breaker, command, &options, input, out)?
+        }
+        (Mode::Phantom, command) => {
+            // the boards are the referee's, so only the theme is set from outside
+            let mut phantom = Phantom::new(options.size);
+            phantom.theme = options.theme();
+            run_position(phantom, command, &options, input, out)?
+        }
+        (Mode::Board, Command::Play) => {
+            let board = Board::new(options.size, options.size);
+            let position = RulesBoard {
+                input_scheme: options.input_scheme,
+                theme: options.theme(),
+                ..RulesBoard::new(board, options.ruleset().into())
+            };
+            match options.ui {
+                Ui::Text => run_position(position, Command::Play, &options, input, out)?,
+                #[cfg(feature = "tui")]
+                Ui::Tui => crate::tui::run_tui(&mut new_game(position, &options))?,
+                #[cfg(not(feature = "tui"))]
+                Ui::Tui => unreachable!("--ui tui is rejected without the tui feature"),
+            }
+        }
+        (Mode::Board, Command::Solve(board_str)) => solve(&board_str, &options, out)?,
+        (Mode::Board, Command::Analyze(record_str)) => analyze(&record_str, &options, out)?,
+        (Mode::Board, Command::Validate(board_str)) => validate(&board_str, &options, out)?,
+        (Mode::Board, Command::Enumerate) => enumerate(&options, out)?,
+        (Mode::Board, Command::Replay(path)) => replay(&path, &options, out)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    fn run(command: Command, options: Options) -> String {
+        let mut out: Vec<u8> = vec![];
+        run_command(command, options, &mut "".as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let (command, options) = parse_args(&args("")).unwrap();
+        assert_eq!(command, Command::Play);
+        assert_eq!(options, Options::default());
+
+        let (command, options) =
+            parse_args(&args("enumerate --size 2 --x computer --format json")).unwrap();
+        assert_eq!(command, Command::Enumerate);
+        assert_eq!(options.size, 2);
+        assert_eq!(options.x, PlayerKind::Computer);
+        assert_eq!(options.o, PlayerKind::Human);
+        assert_eq!(options.format, OutputFormat::Json);
+
+        let (_, options) = parse_args(&args("--rules misere")).unwrap();
+        assert_eq!(options.variant, Variant::Misere);
+        let (_, options) = parse_args(&args("--rules ultimate")).unwrap();
+        assert_eq!(options.mode, Mode::Ultimate);
+
+        let (command, _) = parse_args(&args("replay game.txt")).unwrap();
+        assert_eq!(command, Command::Replay("game.txt".to_string()));
+
+        let (_, options) =
+            parse_args(&args("--glyphs ❌,⭕ --colors green,cyan --color never")).unwrap();
+        assert_eq!(options.theme.x_glyph, "❌");
+        assert_eq!(options.theme.o_color, AnsiColor::Cyan);
+        assert!(!options.theme().color);
+        let result = parse_args(&args("--colors green"));
+        assert!(matches!(result, Err(CliError::BadValue(_, _))));
+
+        let (_, options) = parse_args(&args("--keypad numpad")).unwrap();
+        assert_eq!(options.input_scheme.keypad, Some(KeypadLayout::Numpad));
+    }
+
+    #[test]
+    fn test_bad_args() {
+        let result = parse_args(&args("frobnicate"));
+        assert!(matches!(result, Err(CliError::UnknownCommand(_))));
+        let result = parse_args(&args("play --colour red"));
+        assert!(matches!(result, Err(CliError::UnknownFlag(_))));
+        let result = parse_args(&args("play --size"));
+        assert!(matches!(result, Err(CliError::MissingValue(_))));
+        let result = parse_args(&args("play --size 99"));
+        assert!(matches!(result, Err(CliError::BadValue(_, _))));
+        let result = parse_args(&args("solve"));
+        assert!(matches!(result, Err(CliError::MissingArgument("board"))));
+        let result = parse_args(&args("enumerate now"));
+        assert!(matches!(result, Err(CliError::UnexpectedArgument(_))));
+    }
+
+    #[test]
+    fn test_solve() {
+        let output = run(Command::Solve("XX OO    ".to_string()), Options::default());
+        assert_eq!(output, "best move: c1\noutcome: X wins\n");
+
+        let options = Options {
+            format: OutputFormat::Json,
+            ..Options::default()
+        };
+        let output = run(Command::Solve("XXXOO    ".to_string()), options);
+        assert_eq!(
+            output,
+            "{\"board\": \"XXXOO    \", \"best_move\": null, \"outcome\": \"X wins\"}\n"
+        );
+
+        let (_, options) = parse_args(&args("--size 5")).unwrap();
+        let board = " ".repeat(25);
+        let result = run_command(
+            Command::Solve(board),
+            options,
+            &mut "".as_bytes(),
+            &mut vec![],
+        );
+        assert!(matches!(result, Err(CliError::TooLarge)));
+    }
+
+    #[test]
+    fn test_play_misere() {
+        // X finishes the top row, which loses under misère rules
+        let (command, options) = parse_args(&args("--rules misere play")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let input = "a1\na2\nb1\nb2\nc1\n";
+        run_command(command, options, &mut input.as_bytes(), &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Winner! O"));
+        assert!(!output.contains("Winner! X"));
+    }
+
+    #[test]
+    fn test_analyze() {
+        let output = run(Command::Analyze("b2 b1".to_string()), Options::default());
+        assert_eq!(output, "1. X b2 draw\n2. O b1 X wins (mistake)\n");
+
+        let options = Options {
+            variant: Variant::Misere,
+            ..Options::default()
+        };
+        let output = run(Command::Analyze("a1 a2 b1 b2 c1".to_string()), options);
+        assert!(output.ends_with("5. X c1 O wins (mistake)\n"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let output = run(
+            Command::Validate("XXOO     ".to_string()),
+            Options::default(),
+        );
+        assert_eq!(output, "valid\n");
+        let output = run(
+            Command::Validate("XX       ".to_string()),
+            Options::default(),
+        );
+        assert_eq!(output, "invalid: ImpossibleCells(TooManyXs)\n");
+    }
+
+    #[test]
+    fn test_validate_ultimate() {
+        let options = || Options {
+            mode: Mode::Ultimate,
+            ..Options::default()
+        };
+        let output = run(Command::Validate("B2/a1 A1/c3".to_string()), options());
+        assert_eq!(output, "valid\n");
+        let output = run(Command::Validate("B2/a1 B2/c3".to_string()), options());
+        assert_eq!(
+            output,
+            "invalid: move 2 B2/c3: You have to play on board A1\n"
+        );
+        let mut out: Vec<u8> = vec![];
+        let result = run_command(Command::Enumerate, options(), &mut "".as_bytes(), &mut out);
+        assert!(matches!(result, Err(CliError::Unsupported("enumerate", _))));
+    }
+
+    #[test]
+    fn test_validate_notakto() {
+        let (_, options) = parse_args(&args("--rules notakto --boards 1")).unwrap();
+        assert_eq!(options.mode, Mode::Notakto);
+        // the third mark kills the only board, so the player who made it loses
+        let output = run(Command::Validate("a1 b1 c1".to_string()), options);
+        assert_eq!(output, "valid, O wins\n");
+        let (_, options) = parse_args(&args("--rules notakto --boards 2")).unwrap();
+        let output = run(
+            Command::Validate("1/a1 1/b1 1/c1 1/a2".to_string()),
+            options,
+        );
+        assert_eq!(output, "invalid: move 4 1/a2: Board 1 is dead\n");
+    }
+
+    #[test]
+    fn test_validate_qubic() {
+        let (_, options) = parse_args(&args("--rules qubic")).unwrap();
+        assert_eq!(options.mode, Mode::Qubic);
+        let moves = "1/a1 1/a2 2/b2 1/a3 3/c3 1/b3 4/d4";
+        let output = run(Command::Validate(moves.to_string()), options);
+        assert_eq!(output, "valid, X wins\n");
+    }
+
+    #[test]
+    fn test_validate_gomoku() {
+        let moves = "a1 a15 b1 b15 c1 c15 d1 o15 f1 o14 e1";
+        let (_, options) = parse_args(&args("--rules gomoku")).unwrap();
+        assert_eq!(options.mode, Mode::Gomoku);
+        let output = run(Command::Validate(moves.to_string()), options);
+        assert_eq!(output, "valid, X wins\n");
+        let (_, options) = parse_args(&args("--rules gomoku --overline no-win")).unwrap();
+        let output = run(Command::Validate(moves.to_string()), options);
+        assert_eq!(output, "valid\n");
+        let (_, options) = parse_args(&args("--rules gomoku --viewport 9x5")).unwrap();
+        assert_eq!(options.viewport, Some(Viewport { rows: 5, cols: 9 }));
+        assert!(parse_args(&args("--viewport 0x5")).is_err());
+    }
+
+    #[test]
+    fn test_validate_gravity() {
+        let (_, options) = parse_args(&args("--rules gravity --grid 3x2 --connect 2")).unwrap();
+        assert_eq!(options.grid, (3, 2));
+        let output = run(Command::Validate("b b b".to_string()), options);
+        assert_eq!(output, "invalid: move 3 b: Column b is full\n");
+        let (_, options) = parse_args(&args("--rules gravity --connect 2")).unwrap();
+        let output = run(Command::Validate("a b a".to_string()), options);
+        assert_eq!(output, "valid, X wins\n");
+        assert!(parse_args(&args("--grid 7")).is_err());
+        assert!(parse_args(&args("--grid 0x6")).is_err());
+    }
+
+    #[test]
+    fn test_wild() {
+        let (_, options) = parse_args(&args("--rules wild")).unwrap();
+        assert_eq!(options.mode, Mode::Wild);
+        let output = run(
+            Command::Validate("X a1, O c3, X b1, X c1".to_string()),
+            options,
+        );
+        assert_eq!(output, "valid, O wins\n");
+
+        let (_, options) = parse_args(&args("--rules wild")).unwrap();
+        let output = run(Command::Solve("Oa1 Xc3 Ob1".to_string()), options);
+        assert_eq!(output, "best move: O c1\noutcome: O wins\n");
+        // a single move with a space in it, and moves without commas
+        let (_, options) = parse_args(&args("--rules wild")).unwrap();
+        let output = run(Command::Validate("X a1".to_string()), options);
+        assert_eq!(output, "valid\n");
+        let (_, options) = parse_args(&args("--rules wild")).unwrap();
+        let output = run(
+            Command::Validate("X a1 O c3 X b1 X c1".to_string()),
+            options,
+        );
+        assert_eq!(output, "valid, O wins\n");
+
+        let (_, options) = parse_args(&args("--rules wild")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let result = run_command(
+            Command::Solve("b2".to_string()),
+            options,
+            &mut "".as_bytes(),
+            &mut out,
+        );
+        assert!(matches!(result, Err(CliError::BadMoves(_))));
+
+        let (_, options) = parse_args(&args("--rules wild --size 4")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let result = run_command(
+            Command::Solve("".to_string()),
+            options,
+            &mut "".as_bytes(),
+            &mut out,
+        );
+        assert!(matches!(result, Err(CliError::TooLarge)));
+    }
+
+    #[test]
+    fn test_play_wild_with_glyphs() {
+        let (command, options) = parse_args(&args("--rules wild --glyphs ❌,⭕ play")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        run_command(command, options, &mut "O b2\n".as_bytes(), &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("⭕"));
+    }
+
+    #[test]
+    fn test_validate_order_chaos() {
+        let (_, options) = parse_args(&args("--rules order-chaos")).unwrap();
+        assert_eq!(options.mode, Mode::OrderChaos);
+        let moves = "O a1, O b2, O c3, X f1, O d4, X a6, O e5";
+        let output = run(Command::Validate(moves.to_string()), options);
+        assert_eq!(output, "valid, X wins\n");
+        let (_, options) = parse_args(&args("--rules order-chaos")).unwrap();
+        let output = run(Command::Validate("O a1".to_string()), options);
+        assert_eq!(output, "valid\n");
+    }
+
+    #[test]
+    fn test_play_order_chaos_with_glyphs() {
+        let flags = "--rules order-chaos --glyphs ❌,⭕ play";
+        let (command, options) = parse_args(&args(flags)).unwrap();
+        let mut out: Vec<u8> = vec![];
+        run_command(command, options, &mut "O c3\n".as_bytes(), &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("⭕"));
+    }
+
+    #[test]
+    fn test_toroidal() {
+        let (_, options) = parse_args(&args("--rules torus")).unwrap();
+        assert_eq!(options.variant, Variant::Toroidal);
+        let output = run(Command::Validate(" X OOXXO ".to_string()), options);
+        assert!(output.starts_with("invalid"));
+        let (_, options) = parse_args(&args("--rules toroidal")).unwrap();
+        let output = run(Command::Solve(" X OOX   ".to_string()), options);
+        assert_eq!(output, "best move: a3\noutcome: X wins\n");
+        // X has won on the wrapped diagonal b1 c2 a3 before O's last move
+        let (_, options) = parse_args(&args("--rules toroidal")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let command = Command::Analyze("b1 a1 c2 a2 a3 b3".to_string());
+        let result = run_command(command, options, &mut "".as_bytes(), &mut out);
+        assert!(matches!(result, Err(CliError::BadRecord(_))));
+    }
+
+    #[test]
+    fn test_first_player_and_pie() {
+        let (_, options) = parse_args(&args("--first o --pie on")).unwrap();
+        assert_eq!(options.first, Player::O);
+        assert!(options.pie_rule);
+        let output = run(Command::Validate("O        ".to_string()), options);
+        assert_eq!(output, "valid\n");
+        let (_, options) = parse_args(&args("--first o")).unwrap();
+        let output = run(Command::Validate("X        ".to_string()), options);
+        assert_eq!(output, "invalid: ImpossibleCells(TooManyXs)\n");
+
+        let (_, options) = parse_args(&args("--first o --pie on")).unwrap();
+        let output = run(Command::Analyze("b2 swap b1".to_string()), options);
+        assert_eq!(output, "1. O b2 draw\n2. X b1 O wins (mistake)\n");
+        let mut out: Vec<u8> = vec![];
+        let command = Command::Analyze("b2 swap b1".to_string());
+        let result = run_command(command, Options::default(), &mut "".as_bytes(), &mut out);
+        assert!(matches!(
+            result,
+            Err(CliError::BadRecord(ParseRecordError::IllegalSwap))
+        ));
+        assert!(parse_args(&args("--first z")).is_err());
+    }
+
+    #[test]
+    fn test_numerical() {
+        let (_, options) = parse_args(&args("--rules numerical")).unwrap();
+        assert_eq!(options.mode, Mode::Numerical);
+        let output = run(
+            Command::Validate("1 a1, 6 b1, 3 c3, 8 c1".to_string()),
+            options,
+        );
+        assert_eq!(output, "valid, O wins\n");
+        let (_, options) = parse_args(&args("--rules numerical")).unwrap();
+        let output = run(Command::Validate("1 a1 3 b1".to_string()), options);
+        assert_eq!(
+            output,
+            "invalid: move 2 3 b1: 3 is not one of O's numbers\n"
+        );
+        let (_, options) = parse_args(&args("--rules numerical")).unwrap();
+        let output = run(Command::Solve("9 a1, 2 a2, 1 c1".to_string()), options);
+        assert_eq!(output, "best move: 4 a3\noutcome: O wins\n");
+
+        // 16x16 boards have numbers past 255
+        let (_, options) = parse_args(&args("--rules numerical --size 16")).unwrap();
+        let output = run(Command::Validate("1 a1, 256 b1".to_string()), options);
+        assert_eq!(output, "valid\n");
+    }
+
+    #[test]
+    fn test_quantum() {
+        let (_, options) = parse_args(&args("--rules quantum")).unwrap();
+        assert_eq!(options.mode, Mode::Quantum);
+        let moves = "a1 b2, b2 c3, c3 a1, collapse a1, a2 b3";
+        assert_eq!(
+            run(Command::Validate(moves.to_string()), options),
+            "valid\n"
+        );
+        let (_, options) = parse_args(&args("--rules quantum")).unwrap();
+        let output = run(
+            Command::Validate("a1 b2, b2 a1, a3 c3".to_string()),
+            options,
+        );
+        assert_eq!(
+            output,
+            "invalid: move 3 a3 c3: The last move closed a loop, so choose where it collapses first\n"
+        );
+        let (_, options) = parse_args(&args("--rules quantum")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let result = run_command(
+            Command::Solve(String::new()),
+            options,
+            &mut "".as_bytes(),
+            &mut out,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "solve does not work with quantum rules"
+        );
+    }
+
+    #[test]
+    fn test_morris() {
+        let options = || parse_args(&args("--rules morris")).unwrap().1;
+        assert_eq!(options().mode, Mode::Morris);
+        let moves = "a1 b1 c1 a2 b3 c2 b3-b2 a2-a3 b2-b3 a3-a2 b3-b2 a2-a3 b2-b3 a3-a2";
+        let output = run(Command::Validate(moves.to_string()), options());
+        assert_eq!(output, "valid, draw\n");
+        let output = run(
+            Command::Validate("a1 b1 c1 a2 b3 c2 a1-b2".to_string()),
+            options(),
+        );
+        assert_eq!(output, "invalid: move 7 a1-b2: b2 is not next to a1\n");
+        let output = run(Command::Solve("a1 a2 b1 b2 c2 b3".to_string()), options());
+        assert_eq!(output, "best move: c2-c1\noutcome: X wins\n");
+
+        let (_, options) = parse_args(&args("--rules morris --adjacency diagonal")).unwrap();
+        assert_eq!(options.adjacency, Adjacency::Diagonal);
+        let output = run(
+            Command::Validate("a1 b1 c1 a2 b3 c2 a1-b2".to_string()),
+            options,
+        );
+        assert_eq!(output, "valid\n");
+    }
+
+    #[test]
+    fn test_play_morris_with_glyphs() {
+        let (command, options) = parse_args(&args("--rules morris --glyphs ❌,⭕ play")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        run_command(command, options, &mut "b2\n".as_bytes(), &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("❌"));
+    }
+
+    #[test]
+    fn test_play_vanishing_with_glyphs() {
+        let flags = "--rules vanishing --glyphs ❌,⭕ play";
+        let (command, options) = parse_args(&args(flags)).unwrap();
+        let mut out: Vec<u8> = vec![];
+        run_command(command, options, &mut "b2\n".as_bytes(), &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("❌"));
+    }
+
+    #[test]
+    fn test_vanishing() {
+        let options = || parse_args(&args("--rules vanishing")).unwrap().1;
+        assert_eq!(options().mode, Mode::Vanishing);
+        // a2 would have finished column a, but a1 has gone
+        let output = run(
+            Command::Validate("a1 b2 c1 b1 a3 c2 a2".to_string()),
+            options(),
+        );
+        assert_eq!(output, "valid\n");
+        let output = run(Command::Solve("a1 b2 c3 a3".to_string()), options());
+        assert_eq!(output, "best move: c1\noutcome: X wins\n");
+    }
+
+    #[test]
+    fn test_multiplayer() {
+        let options = || {
+            let flags = "--rules multiplayer --size 4 --connect 3 --computer 2,Δ --glyphs X,O,#";
+            parse_args(&args(flags)).unwrap().1
+        };
+        assert_eq!(options().mode, Mode::Multiplayer);
+        assert_eq!(options().num_players, 3);
+        assert_eq!(options().computer_players(), [Player::O, Player::nth(2)]);
+        assert_eq!(options().theme.other_glyphs[0], "#");
+        let output = run(Command::Validate("XOΔ X           ".to_string()), options());
+        assert_eq!(output, "valid\n");
+        let output = run(Command::Validate("XOΔΔ            ".to_string()), options());
+        assert_eq!(output, "invalid: Δ has too many marks\n");
+        let output = run(Command::Validate("XXXOOΔΔO        ".to_string()), options());
+        assert_eq!(
+            output,
+            "invalid: X had already won when someone else played\n"
+        );
+        assert!(parse_args(&args("--players 5")).is_err());
+        assert!(parse_args(&args("--first 3")).is_err());
+        assert!(parse_args(&args("--glyphs X,O,Δ,□,◇")).is_err());
+    }
+
+    #[test]
+    fn test_positional() {
+        let options = |flags: &str| parse_args(&args(flags)).unwrap().1;
+        let fano = "--rules positional --hypergraph fano";
+        assert_eq!(options(fano).mode, Mode::Positional);
+        assert_eq!(options(fano).hypergraph, Preset::Fano);
+        let output = run(Command::Validate("XOOXX  ".to_string()), options(fano));
+        assert_eq!(output, "valid, X wins\n");
+        let output = run(Command::Validate("XOOXXO ".to_string()), options(fano));
+        assert_eq!(output, "invalid: O played after X had won\n");
+        let affine = "--rules positional --hypergraph affine";
+        let output = run(Command::Solve("".to_string()), options(affine));
+        assert!(output.ends_with("outcome: X wins\n"));
+        let output = run(
+            Command::Solve("b2 b1".to_string()),
+            options("--rules positional"),
+        );
+        assert!(output.ends_with("outcome: X wins\n"));
+        assert!(parse_args(&args("--hypergraph cube")).is_err());
+
+        // a rule file gives the edges, here every 2x2 square
+        let squares = || Options {
+            mode: Mode::Positional,
+            patterns: "shape a1 b1 a2 b2".parse().ok(),
+            ..Options::default()
+        };
+        let output = run(Command::Validate("XXOXXOO  ".to_string()), squares());
+        assert_eq!(output, "valid, X wins\n");
+        let output = run(Command::Solve("".to_string()), squares());
+        assert!(output.ends_with("outcome: draw\n"));
+    }
+
+    #[test]
+    fn test_maker_breaker() {
+        let options = |flags: &str| parse_args(&args(flags)).unwrap().1;
+        let flags = "--rules maker-breaker --bias 2";
+        assert_eq!(options(flags).mode, Mode::MakerBreaker);
+        assert_eq!(options(flags).bias, 2);
+        let output = run(Command::Validate("OX  X O  ".to_string()), options(flags));
+        assert_eq!(output, "valid\n");
+        let output = run(Command::Validate("OXO XO O ".to_string()), options(flags));
+        assert_eq!(output, "valid, O wins\n");
+        let output = run(Command::Validate("OXOOXOO  ".to_string()), options(flags));
+        assert_eq!(output, "invalid: O has too many marks\n");
+        // Maker wins 3x3 unless Breaker claims two points a turn
+        let output = run(
+            Command::Solve("".to_string()),
+            options("--rules maker-breaker"),
+        );
+        assert!(output.ends_with("outcome: X wins\n"));
+        let output = run(Command::Solve("".to_string()), options(flags));
+        assert_eq!(output, "best move: a1\noutcome: O wins\n");
+        let mut out: Vec<u8> = vec![];
+        let result = run_command(
+            Command::Solve("".to_string()),
+            options("--rules maker-breaker --size 5"),
+            &mut "".as_bytes(),
+            &mut out,
+        );
+        assert_eq!(result.unwrap_err().to_string(), "board too large to solve");
+        assert!(parse_args(&args("--bias 0")).is_err());
+    }
+
+    #[test]
+    fn test_phantom() {
+        let options = || parse_args(&args("--rules phantom")).unwrap().1;
+        assert_eq!(options().mode, Mode::Phantom);
+        // O runs into X's mark and plays again
+        let output = run(Command::Validate("b2 b2 a1 a2".to_string()), options());
+        assert_eq!(output, "valid\n");
+        let output = run(Command::Validate("b2 b2 b2".to_string()), options());
+        assert_eq!(
+            output,
+            "invalid: move 3 b2: You already know that cell is taken\n"
+        );
+        let output = run(Command::Validate("a1 b1 a2 b2 a3".to_string()), options());
+        assert_eq!(output, "valid, X wins\n");
+        let mut out: Vec<u8> = vec![];
+        let command = Command::Solve("b2".to_string());
+        let result = run_command(command, options(), &mut "".as_bytes(), &mut out);
+        assert!(matches!(
+            result,
+            Err(CliError::Unsupported("solve", Mode::Phantom))
+        ));
+    }
+
+    #[test]
+    fn test_phantom_hides_computer_moves() {
+        let (_, options) = parse_args(&args("--rules phantom --o computer --color never")).unwrap();
+        let mut out: Vec<u8> = vec![];
+        let input = "b2\nhistory\nundo\na1\nboard\nquit\n";
+        run_command(Command::Play, options, &mut input.as_bytes(), &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("O has moved"));
+        assert!(!output.contains("O plays"));
+        assert!(!output.contains("O's view"));
+        assert!(!output.contains(". O "));
+        assert!(output.contains("1. X b2\n"));
+        assert!(output.contains("cannot be taken back"));
+        // none of O's marks make it onto a drawn row
+        assert!(output
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+            .all(|row| !row.contains('O')));
+    }
+
+    #[test]
+    fn test_patterns() {
+        // the four corners, as a rule file would give them
+        let options = || Options {
+            patterns: "pattern a1 c1 a3 c3".parse().ok(),
+            ..Options::default()
+        };
+        let output = run(Command::Validate("XXXOO O  ".to_string()), options());
+        assert_eq!(output, "valid\n");
+        let output = run(Command::Validate("X XOOOX X".to_string()), options());
+        assert_eq!(output, "valid, X wins\n");
+        let output = run(
+            Command::Analyze("a1 b2 c1 b1 a3 b3 c3".to_string()),
+            options(),
+        );
+        assert!(output.ends_with("7. X c3 X wins\n"));
+        let mut game = Game::new(RulesBoard::new(
+            Board::default(),
+            options().ruleset().into(),
+        ));
+        game.update("a1".parse().unwrap());
+        let record = game.record().unwrap().to_string();
+        assert!(record.contains("pattern a1 c1 a3 c3\n"));
+
+        let output = run(Command::Solve("         ".to_string()), options());
+        // O only has to take one corner
+        assert!(output.ends_with("outcome: draw\n"));
+        assert!(parse_args(&args("--patterns no-such-file")).is_err());
+
+        // a record names the rules or the patterns it was won by, not both
+        let record = "size 3\nrules misere\npattern a1 c1 a3 c3\nmoves a1";
+        let mut out: Vec<u8> = vec![];
+        let command = Command::Analyze(record.to_string());
+        let result = run_command(command, Options::default(), &mut "".as_bytes(), &mut out);
+        assert!(matches!(
+            result,
+            Err(CliError::BadRecord(ParseRecordError::RulesWithPatterns))
+        ));
+    }
+
+    #[test]
+    fn test_enumerate() {
+        let options = Options {
+            size: 2,
+            format: OutputFormat::Json,
+            ..Options::default()
+        };
+        let output = run(Command::Enumerate, options);
+        assert_eq!(
+            output,
+            "{\"total\": 29, \"x_wins\": 12, \"o_wins\": 0, \"draws\": 0, \"in_progress\": 17}\n"
+        );
+    }
+}
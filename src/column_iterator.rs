@@ -1,27 +1,27 @@
-use crate::board::{Board, NUM_CELLS, NUM_ROWS as COL_SIZE};
-use crate::cell::Cell;
+use crate::board::Board;
 use crate::cell_iterator::CellIterator;
 
 pub struct ColumnIterator<'a> {
     count: usize,
-    cells: &'a [Cell; NUM_CELLS],
+    board: &'a Board,
 }
 
 impl<'a> ColumnIterator<'a> {
-    pub fn new(cells: &'a [Cell; NUM_CELLS]) -> ColumnIterator<'a> {
-        ColumnIterator { count: 0, cells }
+    pub fn new(board: &'a Board) -> ColumnIterator<'a> {
+        ColumnIterator { count: 0, board }
     }
 }
 
 impl<'a> Iterator for ColumnIterator<'a> {
     type Item = CellIterator<'a>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.count >= COL_SIZE {
+        if self.count >= self.board.num_cols {
             None
         } else {
-            let next_column_cell_idx =
-                |column_idx: usize, count: usize| column_idx + (COL_SIZE * count);
-            let column_iterator = CellIterator::new(self.cells, self.count, next_column_cell_idx);
+            let row_size = self.board.num_cols;
+            let column_cell_idxs =
+                (0..self.board.num_rows).map(|count| self.count + (row_size * count));
+            let column_iterator = CellIterator::new(&self.board.cells, column_cell_idxs.collect());
             self.count += 1;
             Some(column_iterator)
         }
@@ -29,15 +29,15 @@ impl<'a> Iterator for ColumnIterator<'a> {
 }
 
 impl Board {
-    pub fn columns(&self) -> ColumnIterator {
-        ColumnIterator::new(&self.cells)
+    pub fn columns(&self) -> ColumnIterator<'_> {
+        ColumnIterator::new(self)
     }
 }
 #[cfg(test)]
 mod tests {
 
-    use super::*;
     use crate::board::Board;
+    use crate::cell::Cell;
     use crate::player::Player;
 
     #[test]
@@ -50,18 +50,18 @@ mod tests {
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::X)));
-        assert!(matches!(column.next(), None));
+        assert!(column.next().is_none());
 
         let mut column = columns.next().unwrap();
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::X)));
-        assert!(matches!(column.next(), None));
+        assert!(column.next().is_none());
 
         let mut column = columns.next().unwrap();
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(column.next().unwrap(), Cell::Player(Player::O)));
-        assert!(matches!(column.next(), None));
+        assert!(column.next().is_none());
     }
 }
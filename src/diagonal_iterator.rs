@@ -1,17 +1,29 @@
-use crate::board::{Board, NUM_CELLS, NUM_ROWS as COL_SIZE};
-use crate::cell::Cell;
+use crate::board::Board;
 use crate::cell_iterator::CellIterator;
 
 pub struct DiagonalIterator<'a> {
     diagonal_idx: usize,
-    cells: &'a [Cell; NUM_CELLS],
+    board: &'a Board,
+    // how many diagonals run each way, one unless they wrap around the edges
+    per_direction: usize,
 }
 
 impl<'a> DiagonalIterator<'a> {
-    pub fn new(cells: &'a [Cell; NUM_CELLS]) -> DiagonalIterator<'a> {
+    pub fn new(board: &'a Board) -> DiagonalIterator<'a> {
         DiagonalIterator {
             diagonal_idx: 0,
-            cells,
+            board,
+            per_direction: 1,
+        }
+    }
+
+    // the diagonals of a board whose edges join up like a torus, so a
+    // diagonal can start on any column and carry on from the other side
+    pub fn wrapping(board: &'a Board) -> DiagonalIterator<'a> {
+        DiagonalIterator {
+            diagonal_idx: 0,
+            board,
+            per_direction: board.num_cols,
         }
     }
 }
@@ -19,28 +31,28 @@ impl<'a> DiagonalIterator<'a> {
 impl<'a> Iterator for DiagonalIterator<'a> {
     type Item = CellIterator<'a>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.diagonal_idx == 0 {
-            let increasing_diagonal = |_: usize, count: usize| count + (COL_SIZE * count);
-            let diagonal_iterator =
-                CellIterator::new(self.cells, self.diagonal_idx, increasing_diagonal);
-            self.diagonal_idx += 1;
-            Some(diagonal_iterator)
-        } else if self.diagonal_idx == 1 {
-            let decreasing_diagonal =
-                |_: usize, count: usize| (COL_SIZE - count - 1) + (COL_SIZE * count);
-            let diagonal_iterator =
-                CellIterator::new(self.cells, self.diagonal_idx, decreasing_diagonal);
-            self.diagonal_idx += 1;
-            Some(diagonal_iterator)
-        } else {
-            None
+        let row_size = self.board.num_cols;
+        let diagonal_size = self.board.num_rows.min(self.board.num_cols);
+        if self.diagonal_idx >= 2 * self.per_direction {
+            return None;
         }
+        let offset = self.diagonal_idx % self.per_direction;
+        let increasing = self.diagonal_idx < self.per_direction;
+        let diagonal = (0..diagonal_size).map(|count| {
+            let column = match increasing {
+                true => count + offset,
+                false => diagonal_size - count - 1 + offset,
+            };
+            column % row_size + (row_size * count)
+        });
+        self.diagonal_idx += 1;
+        Some(CellIterator::new(&self.board.cells, diagonal.collect()))
     }
 }
 
 impl Board {
-    pub fn diagonals(&self) -> DiagonalIterator {
-        DiagonalIterator::new(&self.cells)
+    pub fn diagonals(&self) -> DiagonalIterator<'_> {
+        DiagonalIterator::new(self)
     }
 }
 
@@ -49,24 +61,45 @@ mod tests {
 
     use super::*;
     use crate::board::Board;
+    use crate::cell::Cell;
     use crate::player::Player;
 
     #[test]
     fn test_diagonal_iterator() {
         let board = "X O X O X".to_string().parse::<Board>().unwrap();
 
-        let mut diagonals = DiagonalIterator::new(&board.cells);
+        let mut diagonals = DiagonalIterator::new(&board);
 
         let mut diagonal = diagonals.next().unwrap();
         assert!(matches!(diagonal.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(diagonal.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(diagonal.next().unwrap(), Cell::Player(Player::X)));
-        assert!(matches!(diagonal.next(), None));
+        assert!(diagonal.next().is_none());
 
         let mut diagonal = diagonals.next().unwrap();
         assert!(matches!(diagonal.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(diagonal.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(diagonal.next().unwrap(), Cell::Player(Player::O)));
-        assert!(matches!(diagonal.next(), None));
+        assert!(diagonal.next().is_none());
+        assert!(diagonals.next().is_none());
+    }
+
+    #[test]
+    fn test_wrapping_diagonals() {
+        let board = Board::new(3, 3);
+        let diagonals: Vec<Vec<usize>> = DiagonalIterator::wrapping(&board)
+            .map(|diagonal| diagonal.cell_idxs().to_vec())
+            .collect();
+        assert_eq!(
+            diagonals,
+            [
+                [0, 4, 8],
+                [1, 5, 6],
+                [2, 3, 7],
+                [2, 4, 6],
+                [0, 5, 7],
+                [1, 3, 8]
+            ]
+        );
     }
 }
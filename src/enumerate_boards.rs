@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::player::Player;
+use crate::ruleset::{Outcome, Ruleset};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BoardCounts {
+    pub total: usize,
+    pub x_wins: usize,
+    pub o_wins: usize,
+    pub draws: usize,
+    pub in_progress: usize,
+}
+
+fn visit(
+    board: &mut Board,
+    rules: &dyn Ruleset,
+    player: Player,
+    seen: &mut HashSet<Vec<Cell>>,
+    counts: &mut BoardCounts,
+) {
+    if !seen.insert(board.cells.clone()) {
+        return;
+    }
+    counts.total += 1;
+    match rules.outcome(board) {
+        Some(Outcome::Win(Player::X)) => counts.x_wins += 1,
+        Some(Outcome::Win(_)) => counts.o_wins += 1,
+        Some(Outcome::Draw) => counts.draws += 1,
+        None => {
+            counts.in_progress += 1;
+            for cell_id in rules.legal_moves(board) {
+                let idx = cell_id.to_idx(board.num_cols);
+                board.cells[idx] = Cell::Player(player);
+                visit(board, rules, rules.next_player(player), seen, counts);
+                board.cells[idx] = Cell::Unmarked;
+            }
+        }
+    }
+}
+
+// counts every position reachable by legal play, stopping at wins
+pub fn enumerate_boards(size: usize, rules: &dyn Ruleset) -> BoardCounts {
+    let mut counts = BoardCounts::default();
+    let mut seen = HashSet::new();
+    let mut board = Board::new(size, size);
+    let player = rules.player_to_move(&board);
+    visit(&mut board, rules, player, &mut seen, &mut counts);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::misere::MisereRules;
+    use crate::ruleset::ClassicRules;
+
+    #[test]
+    fn test_enumerate_classic_boards() {
+        let counts = enumerate_boards(3, &ClassicRules);
+        assert_eq!(
+            counts,
+            BoardCounts {
+                total: 5478,
+                x_wins: 626,
+                o_wins: 316,
+                draws: 16,
+                in_progress: 4520,
+            }
+        );
+    }
+
+    #[test]
+    fn test_enumerate_misere_boards() {
+        // the same positions are reachable, only the winners swap
+        let counts = enumerate_boards(3, &MisereRules);
+        assert_eq!(counts.total, 5478);
+        assert_eq!(counts.x_wins, 316);
+        assert_eq!(counts.o_wins, 626);
+    }
+}
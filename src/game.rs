@@ -1,89 +1,460 @@
-use crate::board::{Board, NUM_CELLS, NUM_COLS};
-use crate::board_has_win::board_has_win;
-use crate::cell::Cell;
-use crate::cell_id::CellId;
-use crate::game_update::{check_cell, next_player};
-use crate::player::Player;
 use core::fmt;
-use std::io::BufRead;
+use std::io::{self, BufRead, Write};
+
+use crate::game_command::{help_text, parse_command, GameCommand, GameCommandError};
+use crate::game_record::GameRecord;
+use crate::game_update::next_in_turn;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+// how many moves ahead the computer looks when a position is too large to solve
+pub const DEFAULT_DEPTH: usize = 4;
 
+// the text game loop, the same for every mode
 #[derive(Debug)]
-pub struct Game {
-    pub board: Board,
-    pub player: Player,
-    num_cells_played: usize,
+pub struct Game<P: Position> {
+    pub position: P,
+    // players whose moves are chosen by the computer instead of read from input
+    pub computer_players: Vec<Player>,
+    pub depth: usize,
+    // every move so far, with the position it was made from
+    pub history: Vec<(P, P::Move)>,
+    // whether the second player may take over the first player's side after the first move
+    pub pie_rule: bool,
+    pub swapped: bool,
 }
 
-impl Game {
-    pub fn new() -> Game {
+impl<P: Position> Game<P> {
+    pub fn new(position: P) -> Game<P> {
         Game {
-            board: Board::default(),
-            player: Player::X,
-            num_cells_played: 0,
+            position,
+            computer_players: vec![],
+            depth: DEFAULT_DEPTH,
+            history: vec![],
+            pie_rule: false,
+            swapped: false,
         }
     }
 
-    pub fn run<Input: BufRead>(&mut self, f: &mut Input) {
+    pub fn run<Input: BufRead, W: Write>(&mut self, f: &mut Input, out: &mut W) -> io::Result<()> {
         loop {
-            print!("{}", self);
-            let winner = board_has_win(&self.board);
-            if let Cell::Player(p) = winner {
-                println!("Winner! {}", p);
-                break;
+            let player = self.position.player_to_move();
+            let computer_to_move = self.computer_players.contains(&player);
+            let game_over = self.position.outcome().is_some();
+            // with hidden moves the human is only shown their own turns
+            if game_over || !(self.position.hides_moves() && computer_to_move) {
+                write!(out, "{}", self)?;
+            }
+            match self.position.outcome() {
+                Some(Outcome::Win(p)) => {
+                    let moves: Vec<String> = self
+                        .position
+                        .winning_moves()
+                        .iter()
+                        .map(|mv| mv.to_string())
+                        .collect();
+                    match moves.is_empty() {
+                        true => writeln!(out, "Winner! {}", p)?,
+                        false => writeln!(out, "Winner! {} ({})", p, moves.join(" "))?,
+                    }
+                    break;
+                }
+                Some(Outcome::Draw) => {
+                    writeln!(out, "Draw.")?;
+                    break;
+                }
+                None => {}
+            }
+            if self.computer_wants_swap() {
+                self.swap();
+                writeln!(out, "Sides swapped")?;
+                continue;
+            }
+            if computer_to_move {
+                if let Some(mv) = self.position.computer_move(self.depth) {
+                    self.update(mv);
+                    if !self.position.hides_moves() {
+                        writeln!(out, "{} plays {}", player, mv)?;
+                    } else if self.position.player_to_move() != player {
+                        writeln!(out, "{} has moved", player)?;
+                    }
+                    continue;
+                }
             }
-            if self.num_cells_played >= NUM_CELLS {
-                println!("Draw.");
+            if !self.take_turn(f, out)? {
                 break;
             }
-            loop {
-                let mut line = String::new();
-                f.read_line(&mut line).unwrap();
-                let maybe_cell_id = line.trim().parse::<CellId>();
-                if let Ok(cell_id) = maybe_cell_id {
-                    if let Ok(_) = check_cell(&self.board.cells, &cell_id) {
-                        self.update(cell_id);
-                        break;
-                    } else {
-                        println!("That cell is occupied");
+        }
+        Ok(())
+    }
+
+    // who the board is shown to. everyone sees the same unless the position
+    // hides moves, when it is the human to move or the one playing the computer
+    fn viewer(&self) -> Player {
+        let player = self.position.player_to_move();
+        if !self.computer_players.contains(&player) {
+            return player;
+        }
+        Player::all(self.position.num_players())
+            .find(|p| !self.computer_players.contains(p))
+            .unwrap_or(player)
+    }
+
+    // reads commands until the position changes, returns false once the game should stop
+    fn take_turn<Input: BufRead, W: Write>(
+        &mut self,
+        f: &mut Input,
+        out: &mut W,
+    ) -> io::Result<bool> {
+        loop {
+            let mut line = String::new();
+            if f.read_line(&mut line)? == 0 {
+                return Ok(false);
+            }
+            match parse_command(&line) {
+                Ok(GameCommand::Move(input)) => match self
+                    .position
+                    .parse_move(&input)
+                    .and_then(|mv| self.position.check_move(&mv).map(|_| mv))
+                {
+                    Ok(mv) => {
+                        self.update(mv);
+                        return Ok(true);
+                    }
+                    Err(err) => writeln!(out, "{}, or type help", err)?,
+                },
+                Ok(GameCommand::Help) => write!(out, "{}", help_text(&self.position.move_help()))?,
+                Ok(GameCommand::Swap) => {
+                    if self.swap() {
+                        writeln!(out, "Sides swapped")?;
+                        return Ok(true);
+                    }
+                    writeln!(out, "You can only swap straight after the first move")?;
+                }
+                Ok(GameCommand::Undo) if self.position.hides_moves() => {
+                    writeln!(out, "Moves are hidden, so they cannot be taken back")?
+                }
+                Ok(GameCommand::Undo) => {
+                    if self.undo() {
+                        return Ok(true);
                     }
-                } else {
-                    println!("Please use a-c and 1-3 e.g. b2");
+                    writeln!(out, "Nothing to undo")?;
+                }
+                Ok(GameCommand::Hint) => match self.position.solve() {
+                    Some((outcome, Some(mv))) => writeln!(out, "Hint: {} ({})", mv, outcome)?,
+                    // too large to solve, so there is no outcome to go with the move
+                    _ => {
+                        if let Some(mv) = self.position.computer_move(self.depth) {
+                            writeln!(out, "Hint: {}", mv)?;
+                        }
+                    }
+                },
+                Ok(GameCommand::Save(path)) => match self.record() {
+                    Some(record) => match std::fs::write(&path, record.to_string()) {
+                        Ok(_) => writeln!(out, "Saved to {}", path)?,
+                        Err(err) => writeln!(out, "Could not save to {}: {}", path, err)?,
+                    },
+                    None => writeln!(out, "Games of this kind cannot be saved")?,
+                },
+                Ok(GameCommand::Resign) => {
+                    let player = self.position.player_to_move();
+                    match self.position.num_players() {
+                        2 => writeln!(out, "{} resigns. Winner! {}", player, self.opponent())?,
+                        _ => writeln!(out, "{} resigns.", player)?,
+                    }
+                    return Ok(false);
+                }
+                Ok(GameCommand::OfferDraw) => {
+                    if self.draw_accepted(f, out)? {
+                        writeln!(out, "Draw agreed.")?;
+                        return Ok(false);
+                    }
+                    writeln!(out, "Draw declined.")?;
+                }
+                Ok(GameCommand::Board) => write!(out, "{}", self)?,
+                Ok(GameCommand::History) => {
+                    let viewer = self.viewer();
+                    for (move_idx, (position, mv)) in self.history.iter().enumerate() {
+                        let player = position.player_to_move();
+                        // other players' hidden moves are left out
+                        if self.position.hides_moves() && player != viewer {
+                            continue;
+                        }
+                        writeln!(out, "{}. {} {}", move_idx + 1, player, mv)?;
+                        if move_idx == 0 && self.swapped {
+                            writeln!(out, "   sides swapped")?;
+                        }
+                    }
+                }
+                Ok(GameCommand::Quit) => return Ok(false),
+                Err(GameCommandError::Empty) => {}
+                Err(GameCommandError::MissingArgument(name)) => {
+                    writeln!(out, "Missing <{}>", name)?
                 }
             }
         }
     }
-    pub fn update(&mut self, cell_id: CellId) {
-        self.board.cells[cell_id.to_idx()] = Cell::Player(self.player);
-        self.player = next_player(self.player);
+
+    fn opponent(&self) -> Player {
+        next_in_turn(self.position.player_to_move(), self.position.num_players())
     }
-}
 
-impl fmt::Display for Game {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut out: Vec<String> = Vec::new();
-        out.push("  ".to_string());
-        for column_idx in 0..NUM_COLS {
-            out.push(format!(" {}", (column_idx + 'a' as usize) as u8 as char));
+    // the computer takes any draw it cannot be sure of beating, a human is asked
+    fn draw_accepted<Input: BufRead, W: Write>(
+        &self,
+        f: &mut Input,
+        out: &mut W,
+    ) -> io::Result<bool> {
+        let opponent = self.opponent();
+        if self.computer_players.contains(&opponent) {
+            return Ok(self
+                .position
+                .solve()
+                .is_none_or(|(outcome, _)| outcome != Outcome::Win(opponent)));
+        }
+        writeln!(out, "{}, do you accept a draw? (y/n)", opponent)?;
+        let mut line = String::new();
+        f.read_line(&mut line)?;
+        Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    pub fn can_swap(&self) -> bool {
+        self.pie_rule
+            && !self.swapped
+            && self.history.len() == 1
+            && self.position.num_players() == 2
+            && self.position.outcome().is_none()
+    }
+
+    // the second player takes the first move as their own, so whoever the
+    // computer played for, it now plays for the other side
+    pub fn swap(&mut self) -> bool {
+        if !self.can_swap() {
+            return false;
+        }
+        self.swap_computer_players();
+        self.swapped = true;
+        true
+    }
+
+    fn swap_computer_players(&mut self) {
+        let num_players = self.position.num_players();
+        for player in self.computer_players.iter_mut() {
+            *player = next_in_turn(*player, num_players);
         }
+    }
+
+    // the computer swaps when the first move would win for the side that made it
+    pub fn computer_wants_swap(&self) -> bool {
+        self.can_swap()
+            && self
+                .computer_players
+                .contains(&self.position.player_to_move())
+            && self
+                .position
+                .solve()
+                .is_some_and(|(outcome, _)| outcome == Outcome::Win(self.opponent()))
+    }
+
+    pub fn update(&mut self, mv: P::Move) {
+        self.history.push((self.position.clone(), mv));
+        self.position.play(mv);
+    }
 
-        out.push("\n  -------\n".to_string());
-        for (row_idx, row) in self.board.rows().enumerate() {
-            out.push(format!("{} |", row_idx + 1));
-            for cell in row {
-                out.push(format!("{}|", cell));
+    // takes moves back until a human is to play, so the computer does not simply replay them
+    pub fn undo(&mut self) -> bool {
+        let mut undone = false;
+        while let Some((position, _)) = self.history.pop() {
+            self.position = position;
+            undone = true;
+            if self.history.is_empty() && self.swapped {
+                self.swapped = false;
+                self.swap_computer_players();
+            }
+            if !self
+                .computer_players
+                .contains(&self.position.player_to_move())
+            {
+                break;
             }
-            out.push("\n  -------\n".to_string());
         }
+        undone
+    }
+
+    // the moves so far, from where the game started
+    pub fn moves(&self) -> Vec<P::Move> {
+        self.history.iter().map(|(_, mv)| *mv).collect()
+    }
+
+    // None for modes without records
+    pub fn record(&self) -> Option<GameRecord> {
+        let start = self
+            .history
+            .first()
+            .map_or(&self.position, |(position, _)| position);
+        let record = start.record(&self.moves())?;
+        Some(GameRecord {
+            pie_rule: self.pie_rule,
+            swapped: self.swapped,
+            ..record
+        })
+    }
+}
 
-        out.push(format!("Next Turn: {}\n", self.player));
-        write!(f, "{}", out.join(""))
+impl<P: Position> fmt::Display for Game<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.position.view_for(self.viewer()))?;
+        if self.position.outcome().is_none() {
+            writeln!(f, "Next Turn: {}", self.position.player_to_move())?;
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::rc::Rc;
+
     use super::*;
+    use crate::board::Board;
+    use crate::board_has_win::board_has_win;
+    use crate::cell::Cell;
+    use crate::cell_id::CellId;
+    use crate::misere::MisereRules;
+    use crate::rules_board::RulesBoard;
+    use crate::ruleset::{ClassicRules, Ruleset};
+    use crate::variant::Variant;
+
+    fn board_game(board: Board, rules: Rc<dyn Ruleset>) -> Game<RulesBoard> {
+        Game::new(RulesBoard::new(board, rules))
+    }
+
+    fn classic_game() -> Game<RulesBoard> {
+        board_game(Board::default(), Rc::new(ClassicRules))
+    }
+
+    fn cell(cell_str: &str) -> CellId {
+        cell_str.parse::<CellId>().unwrap()
+    }
+
+    #[test]
+    fn test_game_update() {
+        let mut game = classic_game();
+        game.update(cell("b2"));
+        assert_eq!(game.position.board.to_string(), "    X    ");
+        assert_eq!(game.position.player_to_move(), Player::O);
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut game = classic_game();
+        assert!(!game.undo());
+        game.update(cell("b2"));
+        game.update(cell("a1"));
+        assert!(game.undo());
+        assert_eq!(game.position.board.to_string(), "    X    ");
+        assert_eq!(game.position.player_to_move(), Player::O);
+
+        game.computer_players = vec![Player::O];
+        game.update(cell("a1"));
+        assert!(game.undo());
+        assert_eq!(game.position.board.to_string(), "         ");
+        assert_eq!(game.position.player_to_move(), Player::X);
+        assert_eq!(game.record().unwrap().moves, vec![]);
+    }
 
     #[test]
-    fn test_game_update() {}
+    fn test_commands_in_game() {
+        let mut game = classic_game();
+        let input = "help\nb2\nhint\na1\nundo\nc3\nhistory\nresign\nb2\n";
+        game.run(&mut input.as_bytes(), &mut io::sink()).unwrap();
+        assert_eq!(game.position.board.to_string(), "    X   O");
+        assert_eq!(game.position.player_to_move(), Player::X);
+
+        let mut game = classic_game();
+        let input = "b2\noffer draw\nn\noffer draw\nyes\na1\n";
+        game.run(&mut input.as_bytes(), &mut io::sink()).unwrap();
+        assert_eq!(game.position.board.to_string(), "    X    ");
+    }
+
+    #[test]
+    fn test_winner_names_the_line() {
+        let mut game = classic_game();
+        let mut out: Vec<u8> = vec![];
+        let input = "a1\na2\nb1\nb2\nc1\n";
+        game.run(&mut input.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("Winner! X (a1 b1 c1)\n"));
+    }
+
+    #[test]
+    fn test_unreadable_input() {
+        let mut game = classic_game();
+        let result = game.run(&mut [0xff, b'\n'].as_slice(), &mut io::sink());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hint_on_large_board() {
+        let mut game = board_game(Board::new(5, 5), Rc::new(ClassicRules));
+        let mut out: Vec<u8> = vec![];
+        game.run(&mut "hint\n".as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.lines().any(|line| line.starts_with("Hint: ")));
+    }
+
+    #[test]
+    fn test_computer_game() {
+        let mut game = classic_game();
+        game.computer_players = vec![Player::X, Player::O];
+        game.run(&mut "".as_bytes(), &mut io::sink()).unwrap();
+        assert!(game.position.board.is_full());
+        assert_eq!(board_has_win(&game.position.board), Cell::Unmarked);
+    }
+
+    #[test]
+    fn test_misere_computer_game() {
+        let mut game = board_game(Board::default(), Rc::new(MisereRules));
+        game.computer_players = vec![Player::X, Player::O];
+        game.run(&mut "".as_bytes(), &mut io::sink()).unwrap();
+        assert!(game.position.board.is_full());
+        assert_eq!(game.position.outcome(), Some(Outcome::Draw));
+        assert_eq!(game.record().unwrap().variant, Variant::Misere);
+    }
+
+    #[test]
+    fn test_pie_rule() {
+        let rules = Variant::Classic.ruleset_for(Player::O);
+        let mut game = board_game(Board::default(), rules.into());
+        game.pie_rule = true;
+        assert!(!game.swap());
+        let input = "b2\nswap\nswap\na1\n";
+        game.run(&mut input.as_bytes(), &mut io::sink()).unwrap();
+        assert_eq!(game.position.board.to_string(), "X   O    ");
+        let record = game.record().unwrap();
+        assert_eq!(record.first, Player::O);
+        assert!(record.swapped);
+        assert_eq!(record.to_string().parse::<GameRecord>().unwrap(), record);
+
+        // a centre opening is not a win, so the computer keeps its side
+        let mut game = classic_game();
+        game.pie_rule = true;
+        game.computer_players = vec![Player::O];
+        game.update(cell("b2"));
+        assert!(!game.computer_wants_swap());
+        assert!(game.swap());
+        assert_eq!(game.computer_players, vec![Player::X]);
+        assert!(game.undo());
+        assert!(!game.swapped);
+        assert_eq!(game.computer_players, vec![Player::O]);
+    }
+
+    #[test]
+    fn test_game_display() {
+        let board = "X   O    ".parse::<Board>().unwrap();
+        let game = board_game(board, Rc::new(ClassicRules));
+        let expected = "    a   b   c \n  -------------\n1 | X |   |   |\n  -------------\n2 |   | O |   |\n  -------------\n3 |   |   |   |\n  -------------\nNext Turn: X\n";
+        assert_eq!(game.to_string(), expected);
+    }
 }
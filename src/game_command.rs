@@ -0,0 +1,114 @@
+#[derive(Debug, PartialEq, Eq)]
+pub enum GameCommand {
+    // anything that is not a command, to be read as a move
+    Move(String),
+    Help,
+    Undo,
+    Swap,
+    Hint,
+    Save(String),
+    Resign,
+    OfferDraw,
+    Board,
+    History,
+    Quit,
+}
+
+#[derive(Debug)]
+pub enum GameCommandError {
+    Empty,
+    MissingArgument(&'static str),
+}
+
+pub fn parse_command(input: &str) -> Result<GameCommand, GameCommandError> {
+    let input = input.trim();
+    let (word, rest) = match input.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (input, ""),
+    };
+    match (word.to_lowercase().as_str(), rest) {
+        ("", _) => Err(GameCommandError::Empty),
+        ("help" | "?", "") => Ok(GameCommand::Help),
+        ("undo", "") => Ok(GameCommand::Undo),
+        ("swap", "") => Ok(GameCommand::Swap),
+        ("hint", "") => Ok(GameCommand::Hint),
+        ("save", "") => Err(GameCommandError::MissingArgument("file")),
+        ("save", path) => Ok(GameCommand::Save(path.to_string())),
+        ("resign", "") => Ok(GameCommand::Resign),
+        ("offer", r) if r.eq_ignore_ascii_case("draw") => Ok(GameCommand::OfferDraw),
+        ("board", "") => Ok(GameCommand::Board),
+        ("history", "") => Ok(GameCommand::History),
+        ("quit" | "exit", "") => Ok(GameCommand::Quit),
+        _ => Ok(GameCommand::Move(input.to_string())),
+    }
+}
+
+// `move_help` says how to write a move in the mode being played
+pub fn help_text(move_help: &str) -> String {
+    format!(
+        "\
+{}
+Other commands:
+  help          show this message
+  undo          take back the last move
+  swap          take over the first move, with the pie rule
+  hint          suggest a move
+  save <file>   save the game so far
+  resign        give the game to your opponent
+  offer draw    ask your opponent to agree to a draw
+  board         show the board again
+  history       list the moves so far
+  quit          stop playing
+",
+        move_help
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let commands = [
+            ("help", GameCommand::Help),
+            ("  UNDO ", GameCommand::Undo),
+            ("swap", GameCommand::Swap),
+            ("hint", GameCommand::Hint),
+            (
+                "save my game.txt",
+                GameCommand::Save("my game.txt".to_string()),
+            ),
+            ("resign", GameCommand::Resign),
+            ("offer   draw", GameCommand::OfferDraw),
+            ("board", GameCommand::Board),
+            ("history", GameCommand::History),
+            ("quit", GameCommand::Quit),
+            (" b2 ", GameCommand::Move("b2".to_string())),
+            ("top left", GameCommand::Move("top left".to_string())),
+            ("undo twice", GameCommand::Move("undo twice".to_string())),
+        ];
+        for (input, expected) in commands {
+            assert_eq!(parse_command(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_bad_commands() {
+        let result = parse_command("  ");
+        assert!(matches!(result, Err(GameCommandError::Empty)));
+        let result = parse_command("save");
+        assert!(matches!(
+            result,
+            Err(GameCommandError::MissingArgument("file"))
+        ));
+    }
+
+    #[test]
+    fn test_help_text() {
+        let help = help_text("Type a column");
+        assert!(help.starts_with("Type a column\nOther commands:\n"));
+        assert!(help.contains("  swap "));
+    }
+}
@@ -0,0 +1,279 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::board::{Board, MAX_SIZE, NUM_ROWS};
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::patterns::{ParsePatternsError, WinPatterns};
+use crate::player::Player;
+use crate::ruleset::Ruleset;
+use crate::variant::Variant;
+
+// a record is a header line per setting, followed by the moves, e.g.
+//
+// size 3
+// rules misere
+// first o
+// pie on
+// moves b2 swap a1 c3
+//
+// where the rules, first and pie lines are left out for classic games
+// started by X without the pie rule. games won by custom patterns list them
+// as lines of their own, as in a rule file. `swap` after the first move means the
+// second player took over the first player's side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    pub size: usize,
+    pub variant: Variant,
+    pub first: Player,
+    pub pie_rule: bool,
+    pub swapped: bool,
+    pub patterns: Option<WinPatterns>,
+    pub moves: Vec<CellId>,
+}
+
+impl GameRecord {
+    pub fn new(size: usize) -> GameRecord {
+        GameRecord {
+            size,
+            variant: Variant::Classic,
+            first: Player::X,
+            pie_rule: false,
+            swapped: false,
+            patterns: None,
+            moves: vec![],
+        }
+    }
+
+    pub fn ruleset(&self) -> Box<dyn Ruleset> {
+        match &self.patterns {
+            Some(patterns) => patterns.ruleset_for(self.size, self.first),
+            None => self.variant.ruleset_for(self.first),
+        }
+    }
+
+    // every position of the game, starting with the empty board
+    pub fn boards(&self) -> Vec<Board> {
+        let rules = self.ruleset();
+        let mut board = Board::new(self.size, self.size);
+        let mut player = rules.player_to_move(&board);
+        let mut boards = vec![board.clone()];
+        for cell_id in &self.moves {
+            board.cells[cell_id.to_idx(board.num_cols)] = Cell::Player(player);
+            player = rules.next_player(player);
+            boards.push(board.clone());
+        }
+        boards
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut moves: Vec<String> = self.moves.iter().map(|m| m.to_string()).collect();
+        if self.swapped {
+            moves.insert(1, "swap".to_string());
+        }
+        writeln!(f, "size {}", self.size)?;
+        if self.variant != Variant::Classic {
+            writeln!(f, "rules {}", self.variant)?;
+        }
+        if self.first != Player::X {
+            writeln!(f, "first {}", self.first.to_string().to_lowercase())?;
+        }
+        if self.pie_rule {
+            writeln!(f, "pie on")?;
+        }
+        if let Some(patterns) = &self.patterns {
+            write!(f, "{}", patterns)?;
+        }
+        writeln!(f, "moves {}", moves.join(" "))
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseRecordError {
+    BadSize(String),
+    BadRules(String),
+    BadFirst(String),
+    BadPie(String),
+    BadPatterns(ParsePatternsError),
+    // patterns take the place of the rules' lines, so a record has one or the other
+    RulesWithPatterns,
+    UnknownHeader(String),
+    BadMove(String),
+    IllegalMove(CellId),
+    IllegalSwap,
+}
+
+impl FromStr for GameRecord {
+    type Err = ParseRecordError;
+    fn from_str(record_str: &str) -> Result<Self, Self::Err> {
+        let mut size = NUM_ROWS;
+        let mut variant = None;
+        let mut first = Player::X;
+        let mut pie_rule = false;
+        let mut pattern_lines: Vec<&str> = vec![];
+        let mut move_strs: Vec<&str> = vec![];
+        for line in record_str.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => {}
+                Some("size") => {
+                    let size_str = words.collect::<Vec<&str>>().join(" ");
+                    size = match size_str.parse::<usize>() {
+                        Ok(s) if (1..=MAX_SIZE).contains(&s) => s,
+                        _ => return Err(Self::Err::BadSize(size_str)),
+                    };
+                }
+                Some("rules") => {
+                    let rules_str = words.collect::<Vec<&str>>().join(" ");
+                    variant = Some(
+                        rules_str
+                            .parse()
+                            .map_err(|_| Self::Err::BadRules(rules_str))?,
+                    );
+                }
+                Some("first") => {
+                    let first_str = words.collect::<Vec<&str>>().join(" ");
+                    first = match first_str.parse() {
+                        Ok(p @ (Player::X | Player::O)) => p,
+                        _ => return Err(Self::Err::BadFirst(first_str)),
+                    };
+                }
+                Some("pie") => {
+                    let pie_str = words.collect::<Vec<&str>>().join(" ");
+                    pie_rule = match pie_str.as_str() {
+                        "on" => true,
+                        "off" => false,
+                        _ => return Err(Self::Err::BadPie(pie_str)),
+                    };
+                }
+                Some("lines" | "pattern" | "shape") => pattern_lines.push(line),
+                Some("moves") => move_strs.extend(words),
+                Some(header) => return Err(Self::Err::UnknownHeader(header.to_string())),
+            }
+        }
+
+        let patterns = match (pattern_lines.is_empty(), variant) {
+            (true, _) => None,
+            (false, Some(_)) => return Err(Self::Err::RulesWithPatterns),
+            (false, None) => {
+                let patterns = pattern_lines
+                    .join("\n")
+                    .parse::<WinPatterns>()
+                    .map_err(Self::Err::BadPatterns)?;
+                patterns
+                    .cell_sets(size, size)
+                    .map_err(Self::Err::BadPatterns)?;
+                Some(patterns)
+            }
+        };
+        let mut record = GameRecord {
+            variant: variant.unwrap_or(Variant::Classic),
+            first,
+            pie_rule,
+            patterns,
+            ..GameRecord::new(size)
+        };
+        let rules = record.ruleset();
+        let mut board = Board::new(size, size);
+        let mut player = rules.player_to_move(&board);
+        for move_str in move_strs {
+            // the pie rule allows one swap, straight after the first move
+            if move_str == "swap" {
+                if !pie_rule || record.swapped || record.moves.len() != 1 {
+                    return Err(Self::Err::IllegalSwap);
+                }
+                record.swapped = true;
+                continue;
+            }
+            let cell_id = CellId::parse_for(move_str, size, size)
+                .map_err(|_| Self::Err::BadMove(move_str.to_string()))?;
+            if rules.check_move(&board, &cell_id).is_err() {
+                return Err(Self::Err::IllegalMove(cell_id));
+            }
+            board.cells[cell_id.to_idx(size)] = Cell::Player(player);
+            player = rules.next_player(player);
+            record.moves.push(cell_id);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_record_to_from_string() {
+        let record_str = "size 3\nmoves b2 a1 c3\n";
+        let record = record_str.parse::<GameRecord>().unwrap();
+        assert_eq!(record.size, 3);
+        assert_eq!(record.moves.len(), 3);
+        assert_eq!(record.to_string(), record_str);
+
+        let boards = record.boards();
+        assert_eq!(boards.len(), 4);
+        assert_eq!(boards[3].to_string(), "O   X   X");
+
+        let record_str = "size 3\nrules misere\nmoves b2\n";
+        let record = record_str.parse::<GameRecord>().unwrap();
+        assert_eq!(record.variant, Variant::Misere);
+        assert_eq!(record.to_string(), record_str);
+    }
+
+    #[test]
+    fn test_first_player_and_swap() {
+        let record_str = "size 3\nfirst o\npie on\nmoves b2 swap a1\n";
+        let record = record_str.parse::<GameRecord>().unwrap();
+        assert_eq!(record.first, Player::O);
+        assert!(record.pie_rule);
+        assert!(record.swapped);
+        assert_eq!(record.moves.len(), 2);
+        assert_eq!(record.to_string(), record_str);
+        assert_eq!(record.boards()[2].to_string(), "X   O    ");
+
+        let record = "pie on\nmoves b2 a1 swap".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::IllegalSwap)));
+        let record = "moves b2 swap".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::IllegalSwap)));
+        let record = "first z".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::BadFirst(_))));
+        let record = "pie maybe".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::BadPie(_))));
+    }
+
+    #[test]
+    fn test_record_with_patterns() {
+        let record_str = "size 3\npattern a1 c1 a3 c3\nmoves a1 b2 c1 b1 a3 b3 c3\n";
+        let record = record_str.parse::<GameRecord>().unwrap();
+        assert_eq!(record.to_string(), record_str);
+        // the middle column does not count, so the game went on
+        assert_eq!(record.moves.len(), 7);
+        let rules = record.ruleset();
+        let board = record.boards().pop().unwrap();
+        assert_eq!(rules.winning_line(&board), Some(vec![0, 2, 6, 8]));
+
+        let record = "size 3\nshape a1 d1".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::BadPatterns(_))));
+        let record = "size 3\nrules misere\npattern a1 c1 a3 c3".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::RulesWithPatterns)));
+    }
+
+    #[test]
+    fn test_bad_records() {
+        let record = "size 30".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::BadSize(_))));
+        let record = "rules backwards".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::BadRules(_))));
+        let record = "sizes 3".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::UnknownHeader(_))));
+        let record = "moves b2 z9".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::BadMove(_))));
+        let record = "moves b2 b2".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::IllegalMove(_))));
+        let record = "moves a1 a2 b1 b2 c1 c2".parse::<GameRecord>();
+        assert!(matches!(record, Err(ParseRecordError::IllegalMove(_))));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::board::NUM_CELLS;
+use crate::board::Board;
 use crate::cell::Cell;
 use crate::cell_id::CellId;
 use crate::player::Player;
@@ -6,19 +6,40 @@ use crate::player::Player;
 #[derive(Debug)]
 pub struct CellIsMarked;
 
-pub fn check_cell(cells: &[Cell; NUM_CELLS], cell_id: &CellId) -> Result<(), CellIsMarked> {
-    let cell_idx = cell_id.to_idx();
-    if let Cell::Player(_) = cells[cell_idx] {
+pub fn check_cell(board: &Board, cell_id: &CellId) -> Result<(), CellIsMarked> {
+    if let Cell::Player(_) = board.cell(cell_id) {
         return Err(CellIsMarked);
     }
     Ok(())
 }
 
-pub fn next_player(player: Player) -> Player {
-    if let Player::X = player {
-        return Player::O;
+// `num_players` take turns from `first`, so the next is the first one in
+// turn order with fewer marks than `first`
+pub fn player_to_move(board: &Board, first: Player, num_players: usize) -> Player {
+    let count = |player: Player| {
+        board
+            .cells
+            .iter()
+            .filter(|c| **c == Cell::Player(player))
+            .count()
+    };
+    let mut player = next_in_turn(first, num_players);
+    while player != first {
+        if count(player) < count(first) {
+            return player;
+        }
+        player = next_in_turn(player, num_players);
     }
-    Player::X
+    first
+}
+
+pub fn next_player(player: Player) -> Player {
+    next_in_turn(player, 2)
+}
+
+// turns go round the players in order, back to X after the last
+pub fn next_in_turn(player: Player, num_players: usize) -> Player {
+    Player::nth((player.idx() + 1) % num_players)
 }
 
 #[cfg(test)]
@@ -27,16 +48,34 @@ mod test {
 
     #[test]
     fn test_check_cell() {
-        let mut cells = [Cell::Unmarked; NUM_CELLS];
+        let mut board = Board::default();
 
         let cell_id = "a1".to_string().parse::<CellId>().unwrap();
-        let result = check_cell(&cells, &cell_id);
-        assert!(matches!(result, Ok(_)));
+        let result = check_cell(&board, &cell_id);
+        assert!(result.is_ok());
 
         let cell_id = "a1".to_string().parse::<CellId>().unwrap();
-        cells[cell_id.to_idx()] = Cell::Player(Player::X);
+        board.cells[cell_id.to_idx(board.num_cols)] = Cell::Player(Player::X);
 
-        let result = check_cell(&cells, &cell_id);
+        let result = check_cell(&board, &cell_id);
         assert!(matches!(result, Err(CellIsMarked)));
     }
+
+    #[test]
+    fn test_player_to_move() {
+        let board = "         ".to_string().parse::<Board>().unwrap();
+        assert_eq!(player_to_move(&board, Player::X, 2), Player::X);
+        assert_eq!(player_to_move(&board, Player::O, 2), Player::O);
+        let board = "    X    ".to_string().parse::<Board>().unwrap();
+        assert_eq!(player_to_move(&board, Player::X, 2), Player::O);
+        let board = "O   X    ".to_string().parse::<Board>().unwrap();
+        assert_eq!(player_to_move(&board, Player::X, 2), Player::X);
+        assert_eq!(player_to_move(&board, Player::O, 2), Player::O);
+
+        // with three players Δ moves after O
+        let board = "O   X    ".to_string().parse::<Board>().unwrap();
+        assert_eq!(player_to_move(&board, Player::X, 3), Player::nth(2));
+        let board = Board::parse_cells("O        ").unwrap();
+        assert_eq!(player_to_move(&board, Player::O, 3), Player::nth(2));
+    }
 }
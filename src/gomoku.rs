@@ -0,0 +1,414 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::line_runs::{find_run, run, step, DIRECTIONS};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+pub const GOMOKU_SIZE: usize = 15;
+const LINE_LENGTH: usize = 5;
+
+// whether six or more in a row counts as five
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Overline {
+    // freestyle gomoku
+    #[default]
+    Wins,
+    // standard gomoku, only exactly five wins
+    DoesNotWin,
+}
+
+// how much of the board fits on screen, in cells
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Viewport {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Viewport {
+    // each cell takes two columns after the row numbers, and a few lines are
+    // kept for the header, the status lines and the prompt
+    pub fn for_terminal(width: usize, height: usize) -> Viewport {
+        Viewport {
+            rows: height.saturating_sub(5).max(1),
+            cols: (width.saturating_sub(3) / 2).max(1),
+        }
+    }
+
+    // the terminal size from the shell, if it says
+    pub fn from_env() -> Option<Viewport> {
+        let var = |name| std::env::var(name).ok()?.parse::<usize>().ok();
+        Some(Viewport::for_terminal(var("COLUMNS")?, var("LINES")?))
+    }
+
+    // the terminal size asked of the terminal itself, which needs the tui
+    // feature, or else from the shell as most shells do not export it
+    pub fn from_terminal() -> Option<Viewport> {
+        #[cfg(feature = "tui")]
+        if let Ok((width, height)) = crossterm::terminal::size() {
+            return Some(Viewport::for_terminal(width as usize, height as usize));
+        }
+        Viewport::from_env()
+    }
+}
+
+#[derive(Debug)]
+pub enum GomokuMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+}
+
+impl fmt::Display for GomokuMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GomokuMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            GomokuMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a cell e.g. h8")
+            }
+            GomokuMoveError::GameOver => write!(f, "The game is over"),
+            GomokuMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gomoku {
+    pub board: Board,
+    pub player: Player,
+    pub overline: Overline,
+    pub last_move: Option<CellId>,
+    // None draws the whole board
+    pub viewport: Option<Viewport>,
+}
+
+impl Default for Gomoku {
+    fn default() -> Self {
+        Gomoku::new(GOMOKU_SIZE, Overline::default())
+    }
+}
+
+impl Gomoku {
+    pub fn new(size: usize, overline: Overline) -> Gomoku {
+        Gomoku {
+            board: Board::new(size, size),
+            player: Player::X,
+            overline,
+            last_move: None,
+            viewport: None,
+        }
+    }
+
+    fn is_win_length(&self, length: usize) -> bool {
+        match self.overline {
+            Overline::Wins => length >= LINE_LENGTH,
+            Overline::DoesNotWin => length == LINE_LENGTH,
+        }
+    }
+
+    // the cells of a winning row, starting from its first cell
+    pub fn winning_line(&self) -> Option<Vec<CellId>> {
+        find_run(&self.board, |length| self.is_win_length(length))
+    }
+
+    // what playing `cell_id` would do for `player`'s rows through it, the
+    // longer and more open a row, the more it is worth
+    fn threat_score(&self, cell_id: CellId, player: Player) -> i32 {
+        DIRECTIONS
+            .iter()
+            .map(|dir| {
+                let (ahead, open_ahead) = run(&self.board, cell_id, *dir, player);
+                let (behind, open_behind) = run(&self.board, cell_id, (-dir.0, -dir.1), player);
+                let length = ahead + behind + 1;
+                let open_ends = open_ahead as usize + open_behind as usize;
+                if length >= LINE_LENGTH {
+                    return if self.is_win_length(length) {
+                        100_000
+                    } else {
+                        0
+                    };
+                }
+                match (length, open_ends) {
+                    (_, 0) => 0,
+                    (4, 2) => 10_000,
+                    (4, _) | (3, 2) => 1_000,
+                    (3, _) | (2, 2) => 100,
+                    (2, _) | (1, 2) => 10,
+                    _ => 1,
+                }
+            })
+            .sum()
+    }
+
+    // empty cells near a mark, the only places worth thinking about
+    fn candidate_moves(&self) -> Vec<CellId> {
+        let near_a_mark = |cell_id: &CellId| {
+            (-2..=2).any(|dr| {
+                (-2..=2).any(|dc| {
+                    step(&self.board, *cell_id, (dr, dc), 1)
+                        .is_some_and(|near| self.board.cell(&near) != Cell::Unmarked)
+                })
+            })
+        };
+        self.board.unmarked_cell_ids().filter(near_a_mark).collect()
+    }
+
+    // the rows and columns to draw, centred on the last move when the board is too big
+    fn window(&self) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let (num_rows, num_cols) = (self.board.num_rows, self.board.num_cols);
+        let Some(viewport) = self.viewport else {
+            return (0..num_rows, 0..num_cols);
+        };
+        let centre = self.last_move.unwrap_or(CellId {
+            row: num_rows / 2,
+            column: num_cols / 2,
+        });
+        let span = |centre: usize, size: usize, len: usize| {
+            let len = len.min(size);
+            let start = centre.saturating_sub(len / 2).min(size - len);
+            start..start + len
+        };
+        (
+            span(centre.row, num_rows, viewport.rows),
+            span(centre.column, num_cols, viewport.cols),
+        )
+    }
+}
+
+impl Position for Gomoku {
+    type Move = CellId;
+    type MoveError = GomokuMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winning_line() {
+            Some(line) => match self.board.cell(&line[0]) {
+                Cell::Player(p) => Some(Outcome::Win(p)),
+                Cell::Unmarked => None,
+            },
+            None if self.board.is_full() => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board.unmarked_cell_ids().collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, GomokuMoveError> {
+        CellId::parse_for(input.trim(), self.board.num_rows, self.board.num_cols)
+            .map_err(GomokuMoveError::BadCell)
+    }
+
+    fn check_move(&self, mv: &CellId) -> Result<(), GomokuMoveError> {
+        if self.outcome().is_some() {
+            return Err(GomokuMoveError::GameOver);
+        }
+        if self.board.cell(mv) != Cell::Unmarked {
+            return Err(GomokuMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: CellId) {
+        let idx = mv.to_idx(self.board.num_cols);
+        self.board.cells[idx] = Cell::Player(self.player);
+        self.player = next_player(self.player);
+        self.last_move = Some(mv);
+    }
+
+    fn move_help(&self) -> String {
+        let last_column = (b'a' + self.board.num_cols as u8 - 1) as char;
+        format!(
+            "Type a cell from a1 to {}{} e.g. h8",
+            last_column, self.board.num_rows
+        )
+    }
+
+    // the board is too big to search, so score each cell by the rows it
+    // makes for us and the rows it spoils for the opponent. blocking counts
+    // for a little less, so a four of our own beats blocking an open three
+    fn computer_move(&self, _depth: usize) -> Option<CellId> {
+        if self.outcome().is_some() {
+            return None;
+        }
+        let opponent = next_player(self.player);
+        let mut best: Option<(i32, CellId)> = None;
+        for cell_id in self.candidate_moves() {
+            let score = self.threat_score(cell_id, self.player) * 10
+                + self.threat_score(cell_id, opponent) * 9;
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, cell_id));
+            }
+        }
+        best.map(|(_, cell_id)| cell_id).or_else(|| {
+            // nothing on the board yet, so take the centre
+            let centre = CellId {
+                row: self.board.num_rows / 2,
+                column: self.board.num_cols / 2,
+            };
+            self.board.unmarked_cell_ids().find(|c| *c == centre)
+        })
+    }
+}
+
+// a compact grid with a cell per two columns, so 15x15 fits in 80x24, e.g.
+//
+//    a b c d e
+//  1 . . . . .
+//  2 . X O . .
+impl fmt::Display for Gomoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (rows, cols) = self.window();
+        let letters: Vec<String> = cols
+            .clone()
+            .map(|column| ((b'a' + column as u8) as char).to_string())
+            .collect();
+        writeln!(f, "   {}", letters.join(" "))?;
+        for row in rows.clone() {
+            let glyphs: Vec<String> = cols
+                .clone()
+                .map(|column| match self.board.cell(&CellId { row, column }) {
+                    Cell::Player(p) => p.to_string(),
+                    Cell::Unmarked => ".".to_string(),
+                })
+                .collect();
+            writeln!(f, "{:>2} {}", row + 1, glyphs.join(" "))?;
+        }
+        if rows.len() < self.board.num_rows || cols.len() < self.board.num_cols {
+            let corner = |row, column| CellId { row, column };
+            writeln!(
+                f,
+                "Showing {} to {} of {}x{}",
+                corner(rows.start, cols.start),
+                corner(rows.end - 1, cols.end - 1),
+                self.board.num_cols,
+                self.board.num_rows
+            )?;
+        }
+        if let Some(line) = self.winning_line() {
+            let cells: Vec<String> = line.iter().map(|c| c.to_string()).collect();
+            writeln!(f, "Line: {}", cells.join(" "))?;
+        } else if let Some(last_move) = self.last_move {
+            writeln!(f, "Last move: {}", last_move)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(gomoku: &mut Gomoku, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = gomoku.parse_move(mv_str).unwrap();
+            gomoku.check_move(&mv).unwrap();
+            gomoku.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let gomoku = Gomoku::default();
+        assert_eq!(
+            gomoku.parse_move("h8").ok(),
+            Some(CellId { row: 7, column: 7 })
+        );
+        assert_eq!(
+            gomoku.parse_move("O15").ok(),
+            Some(CellId {
+                row: 14,
+                column: 14
+            })
+        );
+        assert!(matches!(
+            gomoku.parse_move("p1"),
+            Err(GomokuMoveError::BadCell(CellIdError::OutOfBounds))
+        ));
+        assert!(matches!(
+            gomoku.parse_move("a16"),
+            Err(GomokuMoveError::BadCell(CellIdError::OutOfBounds))
+        ));
+    }
+
+    #[test]
+    fn test_five_in_a_row() {
+        let mut gomoku = Gomoku::default();
+        play_all(&mut gomoku, "d4 a1 e5 a2 f6 a3 g7 a4");
+        assert_eq!(gomoku.outcome(), None);
+        play_all(&mut gomoku, "h8");
+        assert_eq!(gomoku.outcome(), Some(Outcome::Win(Player::X)));
+        assert!(gomoku.to_string().ends_with("Line: d4 e5 f6 g7 h8\n"));
+    }
+
+    #[test]
+    fn test_overline() {
+        // X fills the gap between a four and a one, making six
+        let moves = "a1 a15 b1 b15 c1 c15 d1 o15 f1 o14 e1";
+        let mut freestyle = Gomoku::default();
+        play_all(&mut freestyle, moves);
+        assert_eq!(freestyle.outcome(), Some(Outcome::Win(Player::X)));
+
+        let mut standard = Gomoku::new(GOMOKU_SIZE, Overline::DoesNotWin);
+        play_all(&mut standard, moves);
+        assert_eq!(standard.outcome(), None);
+    }
+
+    #[test]
+    fn test_computer_move() {
+        let mut gomoku = Gomoku::default();
+        assert_eq!(gomoku.computer_move(0), gomoku.parse_move("h8").ok());
+
+        // O has four in a row open at one end, so X must block
+        play_all(&mut gomoku, "h8 a1 h9 a2 k3 a3 k5 a4");
+        assert_eq!(gomoku.computer_move(0), gomoku.parse_move("a5").ok());
+
+        // X makes an open four rather than blocking O's three
+        let mut gomoku = Gomoku::default();
+        play_all(&mut gomoku, "h8 a1 h9 b1 h10 c1");
+        assert!(
+            [gomoku.parse_move("h7").ok(), gomoku.parse_move("h11").ok()]
+                .contains(&gomoku.computer_move(0))
+        );
+
+        // and takes a win when there is one
+        play_all(&mut gomoku, "h11 d1");
+        let win = gomoku.computer_move(0).unwrap();
+        gomoku.play(win);
+        assert_eq!(gomoku.outcome(), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn test_viewport() {
+        assert_eq!(
+            Viewport::for_terminal(80, 24),
+            Viewport { rows: 19, cols: 38 }
+        );
+
+        let mut gomoku = Gomoku::default();
+        assert_eq!(gomoku.to_string().lines().count(), 16);
+        gomoku.viewport = Some(Viewport { rows: 5, cols: 7 });
+        play_all(&mut gomoku, "n14");
+        let rendered = gomoku.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "   i j k l m n o");
+        assert_eq!(lines[3], "13 . . . . . . .");
+        assert_eq!(lines[4], "14 . . . . . X .");
+        assert_eq!(lines[6], "Showing i11 to o15 of 15x15");
+        assert_eq!(lines[7], "Last move: n14");
+    }
+}
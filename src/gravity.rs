@@ -0,0 +1,291 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::game_update::next_player;
+use crate::line_runs::{run, window_marks, DIRECTIONS};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::{score_lines, search_position};
+
+pub const DEFAULT_COLUMNS: usize = 7;
+pub const DEFAULT_ROWS: usize = 6;
+pub const DEFAULT_CONNECT: usize = 4;
+
+// how far ahead the computer looks at most, deeper searches take too long
+const SEARCH_DEPTH: usize = 7;
+
+// a column to drop a mark into, written as its letter
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Column(pub usize);
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", (b'a' + self.0 as u8) as char)
+    }
+}
+
+#[derive(Debug)]
+pub enum GravityMoveError {
+    NoSuchColumn(String),
+    GameOver,
+    ColumnFull(Column),
+}
+
+impl fmt::Display for GravityMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GravityMoveError::NoSuchColumn(input) => write!(f, "There is no column {:?}", input),
+            GravityMoveError::GameOver => write!(f, "The game is over"),
+            GravityMoveError::ColumnFull(column) => write!(f, "Column {} is full", column),
+        }
+    }
+}
+
+// marks fall to the lowest empty cell of the chosen column, and `connect`
+// in a row in any direction wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gravity {
+    pub board: Board,
+    pub player: Player,
+    pub connect: usize,
+    // set by the move that completes a line, so the search need not look for one
+    pub winner: Option<Player>,
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity::new(DEFAULT_COLUMNS, DEFAULT_ROWS, DEFAULT_CONNECT)
+    }
+}
+
+impl Gravity {
+    // a line can be no longer than the grid is wide or tall
+    pub fn new(num_cols: usize, num_rows: usize, connect: usize) -> Gravity {
+        Gravity {
+            board: Board::new(num_rows, num_cols),
+            player: Player::X,
+            connect: connect.min(num_cols.max(num_rows)),
+            winner: None,
+        }
+    }
+
+    // where a mark dropped into `column` comes to rest, None if it is full
+    pub fn landing(&self, column: Column) -> Option<CellId> {
+        (0..self.board.num_rows)
+            .rev()
+            .map(|row| CellId {
+                row,
+                column: column.0,
+            })
+            .find(|cell_id| self.board.cell(cell_id) == Cell::Unmarked)
+    }
+
+    // whether `player`'s mark at `cell_id` is part of a line of `connect`
+    fn completes_line(&self, cell_id: CellId, player: Player) -> bool {
+        DIRECTIONS.iter().any(|dir| {
+            let (ahead, _) = run(&self.board, cell_id, *dir, player);
+            let (behind, _) = run(&self.board, cell_id, (-dir.0, -dir.1), player);
+            ahead + behind + 1 >= self.connect
+        })
+    }
+}
+
+impl Position for Gravity {
+    type Move = Column;
+    type MoveError = GravityMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winner {
+            Some(p) => Some(Outcome::Win(p)),
+            None if self.board.is_full() => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+
+    // the middle columns first, as they are usually the better moves and
+    // trying them early lets the search cut off sooner
+    fn legal_moves(&self) -> Vec<Column> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        let middle = (self.board.num_cols as isize - 1) / 2;
+        let mut columns: Vec<Column> = (0..self.board.num_cols)
+            .map(Column)
+            .filter(|column| self.landing(*column).is_some())
+            .collect();
+        columns.sort_by_key(|Column(column)| (middle - *column as isize).abs());
+        columns
+    }
+
+    fn parse_move(&self, input: &str) -> Result<Column, GravityMoveError> {
+        let input = input.trim();
+        let mut chars = input.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => {
+                let column = c.to_ascii_lowercase() as usize - 'a' as usize;
+                if column < self.board.num_cols {
+                    return Ok(Column(column));
+                }
+            }
+            _ => {}
+        }
+        Err(GravityMoveError::NoSuchColumn(input.to_string()))
+    }
+
+    fn check_move(&self, mv: &Column) -> Result<(), GravityMoveError> {
+        if self.outcome().is_some() {
+            return Err(GravityMoveError::GameOver);
+        }
+        if self.landing(*mv).is_none() {
+            return Err(GravityMoveError::ColumnFull(*mv));
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: Column) {
+        if let Some(cell_id) = self.landing(mv) {
+            let idx = cell_id.to_idx(self.board.num_cols);
+            self.board.cells[idx] = Cell::Player(self.player);
+            if self.completes_line(cell_id, self.player) {
+                self.winner = Some(self.player);
+            }
+            self.player = next_player(self.player);
+        }
+    }
+
+    fn move_help(&self) -> String {
+        format!(
+            "Type the letter of a column from a to {}",
+            Column(self.board.num_cols - 1)
+        )
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<Column> {
+        search_position(self, depth.min(SEARCH_DEPTH))
+    }
+
+    // every stretch of `connect` cells only one player has marked could still
+    // become their line, and the more marks already in it the better
+    fn evaluate(&self, player: Player) -> i32 {
+        score_lines(window_marks(&self.board, self.connect), player)
+    }
+}
+
+// the grid with the column letters to drop into above it, e.g.
+//
+//  a b c d
+//  . . . .
+//  . X . .
+//  O X O .
+impl fmt::Display for Gravity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letters: Vec<String> = (0..self.board.num_cols)
+            .map(|column| Column(column).to_string())
+            .collect();
+        writeln!(f, " {}", letters.join(" "))?;
+        for row in 0..self.board.num_rows {
+            let glyphs: Vec<String> = (0..self.board.num_cols)
+                .map(|column| match self.board.cell(&CellId { row, column }) {
+                    Cell::Player(p) => p.to_string(),
+                    Cell::Unmarked => ".".to_string(),
+                })
+                .collect();
+            writeln!(f, " {}", glyphs.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::search_position::WIN_SCORE;
+
+    fn play_all(gravity: &mut Gravity, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = gravity.parse_move(mv_str).unwrap();
+            gravity.check_move(&mv).unwrap();
+            gravity.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let gravity = Gravity::default();
+        assert_eq!(gravity.parse_move(" D ").ok(), Some(Column(3)));
+        assert!(matches!(
+            gravity.parse_move("h"),
+            Err(GravityMoveError::NoSuchColumn(_))
+        ));
+        assert!(matches!(
+            gravity.parse_move("d1"),
+            Err(GravityMoveError::NoSuchColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_marks_fall() {
+        let mut gravity = Gravity::new(3, 2, 3);
+        assert_eq!(
+            gravity.landing(Column(1)),
+            Some(CellId { row: 1, column: 1 })
+        );
+        play_all(&mut gravity, "b b");
+        assert_eq!(gravity.landing(Column(1)), None);
+        assert_eq!(gravity.legal_moves(), [Column(0), Column(2)]);
+        assert!(matches!(
+            gravity.check_move(&Column(1)),
+            Err(GravityMoveError::ColumnFull(Column(1)))
+        ));
+        assert_eq!(gravity.to_string(), " a b c\n . O .\n . X .\n");
+    }
+
+    #[test]
+    fn test_connect_fits_the_grid() {
+        assert_eq!(Gravity::new(3, 2, 4).connect, 3);
+        assert_eq!(Gravity::new(2, 5, 9).connect, 5);
+    }
+
+    #[test]
+    fn test_evaluate_wide_grid() {
+        // with many long lines the score stays short of a win instead of overflowing
+        let mut gravity = Gravity::new(40, 40, 12);
+        play_all(&mut gravity, &"a b ".repeat(11));
+        assert!(gravity.evaluate(Player::X) < WIN_SCORE);
+    }
+
+    #[test]
+    fn test_connect_four() {
+        let mut gravity = Gravity::default();
+        play_all(&mut gravity, "d e d e d e");
+        assert_eq!(gravity.outcome(), None);
+        play_all(&mut gravity, "d");
+        assert_eq!(gravity.outcome(), Some(Outcome::Win(Player::X)));
+        assert_eq!(gravity.legal_moves(), []);
+
+        // a diagonal, built on a staircase
+        let mut gravity = Gravity::default();
+        play_all(&mut gravity, "a b b c c d c d d g d");
+        assert_eq!(gravity.outcome(), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn test_computer_move() {
+        let mut gravity = Gravity::default();
+        // X threatens to finish a row at either end, O can only block one
+        play_all(&mut gravity, "c c d d");
+        assert!([Some(Column(1)), Some(Column(4))].contains(&gravity.computer_move(0)));
+        // O blocks three in a column
+        let mut gravity = Gravity::default();
+        play_all(&mut gravity, "a g a g a");
+        assert_eq!(gravity.computer_move(0), Some(Column(0)));
+    }
+}
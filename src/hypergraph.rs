@@ -0,0 +1,428 @@
+use core::fmt;
+
+use crate::board::{Board, ParseBoardError};
+use crate::board_has_win::tracks;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::cells_are_valid::{marks_are_valid, CellsImpossibleError};
+use crate::game_update::{next_player, player_to_move};
+use crate::patterns::{ParsePatternsError, WinPatterns};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::{score_lines, search_position};
+use crate::solve_position::{score_outcome, solve_position, Solvable};
+use crate::theme::Theme;
+
+// more points have too many positions to solve exactly
+const MAX_SOLVED_POINTS: usize = 9;
+
+// the hypergraphs that come ready made
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Preset {
+    // the rows, columns and diagonals of the usual square board
+    #[default]
+    Board,
+    // the affine plane AG(2,3)
+    Affine,
+    // the Fano plane
+    Fano,
+}
+
+impl Preset {
+    pub fn hypergraph(self, size: usize) -> Hypergraph {
+        match self {
+            Preset::Board => Hypergraph::board(size),
+            Preset::Affine => Hypergraph::affine_plane(),
+            Preset::Fano => Hypergraph::fano_plane(),
+        }
+    }
+}
+
+// the board of a positional game: a set of points and the sets of them,
+// called edges, that a player wins by claiming all of. the points are laid
+// out on a grid, so they are named and drawn like the cells of a board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hypergraph {
+    pub num_rows: usize,
+    pub num_cols: usize,
+    // point indices, as cell indices of the grid
+    pub edges: Vec<Vec<usize>>,
+}
+
+impl Hypergraph {
+    // tic-tac-toe, whose edges are the lines of the board
+    pub fn board(size: usize) -> Hypergraph {
+        let board = Board::new(size, size);
+        Hypergraph {
+            num_rows: size,
+            num_cols: size,
+            edges: tracks(&board, false)
+                .map(|t| t.cell_idxs().to_vec())
+                .collect(),
+        }
+    }
+
+    // the cells of a `size` board with the patterns of a rule file as the
+    // edges, e.g. "shape a1 b1 a2 b2" for every 2x2 square
+    pub fn from_patterns(
+        patterns: &WinPatterns,
+        size: usize,
+    ) -> Result<Hypergraph, ParsePatternsError> {
+        Ok(Hypergraph {
+            num_rows: size,
+            num_cols: size,
+            edges: patterns.cell_sets(size, size)?,
+        })
+    }
+
+    // nine points and twelve lines, the rows, columns and diagonals of a
+    // 3x3 board with the diagonals wrapping around the edges
+    pub fn affine_plane() -> Hypergraph {
+        let board = Board::new(3, 3);
+        Hypergraph {
+            num_rows: 3,
+            num_cols: 3,
+            edges: tracks(&board, true)
+                .map(|t| t.cell_idxs().to_vec())
+                .collect(),
+        }
+    }
+
+    // seven points in a row and seven lines of three, any two of which
+    // share exactly one point
+    pub fn fano_plane() -> Hypergraph {
+        Hypergraph {
+            num_rows: 1,
+            num_cols: 7,
+            edges: vec![
+                vec![0, 1, 2],
+                vec![0, 3, 4],
+                vec![0, 5, 6],
+                vec![1, 3, 5],
+                vec![1, 4, 6],
+                vec![2, 3, 6],
+                vec![2, 4, 5],
+            ],
+        }
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.num_rows * self.num_cols
+    }
+
+    // the marks on each point, one character each as for a board
+    pub fn parse_marks(&self, marks_str: &str) -> Result<Board, ParseBoardError> {
+        if marks_str.is_empty() {
+            return Err(ParseBoardError::Empty);
+        }
+        if marks_str.chars().count() != self.num_points() {
+            return Err(ParseBoardError::BadLen);
+        }
+        let mut board = Board::new(self.num_rows, self.num_cols);
+        let mut char_errs: Vec<(usize, char)> = vec![];
+        for (idx, mark) in marks_str.chars().enumerate() {
+            match mark.to_string().parse::<Cell>() {
+                Ok(cell) => board.cells[idx] = cell,
+                Err(_) => char_errs.push((idx, mark)),
+            }
+        }
+        match char_errs.is_empty() {
+            true => Ok(board),
+            false => Err(ParseBoardError::BadChars(char_errs)),
+        }
+    }
+
+    // every edge one player has claimed all of, and who claimed it
+    pub fn claimed_edges<'a>(
+        &'a self,
+        board: &'a Board,
+    ) -> impl Iterator<Item = (Player, &'a Vec<usize>)> + 'a {
+        self.edges
+            .iter()
+            .filter_map(|edge| match board.cells[edge[0]] {
+                Cell::Player(p) if edge.iter().all(|idx| board.cells[*idx] == Cell::Player(p)) => {
+                    Some((p, edge))
+                }
+                _ => None,
+            })
+    }
+
+    pub fn claimed_edge<'a>(&'a self, board: &'a Board) -> Option<(Player, &'a Vec<usize>)> {
+        self.claimed_edges(board).next()
+    }
+}
+
+impl fmt::Display for Hypergraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let points: Vec<String> = edge
+                    .iter()
+                    .map(|idx| CellId::from_idx(*idx, self.num_cols).to_string())
+                    .collect();
+                points.join(" ")
+            })
+            .collect();
+        write!(f, "{}", edges.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub enum PositionalMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+}
+
+impl fmt::Display for PositionalMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionalMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That point is not on the board")
+            }
+            PositionalMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a point e.g. b1")
+            }
+            PositionalMoveError::GameOver => write!(f, "The game is over"),
+            PositionalMoveError::Occupied => write!(f, "That point is taken"),
+        }
+    }
+}
+
+// the strong positional game on any hypergraph: X and O take turns
+// claiming points and the first to claim every point of an edge wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Positional {
+    pub graph: Hypergraph,
+    pub board: Board,
+    pub player: Player,
+    pub last_move: Option<CellId>,
+    pub theme: Theme,
+}
+
+impl Positional {
+    pub fn new(graph: Hypergraph) -> Positional {
+        Positional {
+            board: Board::new(graph.num_rows, graph.num_cols),
+            graph,
+            player: Player::X,
+            last_move: None,
+            theme: Theme::default(),
+        }
+    }
+
+    // picks up a game from the marks on its points, which have to be ones
+    // the players could have made taking turns, stopping at the first edge
+    pub fn with_board(graph: Hypergraph, board: Board) -> Result<Positional, CellsImpossibleError> {
+        let winners: Vec<Player> = graph.claimed_edges(&board).map(|(p, _)| p).collect();
+        marks_are_valid(&board, Player::X, &winners)?;
+        Ok(Positional {
+            player: player_to_move(&board, Player::X, 2),
+            board,
+            ..Positional::new(graph)
+        })
+    }
+}
+
+impl Position for Positional {
+    type Move = CellId;
+    type MoveError = PositionalMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.graph.claimed_edge(&self.board) {
+            Some((p, _)) => Some(Outcome::Win(p)),
+            None if self.board.is_full() => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board.unmarked_cell_ids().collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, PositionalMoveError> {
+        CellId::parse_for(input.trim(), self.board.num_rows, self.board.num_cols)
+            .map_err(PositionalMoveError::BadCell)
+    }
+
+    fn check_move(&self, mv: &CellId) -> Result<(), PositionalMoveError> {
+        if self.outcome().is_some() {
+            return Err(PositionalMoveError::GameOver);
+        }
+        if self.board.cell(mv) != Cell::Unmarked {
+            return Err(PositionalMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: CellId) {
+        self.board.cells[mv.to_idx(self.board.num_cols)] = Cell::Player(self.player);
+        self.last_move = Some(mv);
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        "Type a point e.g. b1, claim every point of an edge to win".to_string()
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<CellId> {
+        match self.solve() {
+            Some((_, best_move)) => best_move,
+            None => search_position(self, depth),
+        }
+    }
+
+    // every edge only one player has claimed points of could still be won
+    // by them, and the more points already claimed the better
+    fn evaluate(&self, player: Player) -> i32 {
+        let edges = self.graph.edges.iter().map(|edge| {
+            edge.iter().filter_map(|idx| match self.board.cells[*idx] {
+                Cell::Player(p) => Some(p),
+                Cell::Unmarked => None,
+            })
+        });
+        score_lines(edges, player)
+    }
+
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<CellId>)> {
+        if self.graph.num_points() > MAX_SOLVED_POINTS {
+            return None;
+        }
+        Some(solve_position(self))
+    }
+}
+
+// who is to move follows from the marks, so the points are enough to tell
+// positions apart
+impl Solvable for Positional {
+    type Key = Vec<Cell>;
+
+    fn key(&self) -> Vec<Cell> {
+        self.board.cells.clone()
+    }
+
+    fn score(&self, player: Player) -> Option<i32> {
+        score_outcome(self.outcome(), &self.board, player)
+    }
+}
+
+// the points drawn as a board with the claimed edge picked out, and the
+// edges listed below, as the grid alone does not show them
+impl fmt::Display for Positional {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted = self
+            .graph
+            .claimed_edge(&self.board)
+            .map(|(_, edge)| edge.clone())
+            .unwrap_or_default();
+        write!(
+            f,
+            "{}",
+            self.theme
+                .render_board(&self.board, &highlighted, self.last_move)
+        )?;
+        writeln!(f, "Edges: {}", self.graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(positional: &mut Positional, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = positional.parse_move(mv_str).unwrap();
+            positional.check_move(&mv).unwrap();
+            positional.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(Hypergraph::board(3).edges.len(), 8);
+        assert_eq!(Hypergraph::affine_plane().edges.len(), 12);
+        // every pair of points is on exactly one line of either plane
+        for graph in [Hypergraph::affine_plane(), Hypergraph::fano_plane()] {
+            for a in 0..graph.num_points() {
+                for b in a + 1..graph.num_points() {
+                    let shared = graph
+                        .edges
+                        .iter()
+                        .filter(|edge| edge.contains(&a) && edge.contains(&b))
+                        .count();
+                    assert_eq!(shared, 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_patterns() {
+        let patterns = "shape a1 b1 a2 b2\npattern a1 c3".parse().unwrap();
+        let graph = Hypergraph::from_patterns(&patterns, 3).unwrap();
+        assert_eq!(graph.edges.len(), 5);
+        assert!(graph.edges.contains(&vec![0, 8]));
+        assert!(Hypergraph::from_patterns(&patterns, 2).is_err());
+    }
+
+    #[test]
+    fn test_fano_game() {
+        let mut positional = Positional::new(Hypergraph::fano_plane());
+        play_all(&mut positional, "a1 b1 d1 c1");
+        assert_eq!(positional.outcome(), None);
+        play_all(&mut positional, "e1");
+        assert_eq!(positional.outcome(), Some(Outcome::Win(Player::X)));
+        assert!(positional
+            .to_string()
+            .contains("Edges: a1 b1 c1, a1 d1 e1,"));
+        assert!(matches!(
+            positional.check_move(&positional.parse_move("f1").unwrap()),
+            Err(PositionalMoveError::GameOver)
+        ));
+        assert!(positional.parse_move("a2").is_err());
+    }
+
+    #[test]
+    fn test_with_board() {
+        let graph = Hypergraph::fano_plane();
+        let board = graph.parse_marks("XOOXX  ").unwrap();
+        let positional = Positional::with_board(graph.clone(), board).unwrap();
+        assert_eq!(positional.outcome(), Some(Outcome::Win(Player::X)));
+        let board = graph.parse_marks("XOOXXO ").unwrap();
+        assert!(matches!(
+            Positional::with_board(graph.clone(), board),
+            Err(CellsImpossibleError::OPlayAfterXWin)
+        ));
+        assert!(matches!(
+            graph.parse_marks("XO"),
+            Err(ParseBoardError::BadLen)
+        ));
+    }
+
+    #[test]
+    fn test_solve() {
+        // the first player wins on both planes, with lines everywhere
+        let (outcome, _) = Positional::new(Hypergraph::fano_plane()).solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        let (outcome, _) = Positional::new(Hypergraph::affine_plane()).solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        // and the usual board is a draw, as the board solver finds
+        let (outcome, _) = Positional::new(Hypergraph::board(3)).solve().unwrap();
+        assert_eq!(outcome, Outcome::Draw);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::player::Player;
+
+// the four ways a line can run: across, down and the two diagonals
+pub const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+// the cell `steps` away from `cell_id` in direction `dir`, if it is on the board
+pub fn step(
+    board: &Board,
+    cell_id: CellId,
+    (dr, dc): (isize, isize),
+    steps: isize,
+) -> Option<CellId> {
+    let row = cell_id.row.checked_add_signed(dr * steps)?;
+    let column = cell_id.column.checked_add_signed(dc * steps)?;
+    (row < board.num_rows && column < board.num_cols).then_some(CellId { row, column })
+}
+
+// how many of `player`'s marks run from `cell_id` in direction `dir`, not
+// counting `cell_id` itself, and whether the run ends on an empty cell
+pub fn run(board: &Board, cell_id: CellId, dir: (isize, isize), player: Player) -> (usize, bool) {
+    let mut length = 0;
+    loop {
+        match step(board, cell_id, dir, length as isize + 1) {
+            Some(next) if board.cell(&next) == Cell::Player(player) => length += 1,
+            Some(next) => return (length, board.cell(&next) == Cell::Unmarked),
+            None => return (length, false),
+        }
+    }
+}
+
+// the cells of the first unbroken run of one player's marks whose length
+// `wins`, for games won by getting some number in a row anywhere on the board
+pub fn find_run(board: &Board, wins: impl Fn(usize) -> bool) -> Option<Vec<CellId>> {
+    for idx in 0..board.num_cells() {
+        let cell_id = CellId::from_idx(idx, board.num_cols);
+        let Cell::Player(player) = board.cell(&cell_id) else {
+            continue;
+        };
+        for dir in DIRECTIONS {
+            // only count each run from its start
+            let (behind, _) = run(board, cell_id, (-dir.0, -dir.1), player);
+            if behind > 0 {
+                continue;
+            }
+            let (ahead, _) = run(board, cell_id, dir, player);
+            if wins(ahead + 1) {
+                return Some(
+                    (0..=ahead as isize)
+                        .filter_map(|k| step(board, cell_id, dir, k))
+                        .collect(),
+                );
+            }
+        }
+    }
+    None
+}
+
+// where every stretch of `length` cells in a line starts and which way it
+// runs, for scoring the lines a player could still make
+pub fn windows(
+    board: &Board,
+    length: usize,
+) -> impl Iterator<Item = (CellId, (isize, isize))> + '_ {
+    (0..board.num_cells()).flat_map(move |idx| {
+        let start = CellId::from_idx(idx, board.num_cols);
+        DIRECTIONS
+            .into_iter()
+            .filter(move |dir| step(board, start, *dir, length as isize - 1).is_some())
+            .map(move |dir| (start, dir))
+    })
+}
+
+// the players with marks in each of those stretches, to score with `score_lines`
+pub fn window_marks(
+    board: &Board,
+    length: usize,
+) -> impl Iterator<Item = impl Iterator<Item = Player> + '_> + '_ {
+    windows(board, length).map(move |(start, dir)| {
+        (0..length as isize).filter_map(move |k| match step(board, start, dir, k) {
+            Some(cell_id) => match board.cell(&cell_id) {
+                Cell::Player(p) => Some(p),
+                Cell::Unmarked => None,
+            },
+            None => None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let board = "XX O  OX ".parse::<Board>().unwrap();
+        let a1 = CellId { row: 0, column: 0 };
+        assert_eq!(run(&board, a1, (0, 1), Player::X), (1, true));
+        assert_eq!(run(&board, a1, (1, 0), Player::X), (0, false));
+        assert_eq!(step(&board, a1, (-1, 0), 1), None);
+    }
+
+    #[test]
+    fn test_find_run() {
+        let board = "OX OX  X ".parse::<Board>().unwrap();
+        let cells: Vec<String> = find_run(&board, |length| length >= 3)
+            .unwrap()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(cells, ["b1", "b2", "b3"]);
+        assert_eq!(find_run(&board, |length| length >= 4), None);
+    }
+
+    #[test]
+    fn test_windows() {
+        // 3 rows, 3 columns and 2 diagonals
+        assert_eq!(windows(&Board::new(3, 3), 3).count(), 8);
+        // 8 across, 8 down and 4 along each way of the diagonals
+        assert_eq!(windows(&Board::new(4, 4), 3).count(), 24);
+        assert_eq!(windows(&Board::new(2, 2), 3).count(), 0);
+    }
+
+    #[test]
+    fn test_window_marks() {
+        let board = "XX O  OX ".parse::<Board>().unwrap();
+        let marks: Vec<Vec<Player>> = window_marks(&board, 3).map(|m| m.collect()).collect();
+        assert_eq!(marks.len(), 8);
+        assert!(marks.contains(&vec![Player::X, Player::X]));
+        assert!(marks.contains(&vec![Player::X, Player::O, Player::O]));
+    }
+}
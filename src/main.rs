@@ -2,17 +2,71 @@ mod board;
 mod board_has_win;
 mod cell;
 mod cell_id;
+mod cell_input;
 mod cell_iterator;
 mod cells_are_valid;
+mod cli;
 mod column_iterator;
 mod diagonal_iterator;
+mod enumerate_boards;
 mod game;
+mod game_command;
+mod game_record;
 mod game_update;
+mod gomoku;
+mod gravity;
+mod hypergraph;
+mod line_runs;
+mod maker_breaker;
+mod misere;
+mod morris;
+mod multiplayer;
+mod notakto;
+mod numerical;
+mod order_chaos;
+mod patterns;
+mod phantom;
 mod player;
+mod position;
+mod quantum;
+mod qubic;
+mod retrograde;
 mod row_iterator;
+mod rules_board;
+mod ruleset;
+mod search_position;
+mod solve_position;
+mod theme;
+mod toroidal;
+#[cfg(feature = "tui")]
+mod tui;
+mod ultimate;
+mod vanishing;
+mod variant;
+mod wild;
 
-use crate::game::Game;
+use crate::cli::{parse_args, run_command, USAGE};
+use std::process::ExitCode;
 
-fn main() {
-    Game::new().run(&mut std::io::stdin().lock());
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, options) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run_command(
+        command,
+        options,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout(),
+    ) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
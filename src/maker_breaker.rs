@@ -0,0 +1,328 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::cells_are_valid::CellsImpossibleError;
+use crate::hypergraph::{Hypergraph, PositionalMoveError};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::search_position;
+use crate::solve_position::{solve_position, Solvable};
+use crate::theme::Theme;
+
+pub const DEFAULT_BIAS: usize = 1;
+
+// more points have too many positions to solve exactly
+const MAX_SOLVED_POINTS: usize = 16;
+
+const MAKER: Player = Player::X;
+const BREAKER: Player = Player::O;
+
+// Maker, playing X, moves first and wins by claiming every point of an edge.
+// Breaker, playing O, claims `bias` points a turn and wins by stopping them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MakerBreaker {
+    pub graph: Hypergraph,
+    pub board: Board,
+    pub bias: usize,
+    pub player: Player,
+    // how many more points Breaker claims this turn
+    pub moves_left: usize,
+    pub last_move: Option<CellId>,
+    pub theme: Theme,
+}
+
+impl MakerBreaker {
+    pub fn new(graph: Hypergraph, bias: usize) -> MakerBreaker {
+        MakerBreaker {
+            board: Board::new(graph.num_rows, graph.num_cols),
+            graph,
+            bias,
+            player: MAKER,
+            moves_left: 1,
+            last_move: None,
+            theme: Theme::default(),
+        }
+    }
+
+    // picks up a game from the marks on its points. Breaker has claimed
+    // `bias` points after each of Maker's but the last, and none once Maker
+    // has an edge. moves after Breaker had already blocked every edge are
+    // not looked for
+    pub fn with_board(
+        graph: Hypergraph,
+        board: Board,
+        bias: usize,
+    ) -> Result<MakerBreaker, CellsImpossibleError> {
+        let count = |player| {
+            let cell = Cell::Player(player);
+            board.cells.iter().filter(|c| **c == cell).count()
+        };
+        let (makers, breakers) = (count(MAKER), count(BREAKER));
+        if let Some(p) = board.cells.iter().find_map(|cell| match cell {
+            Cell::Player(p) if *p != MAKER && *p != BREAKER => Some(*p),
+            _ => None,
+        }) {
+            return Err(CellsImpossibleError::NotPlaying(p));
+        }
+        if breakers > makers * bias {
+            return Err(CellsImpossibleError::TooManyOs);
+        }
+        if makers > 0 && breakers < (makers - 1) * bias {
+            return Err(CellsImpossibleError::TooManyXs);
+        }
+        let mut maker_breaker = MakerBreaker::new(graph, bias);
+        maker_breaker.board = board;
+        if maker_breaker.makers_edge().is_some() && breakers > (makers - 1) * bias {
+            return Err(CellsImpossibleError::OPlayAfterXWin);
+        }
+        if makers > 0 && breakers < makers * bias {
+            maker_breaker.player = BREAKER;
+            maker_breaker.moves_left = makers * bias - breakers;
+        }
+        Ok(maker_breaker)
+    }
+
+    // Breaker's points count for nothing, even all the points of an edge
+    fn makers_edge(&self) -> Option<&Vec<usize>> {
+        self.graph
+            .claimed_edges(&self.board)
+            .find_map(|(p, edge)| (p == MAKER).then_some(edge))
+    }
+
+    // the edges Breaker has not claimed any point of, which Maker can still win
+    fn live_edges(&self) -> impl Iterator<Item = &Vec<usize>> {
+        self.graph.edges.iter().filter(|edge| {
+            edge.iter()
+                .all(|idx| self.board.cells[*idx] != Cell::Player(BREAKER))
+        })
+    }
+
+    // points on no live edge can no longer matter to either player
+    fn useful_moves(&self) -> Vec<CellId> {
+        let mut idxs: Vec<usize> = self
+            .live_edges()
+            .flatten()
+            .copied()
+            .filter(|idx| self.board.cells[*idx] == Cell::Unmarked)
+            .collect();
+        idxs.sort_unstable();
+        idxs.dedup();
+        idxs.into_iter()
+            .map(|idx| CellId::from_idx(idx, self.board.num_cols))
+            .collect()
+    }
+}
+
+impl Position for MakerBreaker {
+    type Move = CellId;
+    type MoveError = PositionalMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    // only Maker wins by claiming points, Breaker wins once every edge is
+    // blocked, so there are no draws
+    fn outcome(&self) -> Option<Outcome> {
+        if self.makers_edge().is_some() {
+            return Some(Outcome::Win(MAKER));
+        }
+        match self.live_edges().next() {
+            Some(_) => None,
+            None => Some(Outcome::Win(BREAKER)),
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board.unmarked_cell_ids().collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, PositionalMoveError> {
+        CellId::parse_for(input.trim(), self.board.num_rows, self.board.num_cols)
+            .map_err(PositionalMoveError::BadCell)
+    }
+
+    fn check_move(&self, mv: &CellId) -> Result<(), PositionalMoveError> {
+        if self.outcome().is_some() {
+            return Err(PositionalMoveError::GameOver);
+        }
+        if self.board.cell(mv) != Cell::Unmarked {
+            return Err(PositionalMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: CellId) {
+        self.board.cells[mv.to_idx(self.board.num_cols)] = Cell::Player(self.player);
+        self.last_move = Some(mv);
+        if self.player == MAKER {
+            self.player = BREAKER;
+            self.moves_left = self.bias;
+        } else {
+            self.moves_left -= 1;
+            if self.moves_left == 0 {
+                self.player = MAKER;
+                self.moves_left = 1;
+            }
+        }
+    }
+
+    fn move_help(&self) -> String {
+        format!(
+            "Type a point e.g. b1. X makes an edge to win, O claims {} a turn to stop them",
+            self.bias
+        )
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<CellId> {
+        match self.solve() {
+            Some((_, best_move)) => best_move,
+            None => search_position(self, depth),
+        }
+    }
+
+    // as in the Erdős–Selfridge criterion, each live edge is a threat that
+    // doubles with every point Maker has claimed in it
+    fn evaluate(&self, player: Player) -> i32 {
+        let danger: i32 = self
+            .live_edges()
+            .map(|edge| {
+                let claimed = edge
+                    .iter()
+                    .filter(|idx| self.board.cells[**idx] == Cell::Player(MAKER))
+                    .count();
+                1 << claimed.min(20)
+            })
+            .sum();
+        match player {
+            MAKER => danger,
+            _ => -danger,
+        }
+    }
+
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<CellId>)> {
+        if self.graph.num_points() > MAX_SOLVED_POINTS {
+            return None;
+        }
+        Some(solve_position(self))
+    }
+}
+
+// Breaker may have more points to claim this turn, so that is part of the key
+impl Solvable for MakerBreaker {
+    type Key = (Vec<Cell>, usize);
+
+    fn key(&self) -> (Vec<Cell>, usize) {
+        (self.board.cells.clone(), self.moves_left)
+    }
+
+    // there are no draws, and only whether Maker can win is asked, so all
+    // wins score the same, which lets far more of the search be cut off
+    fn score(&self, player: Player) -> Option<i32> {
+        self.outcome().map(|outcome| match outcome {
+            Outcome::Win(p) if p == player => 1,
+            _ => -1,
+        })
+    }
+
+    fn moves_to_solve(&self) -> Vec<CellId> {
+        self.useful_moves()
+    }
+}
+
+// the points drawn as a board with Maker's edge picked out, and the edges
+// Maker can still win listed below
+impl fmt::Display for MakerBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted = self.makers_edge().cloned().unwrap_or_default();
+        write!(
+            f,
+            "{}",
+            self.theme
+                .render_board(&self.board, &highlighted, self.last_move)
+        )?;
+        let live = Hypergraph {
+            edges: self.live_edges().cloned().collect(),
+            ..self.graph.clone()
+        };
+        writeln!(f, "Open edges: {}", live)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(maker_breaker: &mut MakerBreaker, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = maker_breaker.parse_move(mv_str).unwrap();
+            maker_breaker.check_move(&mv).unwrap();
+            maker_breaker.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_breaker_claims_bias_points() {
+        let mut maker_breaker = MakerBreaker::new(Hypergraph::board(3), 2);
+        play_all(&mut maker_breaker, "b2 a1");
+        assert_eq!(maker_breaker.player, BREAKER);
+        play_all(&mut maker_breaker, "c3");
+        assert_eq!(maker_breaker.player, MAKER);
+        play_all(&mut maker_breaker, "a2 c2 b1 b3 a3");
+        // every line has an O in it
+        assert_eq!(maker_breaker.outcome(), Some(Outcome::Win(BREAKER)));
+    }
+
+    #[test]
+    fn test_only_maker_makes_lines() {
+        let mut maker_breaker = MakerBreaker::new(Hypergraph::board(3), 1);
+        play_all(&mut maker_breaker, "a2 a1 c3 b1 b2 c1");
+        // Breaker's row does not count
+        assert_eq!(maker_breaker.outcome(), None);
+        play_all(&mut maker_breaker, "c2");
+        assert_eq!(maker_breaker.outcome(), Some(Outcome::Win(MAKER)));
+        assert!(maker_breaker.to_string().contains("Open edges: a2 b2 c2"));
+    }
+
+    #[test]
+    fn test_with_board() {
+        let graph = Hypergraph::board(3);
+        let board = graph.parse_marks("OX  X O  ").unwrap();
+        let maker_breaker = MakerBreaker::with_board(graph.clone(), board, 2).unwrap();
+        assert_eq!(maker_breaker.player, BREAKER);
+        assert_eq!(maker_breaker.moves_left, 2);
+        let board = graph.parse_marks("OXO X O  ").unwrap();
+        assert!(matches!(
+            MakerBreaker::with_board(graph.clone(), board, 1),
+            Err(CellsImpossibleError::TooManyOs)
+        ));
+        let board = graph.parse_marks("XXXOOOOO ").unwrap();
+        assert!(matches!(
+            MakerBreaker::with_board(graph.clone(), board, 2),
+            Err(CellsImpossibleError::OPlayAfterXWin)
+        ));
+    }
+
+    #[test]
+    fn test_solve() {
+        let (outcome, best_move) = MakerBreaker::new(Hypergraph::board(3), 1).solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(MAKER));
+        assert!(best_move.is_some());
+        // two points a turn are enough for Breaker
+        let (outcome, _) = MakerBreaker::new(Hypergraph::board(3), 2).solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(BREAKER));
+        let (outcome, _) = MakerBreaker::new(Hypergraph::board(4), 1).solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(BREAKER));
+    }
+}
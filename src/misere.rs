@@ -0,0 +1,51 @@
+use crate::board::Board;
+use crate::board_has_win::board_has_win;
+use crate::cell::Cell;
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::ruleset::{ClassicRules, Outcome, Ruleset};
+use crate::variant::Variant;
+
+// completing a line loses. The game still ends as soon as a line is
+// completed, so the same boards come up as in the classic game and only
+// who they are good for changes
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MisereRules;
+
+impl Ruleset for MisereRules {
+    fn variant(&self) -> Variant {
+        Variant::Misere
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        match board_has_win(board) {
+            Cell::Player(p) => Some(Outcome::Win(next_player(p))),
+            Cell::Unmarked if board.is_full() => Some(Outcome::Draw),
+            Cell::Unmarked => None,
+        }
+    }
+
+    // lines filling up are bad news for whoever is filling them
+    fn evaluate(&self, board: &Board, player: Player) -> i32 {
+        -ClassicRules.evaluate(board, player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_misere_outcome() {
+        let board = "XXXOO    ".parse::<Board>().unwrap();
+        assert_eq!(MisereRules.outcome(&board), Some(Outcome::Win(Player::O)));
+        assert_eq!(MisereRules.winning_line(&board), Some(vec![0, 1, 2]));
+
+        let board = "XOXXOXOXO".parse::<Board>().unwrap();
+        assert_eq!(MisereRules.outcome(&board), Some(Outcome::Draw));
+
+        let board = "X  O     ".parse::<Board>().unwrap();
+        assert_eq!(MisereRules.evaluate(&board, Player::X), -1);
+    }
+}
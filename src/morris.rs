@@ -0,0 +1,398 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::{board_has_win, winning_line};
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::retrograde::solve_with_loops;
+use crate::ruleset::Outcome;
+use crate::theme::Theme;
+
+// how many marks each player places before they start moving them
+pub const PIECES: usize = 3;
+
+// the same board with the same player to move this many times is a draw
+const REPETITIONS: usize = 3;
+
+// which cells a mark can slide to
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Adjacency {
+    // up, down, left or right
+    #[default]
+    Orthogonal,
+    // diagonally as well
+    Diagonal,
+}
+
+impl Adjacency {
+    fn allows(self, from: CellId, to: CellId) -> bool {
+        let rows = from.row.abs_diff(to.row);
+        let columns = from.column.abs_diff(to.column);
+        match self {
+            Adjacency::Orthogonal => rows + columns == 1,
+            Adjacency::Diagonal => rows.max(columns) == 1,
+        }
+    }
+}
+
+// a new mark, e.g. `b2`, or one of the player's marks moved to a
+// neighbouring cell, e.g. `a1-b1`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MorrisMove {
+    pub from: Option<CellId>,
+    pub to: CellId,
+}
+
+impl fmt::Display for MorrisMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.from {
+            Some(from) => write!(f, "{}-{}", from, self.to),
+            None => write!(f, "{}", self.to),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MorrisMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+    // placing while marks are still to be placed, moving after
+    StillPlacing,
+    MustMove,
+    NotYours(CellId),
+    NotAdjacent(CellId, CellId),
+}
+
+impl fmt::Display for MorrisMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MorrisMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            MorrisMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a cell e.g. b2, or two e.g. a1-b1")
+            }
+            MorrisMoveError::GameOver => write!(f, "The game is over"),
+            MorrisMoveError::Occupied => write!(f, "That cell is occupied"),
+            MorrisMoveError::StillPlacing => {
+                write!(f, "Place all {} of your marks before moving them", PIECES)
+            }
+            MorrisMoveError::MustMove => {
+                write!(f, "All your marks are placed, move one e.g. a1-b1")
+            }
+            MorrisMoveError::NotYours(cell) => write!(f, "{} is not one of your marks", cell),
+            MorrisMoveError::NotAdjacent(from, to) => {
+                write!(f, "{} is not next to {}", to, from)
+            }
+        }
+    }
+}
+
+// three men's morris: each player places three marks, then moves one of
+// them to a neighbouring empty cell each turn until someone has a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Morris {
+    pub board: Board,
+    pub player: Player,
+    pub adjacency: Adjacency,
+    // every board so far and who was to move on it, to spot repetition
+    pub seen: Vec<(Vec<Cell>, Player)>,
+    pub theme: Theme,
+}
+
+impl Default for Morris {
+    fn default() -> Self {
+        Morris::new(Adjacency::default())
+    }
+}
+
+impl Morris {
+    pub fn new(adjacency: Adjacency) -> Morris {
+        let board = Board::default();
+        Morris {
+            seen: vec![(board.cells.clone(), Player::X)],
+            board,
+            player: Player::X,
+            adjacency,
+            theme: Theme::default(),
+        }
+    }
+
+    fn marks(&self, player: Player) -> Vec<CellId> {
+        (0..self.board.num_cells())
+            .filter(|idx| self.board.cells[*idx] == Cell::Player(player))
+            .map(|idx| CellId::from_idx(idx, self.board.num_cols))
+            .collect()
+    }
+
+    pub fn is_placing(&self) -> bool {
+        self.marks(self.player).len() < PIECES
+    }
+
+    fn repetitions(&self) -> usize {
+        let current = (self.board.cells.clone(), self.player);
+        self.seen.iter().filter(|seen| **seen == current).count()
+    }
+
+    // the moves the rules allow, whether or not the game is already over
+    fn moves(&self) -> Vec<MorrisMove> {
+        if self.is_placing() {
+            return self
+                .board
+                .unmarked_cell_ids()
+                .map(|to| MorrisMove { from: None, to })
+                .collect();
+        }
+        let mut moves = vec![];
+        for from in self.marks(self.player) {
+            for to in self.board.unmarked_cell_ids() {
+                if self.adjacency.allows(from, to) {
+                    moves.push(MorrisMove {
+                        from: Some(from),
+                        to,
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    fn key(&self) -> u32 {
+        let cells = self.board.cells.iter().fold(0, |key, cell| {
+            key * 3
+                + match cell {
+                    Cell::Unmarked => 0,
+                    Cell::Player(p) => p.idx() as u32 + 1,
+                }
+        });
+        cells * 2 + (self.player == Player::O) as u32
+    }
+}
+
+impl Position for Morris {
+    type Move = MorrisMove;
+    type MoveError = MorrisMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    // a player who cannot move loses
+    fn outcome(&self) -> Option<Outcome> {
+        if let Cell::Player(p) = board_has_win(&self.board) {
+            return Some(Outcome::Win(p));
+        }
+        if self.repetitions() >= REPETITIONS {
+            return Some(Outcome::Draw);
+        }
+        if self.moves().is_empty() {
+            return Some(Outcome::Win(next_player(self.player)));
+        }
+        None
+    }
+
+    fn legal_moves(&self) -> Vec<MorrisMove> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.moves()
+    }
+
+    // one cell to place a mark, or two joined by `-` or a space to move one
+    fn parse_move(&self, input: &str) -> Result<MorrisMove, MorrisMoveError> {
+        let parse = |word: &str| {
+            CellId::parse_for(word.trim(), self.board.num_rows, self.board.num_cols)
+                .map_err(MorrisMoveError::BadCell)
+        };
+        let input = input.trim();
+        let cells = input
+            .split_once('-')
+            .or_else(|| input.split_once(char::is_whitespace));
+        match cells {
+            Some((from, to)) => Ok(MorrisMove {
+                from: Some(parse(from)?),
+                to: parse(to)?,
+            }),
+            None => Ok(MorrisMove {
+                from: None,
+                to: parse(input)?,
+            }),
+        }
+    }
+
+    fn check_move(&self, mv: &MorrisMove) -> Result<(), MorrisMoveError> {
+        if self.outcome().is_some() {
+            return Err(MorrisMoveError::GameOver);
+        }
+        match (mv.from, self.is_placing()) {
+            (Some(_), true) => return Err(MorrisMoveError::StillPlacing),
+            (None, false) => return Err(MorrisMoveError::MustMove),
+            (Some(from), false) => {
+                if self.board.cell(&from) != Cell::Player(self.player) {
+                    return Err(MorrisMoveError::NotYours(from));
+                }
+                if !self.adjacency.allows(from, mv.to) {
+                    return Err(MorrisMoveError::NotAdjacent(from, mv.to));
+                }
+            }
+            (None, true) => {}
+        }
+        if self.board.cell(&mv.to) != Cell::Unmarked {
+            return Err(MorrisMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: MorrisMove) {
+        if let Some(from) = mv.from {
+            self.board.cells[from.to_idx(self.board.num_cols)] = Cell::Unmarked;
+        }
+        self.board.cells[mv.to.to_idx(self.board.num_cols)] = Cell::Player(self.player);
+        self.player = next_player(self.player);
+        self.seen.push((self.board.cells.clone(), self.player));
+    }
+
+    fn move_help(&self) -> String {
+        match self.is_placing() {
+            true => "Type a cell to place a mark e.g. b2".to_string(),
+            false => "Type one of your marks and where it goes e.g. a1-b1".to_string(),
+        }
+    }
+
+    fn computer_move(&self, _depth: usize) -> Option<MorrisMove> {
+        self.solve().and_then(|(_, best_move)| best_move)
+    }
+
+    // marks move back and forth, so the game tree has loops
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<MorrisMove>)> {
+        if let Some(outcome) = self.outcome() {
+            return Some((outcome, None));
+        }
+        Some(solve_with_loops(self, Morris::key, |morris| {
+            morris.seen.clear()
+        }))
+    }
+}
+
+impl fmt::Display for Morris {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted = winning_line(&self.board).unwrap_or_default();
+        write!(
+            f,
+            "{}",
+            self.theme.render_board(&self.board, &highlighted, None)
+        )?;
+        let to_place: Vec<String> = [Player::X, Player::O]
+            .iter()
+            .map(|player| (player, PIECES - self.marks(*player).len()))
+            .filter(|(_, left)| *left > 0)
+            .map(|(player, left)| format!("{} {}", player, left))
+            .collect();
+        if !to_place.is_empty() {
+            writeln!(f, "Marks left to place: {}", to_place.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(morris: &mut Morris, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = morris.parse_move(mv_str).unwrap();
+            morris.check_move(&mv).unwrap();
+            morris.play(mv);
+        }
+    }
+
+    // all six marks placed without a line, and X to move
+    const PLACED: &str = "a1 b1 c1 a2 b3 c2";
+
+    #[test]
+    fn test_parse_move() {
+        let morris = Morris::default();
+        assert_eq!(morris.parse_move("B2").unwrap().to_string(), "b2");
+        assert_eq!(morris.parse_move("a1 - b1").unwrap().to_string(), "a1-b1");
+        assert_eq!(morris.parse_move("a1 b1").unwrap().to_string(), "a1-b1");
+        assert!(matches!(
+            morris.check_move(&morris.parse_move("a1-b1").unwrap()),
+            Err(MorrisMoveError::StillPlacing)
+        ));
+    }
+
+    #[test]
+    fn test_moving_marks() {
+        let mut morris = Morris::default();
+        play_all(&mut morris, PLACED);
+        assert!(!morris.is_placing());
+        assert!(matches!(
+            morris.check_move(&morris.parse_move("b2").unwrap()),
+            Err(MorrisMoveError::MustMove)
+        ));
+        assert!(matches!(
+            morris.check_move(&morris.parse_move("b1-b2").unwrap()),
+            Err(MorrisMoveError::NotYours(_))
+        ));
+        assert!(matches!(
+            morris.check_move(&morris.parse_move("a1-b2").unwrap()),
+            Err(MorrisMoveError::NotAdjacent(_, _))
+        ));
+        // only b3 has anywhere to go
+        let moves: Vec<String> = morris
+            .legal_moves()
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect();
+        assert_eq!(moves, ["b3-b2", "b3-a3", "b3-c3"]);
+
+        let mut morris = Morris::new(Adjacency::Diagonal);
+        play_all(&mut morris, PLACED);
+        assert!(morris
+            .check_move(&morris.parse_move("a1-b2").unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_repetition_draws() {
+        let mut morris = Morris::default();
+        play_all(&mut morris, PLACED);
+        let shuffle = "b3-b2 a2-a3 b2-b3 a3-a2";
+        play_all(&mut morris, shuffle);
+        assert_eq!(morris.outcome(), None);
+        play_all(&mut morris, shuffle);
+        assert_eq!(morris.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn test_solve() {
+        // X slides into a line
+        let mut morris = Morris::default();
+        play_all(&mut morris, "a1 a2 b1 b2 c2 b3");
+        let (outcome, best_move) = morris.solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        assert_eq!(
+            best_move.map(|mv| mv.to_string()),
+            Some("c2-c1".to_string())
+        );
+        morris.play(best_move.unwrap());
+        assert_eq!(morris.outcome(), Some(Outcome::Win(Player::X)));
+
+        // from the start X wins when marks only move orthogonally, and it is
+        // a draw when they can move diagonally as well
+        let (outcome, _) = Morris::default().solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        let (outcome, _) = Morris::new(Adjacency::Diagonal).solve().unwrap();
+        assert_eq!(outcome, Outcome::Draw);
+    }
+}
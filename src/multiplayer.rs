@@ -0,0 +1,301 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::cells_are_valid::{mark_counts_are_valid, CellsImpossibleError};
+use crate::game_update::next_in_turn;
+use crate::line_runs::{find_run, run, window_marks, DIRECTIONS};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::{score_lines, WIN_SCORE};
+use crate::theme::Theme;
+
+pub const DEFAULT_NUM_PLAYERS: usize = 3;
+
+// every extra player multiplies the moves to look through, so the computer looks less far ahead
+const SEARCH_DEPTH: usize = 3;
+
+#[derive(Debug)]
+pub enum MultiplayerMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+}
+
+impl fmt::Display for MultiplayerMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MultiplayerMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            MultiplayerMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a cell e.g. b2")
+            }
+            MultiplayerMoveError::GameOver => write!(f, "The game is over"),
+            MultiplayerMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+// more than two players take turns in order, X, O, Δ and so on, and the
+// first to get `connect` in a row wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiplayer {
+    pub board: Board,
+    pub num_players: usize,
+    pub connect: usize,
+    pub player: Player,
+    // set by the move that completes a line, so the search need not look for one
+    pub winner: Option<Player>,
+    pub last_move: Option<CellId>,
+    pub theme: Theme,
+}
+
+impl Multiplayer {
+    pub fn new(size: usize, num_players: usize, connect: usize) -> Multiplayer {
+        Multiplayer {
+            board: Board::new(size, size),
+            num_players,
+            connect: connect.min(size),
+            player: Player::X,
+            winner: None,
+            last_move: None,
+            theme: Theme::default(),
+        }
+    }
+
+    // picks up a game from its board, which has to be one the players could
+    // have reached taking turns, with nobody moving after a line was made
+    pub fn with_board(
+        board: Board,
+        num_players: usize,
+        connect: usize,
+    ) -> Result<Multiplayer, CellsImpossibleError> {
+        let last = mark_counts_are_valid(&board, num_players)?;
+        let mut multiplayer = Multiplayer::new(board.num_rows, num_players, connect);
+        multiplayer.board = board;
+        for idx in 0..multiplayer.board.num_cells() {
+            let cell_id = CellId::from_idx(idx, multiplayer.board.num_cols);
+            let Cell::Player(p) = multiplayer.board.cell(&cell_id) else {
+                continue;
+            };
+            if multiplayer.completes_line(cell_id, p) {
+                // only the last player to move can have a line
+                if Some(p) != last {
+                    return Err(CellsImpossibleError::PlayAfterWin(p));
+                }
+                multiplayer.winner = Some(p);
+            }
+        }
+        multiplayer.player = last.map_or(Player::X, |p| next_in_turn(p, num_players));
+        Ok(multiplayer)
+    }
+
+    // whether `player`'s mark at `cell_id` is part of a line of `connect`
+    fn completes_line(&self, cell_id: CellId, player: Player) -> bool {
+        DIRECTIONS.iter().any(|dir| {
+            let (ahead, _) = run(&self.board, cell_id, *dir, player);
+            let (behind, _) = run(&self.board, cell_id, (-dir.0, -dir.1), player);
+            ahead + behind + 1 >= self.connect
+        })
+    }
+
+    // with more than two players one player's gain is not simply another's
+    // loss, so the search assumes everyone else plays against `player`
+    fn paranoid(&self, player: Player, depth: usize, mut alpha: i32, mut beta: i32) -> i32 {
+        match self.outcome() {
+            Some(Outcome::Win(p)) if p == player => return WIN_SCORE + depth as i32,
+            Some(Outcome::Win(_)) => return -WIN_SCORE - depth as i32,
+            Some(Outcome::Draw) => return 0,
+            None if depth == 0 => return self.evaluate(player),
+            None => {}
+        }
+        let maximising = self.player == player;
+        let mut best = if maximising { i32::MIN } else { i32::MAX };
+        for mv in self.legal_moves() {
+            let mut next = self.clone();
+            next.play(mv);
+            let score = next.paranoid(player, depth - 1, alpha, beta);
+            if maximising {
+                best = best.max(score);
+                alpha = alpha.max(score);
+            } else {
+                best = best.min(score);
+                beta = beta.min(score);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl Position for Multiplayer {
+    type Move = CellId;
+    type MoveError = MultiplayerMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn num_players(&self) -> usize {
+        self.num_players
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winner {
+            Some(p) => Some(Outcome::Win(p)),
+            None if self.board.is_full() => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board.unmarked_cell_ids().collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, MultiplayerMoveError> {
+        CellId::parse_for(input.trim(), self.board.num_rows, self.board.num_cols)
+            .map_err(MultiplayerMoveError::BadCell)
+    }
+
+    fn check_move(&self, mv: &CellId) -> Result<(), MultiplayerMoveError> {
+        if self.outcome().is_some() {
+            return Err(MultiplayerMoveError::GameOver);
+        }
+        if self.board.cell(mv) != Cell::Unmarked {
+            return Err(MultiplayerMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: CellId) {
+        self.board.cells[mv.to_idx(self.board.num_cols)] = Cell::Player(self.player);
+        if self.completes_line(mv, self.player) {
+            self.winner = Some(self.player);
+        }
+        self.last_move = Some(mv);
+        self.player = next_in_turn(self.player, self.num_players);
+    }
+
+    fn move_help(&self) -> String {
+        format!("Type a cell e.g. b2, {} in a row wins", self.connect)
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<CellId> {
+        let player = self.player;
+        let depth = depth.clamp(1, SEARCH_DEPTH);
+        let mut best: Option<(i32, CellId)> = None;
+        for mv in self.legal_moves() {
+            let mut next = self.clone();
+            next.play(mv);
+            let alpha = best.map_or(i32::MIN, |(score, _)| score);
+            let score = next.paranoid(player, depth - 1, alpha, i32::MAX);
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, mv));
+            }
+        }
+        best.map(|(_, mv)| mv)
+    }
+
+    // every stretch of `connect` cells only one player has marked could still
+    // become their line, and the more marks already in it the better
+    fn evaluate(&self, player: Player) -> i32 {
+        score_lines(window_marks(&self.board, self.connect), player)
+    }
+}
+
+// the board drawn with the theme's glyphs, so each player's marks can be told apart
+impl fmt::Display for Multiplayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted: Vec<usize> = match self.winner {
+            Some(_) => find_run(&self.board, |length| length >= self.connect)
+                .unwrap_or_default()
+                .iter()
+                .map(|cell_id| cell_id.to_idx(self.board.num_cols))
+                .collect(),
+            None => vec![],
+        };
+        write!(
+            f,
+            "{}",
+            self.theme
+                .render_board(&self.board, &highlighted, self.last_move)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(multiplayer: &mut Multiplayer, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = multiplayer.parse_move(mv_str).unwrap();
+            multiplayer.check_move(&mv).unwrap();
+            multiplayer.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_turns_go_round() {
+        let mut multiplayer = Multiplayer::new(4, 3, 3);
+        play_all(&mut multiplayer, "a1 b1 c1");
+        assert_eq!(multiplayer.board.to_string(), "XOΔ             ");
+        assert_eq!(multiplayer.player, Player::X);
+        play_all(&mut multiplayer, "a2 b2 c2 a3");
+        assert_eq!(multiplayer.outcome(), Some(Outcome::Win(Player::X)));
+        assert!(multiplayer.to_string().contains("3 |[X]|   |   |   |"));
+    }
+
+    #[test]
+    fn test_third_player_wins() {
+        let mut multiplayer = Multiplayer::new(5, 3, 3);
+        play_all(&mut multiplayer, "a1 e5 c3 a5 e1 d3 b5 a4");
+        assert_eq!(multiplayer.outcome(), None);
+        play_all(&mut multiplayer, "b3");
+        assert_eq!(multiplayer.outcome(), Some(Outcome::Win(Player::nth(2))));
+    }
+
+    #[test]
+    fn test_with_board() {
+        let board = Board::parse_cells("XOΔ X           ").unwrap();
+        let multiplayer = Multiplayer::with_board(board, 3, 3).unwrap();
+        assert_eq!(multiplayer.player, Player::O);
+
+        let board = Board::parse_cells("XOΔΔ            ").unwrap();
+        assert!(matches!(
+            Multiplayer::with_board(board, 3, 3),
+            Err(CellsImpossibleError::TooManyMarks(p)) if p == Player::nth(2)
+        ));
+        let board = Board::parse_cells("XXXOOΔΔO        ").unwrap();
+        assert!(matches!(
+            Multiplayer::with_board(board, 3, 3),
+            Err(CellsImpossibleError::PlayAfterWin(Player::X))
+        ));
+        let board = Board::parse_cells("XXXOOΔΔ□        ").unwrap();
+        assert!(matches!(
+            Multiplayer::with_board(board, 3, 3),
+            Err(CellsImpossibleError::NotPlaying(_))
+        ));
+    }
+
+    #[test]
+    fn test_computer_move() {
+        // O takes the win rather than blocking X
+        let mut multiplayer = Multiplayer::new(4, 3, 3);
+        play_all(&mut multiplayer, "a1 a2 d4 b1 b2 d3 d1");
+        assert_eq!(multiplayer.computer_move(3), "c2".parse().ok());
+        // Δ cannot win yet, so blocks X, who is next
+        let mut multiplayer = Multiplayer::new(4, 3, 3);
+        play_all(&mut multiplayer, "a1 d4 c4 b1 a4");
+        assert_eq!(multiplayer.computer_move(3), "c1".parse().ok());
+    }
+}
@@ -0,0 +1,509 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::board_has_win;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+// every board is 3x3
+const SIZE: usize = 3;
+
+pub const DEFAULT_NUM_BOARDS: usize = 3;
+
+// a cell on one of the boards, written `2/b2` with the boards numbered from 1
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NotaktoMove {
+    pub board: usize,
+    pub cell: CellId,
+}
+
+impl fmt::Display for NotaktoMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.board + 1, self.cell)
+    }
+}
+
+#[derive(Debug)]
+pub enum NotaktoMoveError {
+    BadCell(CellIdError),
+    // numbered from 1, as typed
+    NoSuchBoard(usize),
+    GameOver,
+    DeadBoard(usize),
+    Occupied,
+}
+
+impl fmt::Display for NotaktoMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotaktoMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            NotaktoMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a board and a cell e.g. 2/b2")
+            }
+            NotaktoMoveError::NoSuchBoard(board) => write!(f, "There is no board {}", board),
+            NotaktoMoveError::GameOver => write!(f, "The game is over"),
+            NotaktoMoveError::DeadBoard(board) => write!(f, "Board {} is dead", board + 1),
+            NotaktoMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+// an element of the misère quotient of Notakto found by Plambeck and Whitehead,
+// a^a b^b c^c d^d with a² = 1, b³ = b, b²c = c, c³ = ac², b²d = d, cd = ad, d² = c²
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct QuotientValue {
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+}
+
+const fn q(a: u8, b: u8, c: u8, d: u8) -> QuotientValue {
+    QuotientValue { a, b, c, d }
+}
+
+const ONE: QuotientValue = q(0, 0, 0, 0);
+
+impl QuotientValue {
+    fn times(self, other: QuotientValue) -> QuotientValue {
+        let (mut a, mut b, mut c, mut d) = (
+            self.a + other.a,
+            self.b + other.b,
+            self.c + other.c,
+            self.d + other.d,
+        );
+        loop {
+            let before = (a, b, c, d);
+            if b >= 3 {
+                b -= 2;
+            }
+            if b >= 2 && (c >= 1 || d >= 1) {
+                b -= 2;
+            }
+            if c >= 3 {
+                c -= 1;
+                a += 1;
+            }
+            if c >= 1 && d >= 1 {
+                c -= 1;
+                a += 1;
+            }
+            if d >= 2 {
+                d -= 2;
+                c += 2;
+            }
+            a %= 2;
+            if (a, b, c, d) == before {
+                return q(a, b, c, d);
+            }
+        }
+    }
+
+    // the player to move loses exactly when the boards multiply out to a, b², bc or c²
+    fn is_p(self) -> bool {
+        [q(1, 0, 0, 0), q(0, 2, 0, 0), q(0, 1, 1, 0), q(0, 0, 2, 0)].contains(&self)
+    }
+}
+
+// the value of every live board, by the marks on it as bits (a1 is bit 0,
+// b1 bit 1 ...) and only for the smallest bits among its rotations and reflections
+const BOARD_VALUES: [(u16, QuotientValue); 46] = [
+    (0, q(0, 0, 1, 0)),
+    (1, q(0, 0, 0, 0)),
+    (2, q(0, 0, 0, 0)),
+    (3, q(0, 0, 0, 1)),
+    (5, q(0, 1, 0, 0)),
+    (10, q(1, 0, 0, 0)),
+    (11, q(0, 1, 0, 0)),
+    (12, q(0, 1, 0, 0)),
+    (13, q(1, 0, 0, 0)),
+    (14, q(1, 0, 0, 1)),
+    (16, q(0, 0, 2, 0)),
+    (17, q(0, 1, 0, 0)),
+    (18, q(0, 1, 0, 0)),
+    (19, q(1, 1, 0, 0)),
+    (21, q(1, 0, 0, 0)),
+    (26, q(1, 1, 0, 0)),
+    (27, q(1, 0, 0, 0)),
+    (28, q(1, 0, 0, 0)),
+    (29, q(0, 1, 0, 0)),
+    (30, q(0, 1, 0, 0)),
+    (40, q(1, 0, 0, 0)),
+    (41, q(1, 0, 0, 1)),
+    (42, q(0, 1, 0, 0)),
+    (43, q(1, 0, 0, 0)),
+    (45, q(0, 1, 0, 0)),
+    (68, q(1, 0, 0, 0)),
+    (69, q(1, 1, 0, 0)),
+    (70, q(1, 0, 0, 1)),
+    (78, q(1, 1, 0, 0)),
+    (97, q(1, 0, 0, 0)),
+    (98, q(0, 0, 0, 0)),
+    (99, q(0, 1, 0, 0)),
+    (101, q(0, 1, 0, 0)),
+    (102, q(1, 0, 0, 0)),
+    (106, q(1, 1, 0, 0)),
+    (108, q(1, 0, 0, 0)),
+    (110, q(0, 1, 0, 0)),
+    (113, q(0, 1, 0, 0)),
+    (114, q(0, 1, 0, 0)),
+    (115, q(1, 0, 0, 0)),
+    (170, q(1, 0, 0, 0)),
+    (171, q(0, 1, 0, 0)),
+    (173, q(1, 0, 0, 0)),
+    (229, q(1, 0, 0, 0)),
+    (238, q(1, 0, 0, 0)),
+    (325, q(1, 0, 0, 0)),
+];
+
+fn board_bits(board: &Board) -> u16 {
+    board
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| **cell != Cell::Unmarked)
+        .map(|(idx, _)| 1 << idx)
+        .sum()
+}
+
+// the smallest bits among the eight rotations and reflections of a board
+fn canonical_bits(bits: u16) -> u16 {
+    let rotate = |idx: usize| (idx % SIZE) * SIZE + (SIZE - 1 - idx / SIZE);
+    let reflect = |idx: usize| (idx / SIZE) * SIZE + (SIZE - 1 - idx % SIZE);
+    let mut perm: Vec<usize> = (0..SIZE * SIZE).collect();
+    let mut smallest = u16::MAX;
+    for _ in 0..4 {
+        for p in [perm.clone(), perm.iter().map(|idx| reflect(*idx)).collect()] {
+            let moved = (0..SIZE * SIZE)
+                .filter(|idx| bits & (1 << idx) != 0)
+                .map(|idx| 1 << p[idx])
+                .sum();
+            smallest = smallest.min(moved);
+        }
+        perm = perm.iter().map(|idx| rotate(*idx)).collect();
+    }
+    smallest
+}
+
+fn is_dead(board: &Board) -> bool {
+    board_has_win(board) != Cell::Unmarked
+}
+
+// a dead board takes no part in the game, which is the same as it not being there
+fn board_value(board: &Board) -> QuotientValue {
+    if is_dead(board) {
+        return ONE;
+    }
+    let bits = canonical_bits(board_bits(board));
+    BOARD_VALUES
+        .iter()
+        .find(|(b, _)| *b == bits)
+        .map(|(_, value)| *value)
+        .expect("every live board has a value")
+}
+
+// both players mark X, a board dies once it has three in a row,
+// and whoever kills the last live board loses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notakto {
+    pub boards: Vec<Board>,
+    pub player: Player,
+}
+
+impl Notakto {
+    pub fn new(num_boards: usize) -> Notakto {
+        Notakto {
+            boards: vec![Board::default(); num_boards],
+            player: Player::X,
+        }
+    }
+
+    pub fn live_boards(&self) -> Vec<usize> {
+        (0..self.boards.len())
+            .filter(|board| !is_dead(&self.boards[*board]))
+            .collect()
+    }
+
+    fn value(&self) -> QuotientValue {
+        self.boards
+            .iter()
+            .fold(ONE, |value, board| value.times(board_value(board)))
+    }
+
+    // whether the player to move loses against perfect play
+    pub fn is_lost(&self) -> bool {
+        self.value().is_p()
+    }
+}
+
+impl Position for Notakto {
+    type Move = NotaktoMove;
+    type MoveError = NotaktoMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        // the previous player killed the last board
+        self.live_boards()
+            .is_empty()
+            .then_some(Outcome::Win(self.player))
+    }
+
+    fn legal_moves(&self) -> Vec<NotaktoMove> {
+        self.live_boards()
+            .into_iter()
+            .flat_map(|board| {
+                self.boards[board]
+                    .unmarked_cell_ids()
+                    .map(move |cell| NotaktoMove { board, cell })
+                    .collect::<Vec<NotaktoMove>>()
+            })
+            .collect()
+    }
+
+    // the board may be left out when there is only one
+    fn parse_move(&self, input: &str) -> Result<NotaktoMove, NotaktoMoveError> {
+        let (board, cell_str) = match input.trim().split_once('/') {
+            Some((board_str, cell_str)) => match board_str.trim().parse::<usize>() {
+                Ok(board) if (1..=self.boards.len()).contains(&board) => (board - 1, cell_str),
+                Ok(board) => return Err(NotaktoMoveError::NoSuchBoard(board)),
+                Err(_) => return Err(NotaktoMoveError::BadCell(CellIdError::UnparseableInput)),
+            },
+            None if self.boards.len() == 1 => (0, input),
+            None => return Err(NotaktoMoveError::BadCell(CellIdError::UnparseableInput)),
+        };
+        let cell =
+            CellId::parse_for(cell_str.trim(), SIZE, SIZE).map_err(NotaktoMoveError::BadCell)?;
+        Ok(NotaktoMove { board, cell })
+    }
+
+    fn check_move(&self, mv: &NotaktoMove) -> Result<(), NotaktoMoveError> {
+        if self.outcome().is_some() {
+            return Err(NotaktoMoveError::GameOver);
+        }
+        let board = self
+            .boards
+            .get(mv.board)
+            .ok_or(NotaktoMoveError::NoSuchBoard(mv.board + 1))?;
+        if is_dead(board) {
+            return Err(NotaktoMoveError::DeadBoard(mv.board));
+        }
+        if board.cell(&mv.cell) != Cell::Unmarked {
+            return Err(NotaktoMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: NotaktoMove) {
+        self.boards[mv.board].cells[mv.cell.to_idx(SIZE)] = Cell::Player(Player::X);
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        match self.boards.len() {
+            1 => "Type a cell a1-c3 e.g. b2, everyone plays X".to_string(),
+            n => format!(
+                "Type a board 1-{} and a cell a1-c3 on it e.g. 1/b2, everyone plays X",
+                n
+            ),
+        }
+    }
+
+    // leaves the opponent a losing position when there is one, otherwise
+    // keeps as many boards alive as possible and hopes for a mistake
+    fn computer_move(&self, _depth: usize) -> Option<NotaktoMove> {
+        let moves = self.legal_moves();
+        let after = |mv: &NotaktoMove| {
+            let mut next = self.clone();
+            next.play(*mv);
+            next
+        };
+        moves
+            .iter()
+            .find(|mv| after(mv).is_lost())
+            .or_else(|| {
+                moves
+                    .iter()
+                    .find(|mv| !is_dead(&after(mv).boards[mv.board]))
+            })
+            .or(moves.first())
+            .copied()
+    }
+}
+
+// the boards are drawn side by side, e.g.
+//
+//    1        2
+//    a b c    a b c
+// 1  X . .    X X X
+// 2  . . .    . . .
+// 3  . . .    . . .
+//             dead
+impl fmt::Display for Notakto {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let gap = "    ";
+        let numbers: Vec<String> = (0..self.boards.len())
+            .map(|board| format!("{:<5}", board + 1))
+            .collect();
+        writeln!(f, "   {}", numbers.join(gap).trim_end())?;
+        writeln!(f, "   {}", vec!["a b c"; self.boards.len()].join(gap))?;
+        for row in 0..SIZE {
+            let blocks: Vec<String> = self
+                .boards
+                .iter()
+                .map(|board| {
+                    let glyphs: Vec<String> = (0..SIZE)
+                        .map(|column| match board.cell(&CellId { row, column }) {
+                            Cell::Player(p) => p.to_string(),
+                            Cell::Unmarked => ".".to_string(),
+                        })
+                        .collect();
+                    glyphs.join(" ")
+                })
+                .collect();
+            writeln!(f, "{}  {}", row + 1, blocks.join(gap))?;
+        }
+        if self.boards.iter().any(is_dead) {
+            let labels: Vec<String> = self
+                .boards
+                .iter()
+                .map(|board| format!("{:<5}", if is_dead(board) { "dead" } else { "" }))
+                .collect();
+            writeln!(f, "   {}", labels.join(gap).trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(notakto: &mut Notakto, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = notakto.parse_move(mv_str).unwrap();
+            notakto.check_move(&mv).unwrap();
+            notakto.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_quotient_values() {
+        let (a, b, c, d) = (q(1, 0, 0, 0), q(0, 1, 0, 0), q(0, 0, 1, 0), q(0, 0, 0, 1));
+        assert_eq!(a.times(a), ONE);
+        assert_eq!(b.times(b).times(b), b);
+        assert_eq!(b.times(b).times(c), c);
+        assert_eq!(c.times(c).times(c), a.times(c).times(c));
+        assert_eq!(c.times(d), a.times(d));
+        assert_eq!(d.times(d), c.times(c));
+        assert!(c.times(c).is_p());
+        assert!(!c.is_p());
+
+        // the quotient has 18 elements
+        let mut values = vec![ONE];
+        let mut idx = 0;
+        while idx < values.len() {
+            for generator in [a, b, c, d] {
+                let value = values[idx].times(generator);
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            idx += 1;
+        }
+        assert_eq!(values.len(), 18);
+    }
+
+    #[test]
+    fn test_canonical_bits() {
+        // every corner is the same as a1, every edge the same as b1
+        assert_eq!(canonical_bits(1 << 8), 1);
+        assert_eq!(canonical_bits(1 << 5), 2);
+        assert_eq!(canonical_bits(0), 0);
+        for (bits, _) in BOARD_VALUES {
+            assert_eq!(canonical_bits(bits), bits);
+        }
+    }
+
+    #[test]
+    fn test_known_results() {
+        // the first player wins on one or three boards, the second on two
+        assert!(!Notakto::new(1).is_lost());
+        assert!(Notakto::new(2).is_lost());
+        assert!(!Notakto::new(3).is_lost());
+
+        // on one board the winning move is the center
+        let notakto = Notakto::new(1);
+        let mv = notakto.computer_move(0).unwrap();
+        assert_eq!(mv.cell, CellId { row: 1, column: 1 });
+    }
+
+    #[test]
+    fn test_dead_boards() {
+        let mut notakto = Notakto::new(2);
+        play_all(&mut notakto, "1/a1 1/b1 1/c1");
+        assert_eq!(notakto.live_boards(), vec![1]);
+        assert_eq!(notakto.outcome(), None);
+        assert!(matches!(
+            notakto.check_move(&notakto.parse_move("1/a2").unwrap()),
+            Err(NotaktoMoveError::DeadBoard(0))
+        ));
+        assert!(notakto.legal_moves().iter().all(|mv| mv.board == 1));
+
+        // O kills the last board, so X wins
+        play_all(&mut notakto, "2/a1 2/a2 2/a3");
+        assert_eq!(notakto.outcome(), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let notakto = Notakto::new(2);
+        let mv = notakto.parse_move("2/B2").unwrap();
+        assert_eq!(mv.to_string(), "2/b2");
+        assert!(matches!(
+            notakto.parse_move("3/b2"),
+            Err(NotaktoMoveError::NoSuchBoard(3))
+        ));
+        assert!(notakto.parse_move("b2").is_err());
+        assert_eq!(
+            Notakto::new(1).parse_move("b2").unwrap().to_string(),
+            "1/b2"
+        );
+    }
+
+    #[test]
+    fn test_computer_wins_from_a_won_position() {
+        // whenever the computer is handed a winning position it keeps it
+        let mut notakto = Notakto::new(3);
+        while notakto.outcome().is_none() {
+            assert!(!notakto.is_lost());
+            let mv = notakto.computer_move(0).unwrap();
+            notakto.play(mv);
+            assert!(notakto.is_lost());
+            if notakto.outcome().is_some() {
+                break;
+            }
+            // the other side just plays the first move it finds
+            let mv = notakto.legal_moves()[0];
+            notakto.play(mv);
+        }
+        assert_eq!(notakto.outcome(), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn test_display() {
+        let mut notakto = Notakto::new(2);
+        play_all(&mut notakto, "2/a1 2/b1 2/c1");
+        let expected = "   1        2\n   a b c    a b c\n1  . . .    X X X\n2  . . .    . . .\n3  . . .    . . .\n            dead\n";
+        assert_eq!(notakto.to_string(), expected);
+    }
+}
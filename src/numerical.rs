@@ -0,0 +1,471 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::board::Board;
+use crate::board_has_win::tracks;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::search_position;
+
+// bigger boards have too many positions to solve exactly
+const MAX_SOLVED_SIZE: usize = 3;
+
+// a number and where it goes, e.g. `5 b2`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NumberMove {
+    pub number: u16,
+    pub cell: CellId,
+}
+
+impl fmt::Display for NumberMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.number, self.cell)
+    }
+}
+
+#[derive(Debug)]
+pub enum NumberMoveError {
+    BadNumber(String),
+    BadCell(CellIdError),
+    GameOver,
+    // odd numbers are X's and even numbers are O's
+    NotYours(u16, Player),
+    AlreadyUsed(u16),
+    Occupied,
+}
+
+impl fmt::Display for NumberMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumberMoveError::BadNumber(input) => {
+                write!(f, "{:?} does not start with a number e.g. 5 b2", input)
+            }
+            NumberMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            NumberMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a number and a cell e.g. 5 b2")
+            }
+            NumberMoveError::GameOver => write!(f, "The game is over"),
+            NumberMoveError::NotYours(number, player) => {
+                write!(f, "{} is not one of {}'s numbers", number, player)
+            }
+            NumberMoveError::AlreadyUsed(number) => write!(f, "{} has been used", number),
+            NumberMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+// X plays the odd numbers and O the even ones, from 1 up to the number of
+// cells, and whoever fills a line that adds up to the magic sum wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Numerical {
+    // who wrote in each cell, so the board's lines can be walked as usual
+    pub board: Board,
+    // what was written in each cell
+    pub numbers: Vec<Option<u16>>,
+    pub player: Player,
+}
+
+impl Default for Numerical {
+    fn default() -> Self {
+        Numerical::new(3)
+    }
+}
+
+impl Numerical {
+    pub fn new(size: usize) -> Numerical {
+        Numerical {
+            board: Board::new(size, size),
+            numbers: vec![None; size * size],
+            player: Player::X,
+        }
+    }
+
+    // what a full line has to add up to, 15 on a 3x3 board
+    pub fn target(&self) -> u32 {
+        let size = self.board.num_rows as u32;
+        size * (size * size + 1) / 2
+    }
+
+    fn owner(number: u16) -> Player {
+        match number % 2 {
+            1 => Player::X,
+            _ => Player::O,
+        }
+    }
+
+    // the numbers `player` has not written yet
+    pub fn unused_numbers(&self, player: Player) -> Vec<u16> {
+        (1..=self.board.num_cells() as u16)
+            .filter(|n| Numerical::owner(*n) == player && !self.numbers.contains(&Some(*n)))
+            .collect()
+    }
+
+    pub fn winning_line(&self) -> Option<Vec<usize>> {
+        tracks(&self.board, false)
+            .map(|track| track.cell_idxs().to_vec())
+            .find(|idxs| {
+                let line: Option<Vec<u16>> = idxs.iter().map(|idx| self.numbers[*idx]).collect();
+                line.is_some_and(|line| {
+                    line.iter().map(|n| *n as u32).sum::<u32>() == self.target()
+                })
+            })
+    }
+
+    // a move for the player to move that completes a line, if there is one
+    pub fn winning_move(&self) -> Option<NumberMove> {
+        self.legal_moves().into_iter().find(|mv| {
+            let mut next = self.clone();
+            next.play(*mv);
+            next.winning_line().is_some()
+        })
+    }
+}
+
+// an exact search over just the numbers, which is far quicker than playing
+// out whole positions. values are 1 if the player to move wins, -1 if they
+// lose and 0 for a draw
+struct Solver {
+    // the cells of every line, and which lines go through each cell
+    lines: Vec<Vec<usize>>,
+    lines_through: Vec<Vec<usize>>,
+    target: u32,
+    max_number: u16,
+    table: HashMap<u64, i8>,
+}
+
+impl Solver {
+    fn new(numerical: &Numerical) -> Solver {
+        let lines: Vec<Vec<usize>> = tracks(&numerical.board, false)
+            .map(|track| track.cell_idxs().to_vec())
+            .collect();
+        let lines_through = (0..numerical.board.num_cells())
+            .map(|idx| {
+                (0..lines.len())
+                    .filter(|l| lines[*l].contains(&idx))
+                    .collect()
+            })
+            .collect();
+        Solver {
+            lines,
+            lines_through,
+            target: numerical.target(),
+            max_number: numerical.board.num_cells() as u16,
+            table: HashMap::new(),
+        }
+    }
+
+    // each cell's number in four bits
+    fn key(cells: &[u16]) -> u64 {
+        cells.iter().fold(0, |key, n| key << 4 | *n as u64)
+    }
+
+    fn is_unused(&self, cells: &[u16], number: u16, player: Player) -> bool {
+        (1..=self.max_number).contains(&number)
+            && Numerical::owner(number) == player
+            && !cells.contains(&number)
+    }
+
+    // whether writing `number` at `idx` finishes a line
+    fn completes_line(&self, cells: &[u16], idx: usize, number: u16) -> bool {
+        self.lines_through[idx].iter().any(|l| {
+            let line = &self.lines[*l];
+            line.iter().all(|i| *i == idx || cells[*i] != 0)
+                && line
+                    .iter()
+                    .map(|i| if *i == idx { number } else { cells[*i] } as u32)
+                    .sum::<u32>()
+                    == self.target
+        })
+    }
+
+    // a line with one cell left that one of `player`'s numbers would finish
+    fn winning_move(&self, cells: &[u16], player: Player) -> Option<(usize, u16)> {
+        self.lines.iter().find_map(|line| {
+            let empty: Vec<&usize> = line.iter().filter(|i| cells[**i] == 0).collect();
+            let [idx] = empty[..] else {
+                return None;
+            };
+            let sum: u32 = line.iter().map(|i| cells[*i] as u32).sum();
+            let number = u16::try_from(self.target.checked_sub(sum)?).ok()?;
+            self.is_unused(cells, number, player)
+                .then_some((*idx, number))
+        })
+    }
+
+    fn moves(&self, cells: &[u16], player: Player) -> Vec<(usize, u16)> {
+        let numbers: Vec<u16> = (1..=self.max_number)
+            .filter(|n| self.is_unused(cells, *n, player))
+            .collect();
+        (0..cells.len())
+            .filter(|idx| cells[*idx] == 0)
+            .flat_map(|idx| numbers.iter().map(move |n| (idx, *n)))
+            .collect()
+    }
+
+    // the value of a position no line has been finished in yet
+    fn value(&mut self, cells: &mut Vec<u16>, player: Player) -> i8 {
+        if self.winning_move(cells, player).is_some() {
+            return 1;
+        }
+        let key = Solver::key(cells);
+        if let Some(value) = self.table.get(&key) {
+            return *value;
+        }
+        let mut best = None;
+        for (idx, number) in self.moves(cells, player) {
+            cells[idx] = number;
+            let value = -self.value(cells, next_player(player));
+            cells[idx] = 0;
+            best = best.max(Some(value));
+            if value == 1 {
+                break;
+            }
+        }
+        // no moves left is a draw
+        let best = best.unwrap_or(0);
+        self.table.insert(key, best);
+        best
+    }
+}
+
+impl Position for Numerical {
+    type Move = NumberMove;
+    type MoveError = NumberMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    // the game stops as soon as a line adds up, so it was made by the last player
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winning_line() {
+            Some(_) => Some(Outcome::Win(next_player(self.player))),
+            None if self.board.is_full() || self.unused_numbers(self.player).is_empty() => {
+                Some(Outcome::Draw)
+            }
+            None => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<NumberMove> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        let numbers = self.unused_numbers(self.player);
+        self.board
+            .unmarked_cell_ids()
+            .flat_map(|cell| {
+                numbers.iter().map(move |number| NumberMove {
+                    number: *number,
+                    cell,
+                })
+            })
+            .collect()
+    }
+
+    // the number and then the cell, with or without a space between them
+    fn parse_move(&self, input: &str) -> Result<NumberMove, NumberMoveError> {
+        let input = input.trim();
+        let digits_end = input
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len());
+        let number = input[..digits_end]
+            .parse::<u16>()
+            .ok()
+            .filter(|n| (1..=self.board.num_cells()).contains(&(*n as usize)))
+            .ok_or_else(|| NumberMoveError::BadNumber(input.to_string()))?;
+        let cell = CellId::parse_for(
+            input[digits_end..].trim(),
+            self.board.num_rows,
+            self.board.num_cols,
+        )
+        .map_err(NumberMoveError::BadCell)?;
+        Ok(NumberMove { number, cell })
+    }
+
+    fn check_move(&self, mv: &NumberMove) -> Result<(), NumberMoveError> {
+        if self.outcome().is_some() {
+            return Err(NumberMoveError::GameOver);
+        }
+        if Numerical::owner(mv.number) != self.player {
+            return Err(NumberMoveError::NotYours(mv.number, self.player));
+        }
+        if self.numbers.contains(&Some(mv.number)) {
+            return Err(NumberMoveError::AlreadyUsed(mv.number));
+        }
+        if self.board.cell(&mv.cell) != Cell::Unmarked {
+            return Err(NumberMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: NumberMove) {
+        let idx = mv.cell.to_idx(self.board.num_cols);
+        self.board.cells[idx] = Cell::Player(self.player);
+        self.numbers[idx] = Some(mv.number);
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        format!(
+            "Type one of your numbers then a cell e.g. 5 b2, lines adding up to {} win",
+            self.target()
+        )
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<NumberMove> {
+        match self.solve() {
+            Some((_, best_move)) => best_move,
+            None => self.winning_move().or_else(|| search_position(self, depth)),
+        }
+    }
+
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<NumberMove>)> {
+        if self.board.num_rows > MAX_SOLVED_SIZE {
+            return None;
+        }
+        if let Some(outcome) = self.outcome() {
+            return Some((outcome, None));
+        }
+        let mut solver = Solver::new(self);
+        let mut cells: Vec<u16> = self.numbers.iter().map(|n| n.unwrap_or(0)).collect();
+        let mut best: Option<(i8, NumberMove)> = None;
+        for (idx, number) in solver.moves(&cells, self.player) {
+            let value = if solver.completes_line(&cells, idx, number) {
+                1
+            } else {
+                cells[idx] = number;
+                let value = -solver.value(&mut cells, next_player(self.player));
+                cells[idx] = 0;
+                value
+            };
+            if best.is_none_or(|(best_value, _)| value > best_value) {
+                let cell = CellId::from_idx(idx, self.board.num_cols);
+                best = Some((value, NumberMove { number, cell }));
+            }
+            if value == 1 {
+                break;
+            }
+        }
+        let (value, best_move) = best?;
+        let outcome = match value {
+            1 => Outcome::Win(self.player),
+            -1 => Outcome::Win(next_player(self.player)),
+            _ => Outcome::Draw,
+        };
+        Some((outcome, Some(best_move)))
+    }
+}
+
+// the numbers on the grid, then what each player has left, e.g.
+//
+//    a b c
+// 1  . 5 .
+// 2  . . 8
+// 3  . . .
+// X: 1 3 7 9
+// O: 2 4 6
+impl fmt::Display for Numerical {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.board.num_cells().to_string().len();
+        let letters: Vec<String> = (0..self.board.num_cols)
+            .map(|column| format!("{:>width$}", (b'a' + column as u8) as char))
+            .collect();
+        writeln!(f, "   {}", letters.join(" "))?;
+        for row in 0..self.board.num_rows {
+            let numbers: Vec<String> = (0..self.board.num_cols)
+                .map(|column| {
+                    let idx = CellId { row, column }.to_idx(self.board.num_cols);
+                    match self.numbers[idx] {
+                        Some(n) => format!("{:>width$}", n),
+                        None => format!("{:>width$}", "."),
+                    }
+                })
+                .collect();
+            writeln!(f, "{:<2} {}", row + 1, numbers.join(" "))?;
+        }
+        for player in [Player::X, Player::O] {
+            let unused: Vec<String> = self
+                .unused_numbers(player)
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            writeln!(f, "{}: {}", player, unused.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(numerical: &mut Numerical, moves: &str) {
+        for mv_str in moves.split(',') {
+            let mv = numerical.parse_move(mv_str).unwrap();
+            numerical.check_move(&mv).unwrap();
+            numerical.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let numerical = Numerical::default();
+        assert_eq!(numerical.parse_move("5 B2").unwrap().to_string(), "5 b2");
+        assert_eq!(numerical.parse_move("7a3").unwrap().to_string(), "7 a3");
+        assert!(matches!(
+            numerical.parse_move("10 a1"),
+            Err(NumberMoveError::BadNumber(_))
+        ));
+        assert!(matches!(
+            numerical.parse_move("b2"),
+            Err(NumberMoveError::BadNumber(_))
+        ));
+        assert!(matches!(
+            numerical.check_move(&numerical.parse_move("4 a1").unwrap()),
+            Err(NumberMoveError::NotYours(4, Player::X))
+        ));
+    }
+
+    #[test]
+    fn test_lines_adding_up() {
+        let mut numerical = Numerical::default();
+        assert_eq!(numerical.target(), 15);
+        play_all(&mut numerical, "1 a1, 6 b1");
+        assert!(matches!(
+            numerical.check_move(&numerical.parse_move("1 c1").unwrap()),
+            Err(NumberMoveError::AlreadyUsed(1))
+        ));
+        assert_eq!(numerical.outcome(), None);
+        // either player may finish a line with the other's numbers in it
+        play_all(&mut numerical, "3 c3, 8 c1");
+        assert_eq!(numerical.outcome(), Some(Outcome::Win(Player::O)));
+        assert_eq!(numerical.winning_line(), Some(vec![0, 1, 2]));
+        assert!(numerical.to_string().starts_with("   a b c\n1  1 6 8\n"));
+    }
+
+    #[test]
+    fn test_solve() {
+        // O can finish a line at once
+        let mut numerical = Numerical::default();
+        play_all(&mut numerical, "9 a1, 2 a2, 1 c1");
+        let (outcome, best_move) = numerical.solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::O));
+        assert_eq!(best_move.map(|mv| mv.to_string()), Some("4 a3".to_string()));
+
+        // and the first player wins from the start
+        let (outcome, _) = Numerical::default().solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+    }
+}
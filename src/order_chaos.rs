@@ -0,0 +1,194 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::game_update::next_player;
+use crate::line_runs::{find_run, step, windows};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::search_position;
+use crate::theme::Theme;
+use crate::wild::{parse_mark_move, Mark, WildMove, WildMoveError};
+
+pub const ORDER_CHAOS_SIZE: usize = 6;
+const LINE_LENGTH: usize = 5;
+
+// X plays Order and moves first, O plays Chaos
+pub const ORDER: Player = Player::X;
+pub const CHAOS: Player = Player::O;
+
+// both players may draw either mark. Order wins with five of a mark in a
+// row, and Chaos wins if the board fills up without one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderChaos {
+    pub board: Board,
+    pub player: Player,
+    pub theme: Theme,
+}
+
+impl Default for OrderChaos {
+    fn default() -> Self {
+        OrderChaos {
+            board: Board::new(ORDER_CHAOS_SIZE, ORDER_CHAOS_SIZE),
+            player: ORDER,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Position for OrderChaos {
+    type Move = WildMove;
+    type MoveError = WildMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if find_run(&self.board, |length| length >= LINE_LENGTH).is_some() {
+            Some(Outcome::Win(ORDER))
+        } else if self.board.is_full() {
+            Some(Outcome::Win(CHAOS))
+        } else {
+            None
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<WildMove> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board
+            .unmarked_cell_ids()
+            .flat_map(|cell| Mark::ALL.map(|mark| WildMove { mark, cell }))
+            .collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<WildMove, WildMoveError> {
+        parse_mark_move(input, self.board.num_rows, self.board.num_cols)
+    }
+
+    fn check_move(&self, mv: &WildMove) -> Result<(), WildMoveError> {
+        if self.outcome().is_some() {
+            return Err(WildMoveError::GameOver);
+        }
+        if self.board.cell(&mv.cell) != Cell::Unmarked {
+            return Err(WildMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: WildMove) {
+        let idx = mv.cell.to_idx(self.board.num_cols);
+        self.board.cells[idx] = mv.mark.cell();
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        "Type the mark you want, X or O, then a cell e.g. X c3".to_string()
+    }
+
+    // with two marks to choose from there are too many moves to look far,
+    // so each side only considers the other's reply
+    fn computer_move(&self, depth: usize) -> Option<WildMove> {
+        search_position(self, depth.min(2))
+    }
+
+    // every stretch of five holding only one kind of mark could still become
+    // Order's line, more so the more marks it has. Chaos wants the opposite
+    fn evaluate(&self, player: Player) -> i32 {
+        let mut score = 0;
+        for (start, dir) in windows(&self.board, LINE_LENGTH) {
+            let (mut xs, mut os) = (0, 0);
+            for k in 0..LINE_LENGTH as isize {
+                match step(&self.board, start, dir, k).map(|c| self.board.cell(&c)) {
+                    Some(Cell::Player(Player::X)) => xs += 1,
+                    Some(Cell::Player(Player::O)) => os += 1,
+                    _ => {}
+                }
+            }
+            if xs == 0 || os == 0 {
+                score += 4_i32.pow(xs.max(os));
+            }
+        }
+        if player == ORDER {
+            score
+        } else {
+            -score
+        }
+    }
+}
+
+impl fmt::Display for OrderChaos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted: Vec<usize> = find_run(&self.board, |length| length >= LINE_LENGTH)
+            .unwrap_or_default()
+            .iter()
+            .map(|cell_id| cell_id.to_idx(self.board.num_cols))
+            .collect();
+        write!(
+            f,
+            "{}",
+            self.theme.render_board(&self.board, &highlighted, None)
+        )?;
+        writeln!(f, "{} plays Order, {} plays Chaos", ORDER, CHAOS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(order_chaos: &mut OrderChaos, moves: &str) {
+        for mv_str in moves.split(',') {
+            let mv = order_chaos.parse_move(mv_str).unwrap();
+            order_chaos.check_move(&mv).unwrap();
+            order_chaos.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_order_wins_with_five() {
+        let mut order_chaos = OrderChaos::default();
+        // Chaos helps by drawing Os too
+        play_all(&mut order_chaos, "O a1, O b2, O c3, X f1, O d4");
+        assert_eq!(order_chaos.outcome(), None);
+        play_all(&mut order_chaos, "X a6, O e5");
+        assert_eq!(order_chaos.outcome(), Some(Outcome::Win(ORDER)));
+    }
+
+    #[test]
+    fn test_chaos_wins_a_full_board() {
+        // rows of XXXOOO and OOOXXX alternating in pairs never line up five
+        let cells: String = ["XXXOOO", "XXXOOO", "OOOXXX", "OOOXXX", "XXXOOO", "XXXOO "].concat();
+        let mut order_chaos = OrderChaos {
+            board: cells.parse().unwrap(),
+            player: CHAOS,
+            ..OrderChaos::default()
+        };
+        assert_eq!(order_chaos.outcome(), None);
+        play_all(&mut order_chaos, "O f6");
+        assert_eq!(order_chaos.outcome(), Some(Outcome::Win(CHAOS)));
+    }
+
+    #[test]
+    fn test_computer_roles() {
+        // Order completes four Xs in a row
+        let mut order_chaos = OrderChaos::default();
+        play_all(
+            &mut order_chaos,
+            "X a1, O f6, X b1, O f5, X c1, O f4, X d1, O f3",
+        );
+        let mv = order_chaos.computer_move(2).unwrap();
+        order_chaos.play(mv);
+        assert_eq!(order_chaos.outcome(), Some(Outcome::Win(ORDER)));
+
+        // Chaos spoils four Xs in a row with an O at the open end
+        let mut order_chaos = OrderChaos::default();
+        play_all(&mut order_chaos, "X a1, O f6, X b1, O f5, X c1, O f4, X d1");
+        let mv = order_chaos.computer_move(2).unwrap();
+        assert_eq!(mv.to_string(), "O e1");
+    }
+}
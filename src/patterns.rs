@@ -0,0 +1,336 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::board::{Board, MAX_SIZE};
+use crate::board_has_win::tracks;
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::cells_are_valid::{marks_are_valid, CellsImpossibleError};
+use crate::player::Player;
+use crate::ruleset::{with_first_player, Outcome, Ruleset};
+use crate::variant::Variant;
+
+// one way to win, a line of a rule file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    // the usual rows, columns and diagonals
+    Lines,
+    // exactly these cells, e.g. the four corners
+    Cells(Vec<CellId>),
+    // these cells and every copy of them moved across and down the board,
+    // e.g. any 2x2 square
+    Shape(Vec<CellId>),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells = |cell_ids: &[CellId]| {
+            let cells: Vec<String> = cell_ids.iter().map(|c| c.to_string()).collect();
+            cells.join(" ")
+        };
+        match self {
+            Pattern::Lines => write!(f, "lines"),
+            Pattern::Cells(cell_ids) => write!(f, "pattern {}", cells(cell_ids)),
+            Pattern::Shape(cell_ids) => write!(f, "shape {}", cells(cell_ids)),
+        }
+    }
+}
+
+// the ways to win from a rule file, a line each, e.g.
+//
+// # the usual lines, the corners of a 4x4 board and any plus sign
+// lines
+// pattern a1 d1 a4 d4
+// shape b1 a2 b2 c2 b3
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WinPatterns(pub Vec<Pattern>);
+
+impl fmt::Display for WinPatterns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for pattern in &self.0 {
+            writeln!(f, "{}", pattern)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ParsePatternsError {
+    UnknownHeader(String),
+    BadCell(String),
+    NoCells(String),
+    NoPatterns,
+    DoesNotFit(Pattern),
+}
+
+impl fmt::Display for ParsePatternsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePatternsError::UnknownHeader(header) => {
+                write!(f, "unknown pattern type {:?}", header)
+            }
+            ParsePatternsError::BadCell(cell) => write!(f, "cannot read cell {:?}", cell),
+            ParsePatternsError::NoCells(header) => write!(f, "{} needs some cells", header),
+            ParsePatternsError::NoPatterns => write!(f, "there is no way to win"),
+            ParsePatternsError::DoesNotFit(pattern) => {
+                write!(f, "{:?} does not fit on the board", pattern.to_string())
+            }
+        }
+    }
+}
+
+impl FromStr for WinPatterns {
+    type Err = ParsePatternsError;
+    fn from_str(patterns_str: &str) -> Result<Self, Self::Err> {
+        let mut patterns = vec![];
+        for line in patterns_str.lines() {
+            let mut words = line.split_whitespace();
+            let header = match words.next() {
+                None => continue,
+                Some(comment) if comment.starts_with('#') => continue,
+                Some(header) => header,
+            };
+            let cell_ids = words
+                .map(|word| {
+                    CellId::parse_for(word, MAX_SIZE, MAX_SIZE)
+                        .map_err(|_| Self::Err::BadCell(word.to_string()))
+                })
+                .collect::<Result<Vec<CellId>, Self::Err>>()?;
+            let pattern = match header {
+                "lines" => Pattern::Lines,
+                "pattern" | "shape" if cell_ids.is_empty() => {
+                    return Err(Self::Err::NoCells(header.to_string()))
+                }
+                "pattern" => Pattern::Cells(cell_ids),
+                "shape" => Pattern::Shape(cell_ids),
+                _ => return Err(Self::Err::UnknownHeader(header.to_string())),
+            };
+            patterns.push(pattern);
+        }
+        match patterns.is_empty() {
+            true => Err(Self::Err::NoPatterns),
+            false => Ok(WinPatterns(patterns)),
+        }
+    }
+}
+
+impl WinPatterns {
+    // the cell indices of every way to win on a board of the given size
+    pub fn cell_sets(
+        &self,
+        num_rows: usize,
+        num_cols: usize,
+    ) -> Result<Vec<Vec<usize>>, ParsePatternsError> {
+        let board = Board::new(num_rows, num_cols);
+        let mut sets: Vec<Vec<usize>> = vec![];
+        for pattern in &self.0 {
+            let does_not_fit = || ParsePatternsError::DoesNotFit(pattern.clone());
+            match pattern {
+                Pattern::Lines => {
+                    sets.extend(tracks(&board, false).map(|t| t.cell_idxs().to_vec()))
+                }
+                Pattern::Cells(cell_ids) => {
+                    if cell_ids
+                        .iter()
+                        .any(|c| c.row >= num_rows || c.column >= num_cols)
+                    {
+                        return Err(does_not_fit());
+                    }
+                    sets.push(cell_ids.iter().map(|c| c.to_idx(num_cols)).collect());
+                }
+                Pattern::Shape(cell_ids) => {
+                    // measured from the top left of the shape, wherever it was written
+                    let top = cell_ids.iter().map(|c| c.row).min().unwrap_or(0);
+                    let left = cell_ids.iter().map(|c| c.column).min().unwrap_or(0);
+                    let height = cell_ids.iter().map(|c| c.row - top + 1).max().unwrap_or(0);
+                    let width = cell_ids
+                        .iter()
+                        .map(|c| c.column - left + 1)
+                        .max()
+                        .unwrap_or(0);
+                    if height > num_rows || width > num_cols {
+                        return Err(does_not_fit());
+                    }
+                    for row in 0..=num_rows - height {
+                        for column in 0..=num_cols - width {
+                            sets.push(
+                                cell_ids
+                                    .iter()
+                                    .map(|c| CellId {
+                                        row: c.row - top + row,
+                                        column: c.column - left + column,
+                                    })
+                                    .map(|c| c.to_idx(num_cols))
+                                    .collect(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        // the same cells may come from more than one pattern
+        for set in sets.iter_mut() {
+            set.sort_unstable();
+            set.dedup();
+        }
+        sets.sort();
+        sets.dedup();
+        Ok(sets)
+    }
+
+    // the classic game won with these patterns, which must already have been
+    // checked to fit a board of this size
+    pub fn ruleset_for(&self, size: usize, first: Player) -> Box<dyn Ruleset> {
+        let rules = PatternRules::new(self.clone(), size, size).expect("patterns fit the board");
+        with_first_player(Box::new(rules), first)
+    }
+}
+
+// the classic game, won by claiming every cell of any of the patterns
+// instead of only by lines
+#[derive(Debug, Clone)]
+pub struct PatternRules {
+    pub patterns: WinPatterns,
+    sets: Vec<Vec<usize>>,
+}
+
+impl PatternRules {
+    pub fn new(
+        patterns: WinPatterns,
+        num_rows: usize,
+        num_cols: usize,
+    ) -> Result<PatternRules, ParsePatternsError> {
+        let sets = patterns.cell_sets(num_rows, num_cols)?;
+        Ok(PatternRules { patterns, sets })
+    }
+
+    // every completed pattern and who completed it
+    fn wins<'a>(&'a self, board: &'a Board) -> impl Iterator<Item = (Player, &'a Vec<usize>)> + 'a {
+        self.sets
+            .iter()
+            .filter_map(|set| match board.cells[set[0]] {
+                Cell::Player(p) if set.iter().all(|idx| board.cells[*idx] == Cell::Player(p)) => {
+                    Some((p, set))
+                }
+                _ => None,
+            })
+    }
+}
+
+impl Ruleset for PatternRules {
+    fn variant(&self) -> Variant {
+        Variant::Classic
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        match self.wins(board).next() {
+            Some((p, _)) => Some(Outcome::Win(p)),
+            None if board.is_full() => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+
+    fn winning_line(&self, board: &Board) -> Option<Vec<usize>> {
+        self.wins(board).next().map(|(_, set)| set.clone())
+    }
+
+    fn lines(&self, _board: &Board) -> Vec<Vec<usize>> {
+        self.sets.clone()
+    }
+
+    fn win_patterns(&self) -> Option<WinPatterns> {
+        Some(self.patterns.clone())
+    }
+
+    fn validate_from(&self, board: &Board, first: Player) -> Result<(), CellsImpossibleError> {
+        let winners: Vec<Player> = self.wins(board).map(|(p, _)| p).collect();
+        marks_are_valid(board, first, &winners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::rc::Rc;
+
+    use crate::position::Position;
+    use crate::rules_board::RulesBoard;
+
+    fn rules(patterns: &str, size: usize) -> PatternRules {
+        PatternRules::new(patterns.parse().unwrap(), size, size).unwrap()
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        let patterns_str = "lines\npattern a1 d1 a4 d4\nshape b1 a2 b2 c2 b3\n";
+        let patterns = patterns_str.parse::<WinPatterns>().unwrap();
+        assert_eq!(patterns.0.len(), 3);
+        assert_eq!(patterns.to_string(), patterns_str);
+        let patterns = "# a comment\n\nshape A1 b1".parse::<WinPatterns>().unwrap();
+        assert_eq!(patterns.to_string(), "shape a1 b1\n");
+
+        let err = "square a1".parse::<WinPatterns>();
+        assert!(matches!(err, Err(ParsePatternsError::UnknownHeader(_))));
+        let err = "pattern a1 zz".parse::<WinPatterns>();
+        assert!(matches!(err, Err(ParsePatternsError::BadCell(_))));
+        let err = "shape".parse::<WinPatterns>();
+        assert!(matches!(err, Err(ParsePatternsError::NoCells(_))));
+        let err = "# nothing".parse::<WinPatterns>();
+        assert!(matches!(err, Err(ParsePatternsError::NoPatterns)));
+    }
+
+    #[test]
+    fn test_cell_sets() {
+        // a 2x2 square fits in four places on a 3x3 board
+        let patterns = "shape b2 c2 b3 c3".parse::<WinPatterns>().unwrap();
+        let sets = patterns.cell_sets(3, 3).unwrap();
+        assert_eq!(
+            sets,
+            [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]]
+        );
+        assert!(matches!(
+            patterns.cell_sets(1, 3),
+            Err(ParsePatternsError::DoesNotFit(_))
+        ));
+        let patterns = "lines\npattern a1 b1 c1".parse::<WinPatterns>().unwrap();
+        assert_eq!(patterns.cell_sets(3, 3).unwrap().len(), 8);
+        let patterns = "pattern a1 d4".parse::<WinPatterns>().unwrap();
+        assert!(patterns.cell_sets(3, 3).is_err());
+    }
+
+    #[test]
+    fn test_pattern_outcome() {
+        let corners = rules("pattern a1 c1 a3 c3", 3);
+        // a row is no longer a win
+        let board = Board::parse_cells("XXXOO O  ").unwrap();
+        assert_eq!(corners.outcome(&board), None);
+        let board = Board::parse_cells("X XOOOX X").unwrap();
+        assert_eq!(corners.outcome(&board), Some(Outcome::Win(Player::X)));
+        assert_eq!(corners.winning_line(&board), Some(vec![0, 2, 6, 8]));
+
+        // an L, in any of the places it fits
+        let ells = rules("shape a1 a2 b2", 3);
+        let board = Board::parse_cells("O  XO XX ").unwrap();
+        assert_eq!(ells.outcome(&board), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn test_pattern_validate_and_solve() {
+        let squares = rules("shape a1 b1 a2 b2", 3);
+        let board = Board::parse_cells("XXOXXO O ").unwrap();
+        assert!(squares.validate(&board).is_ok());
+        // O played on after X's square
+        let board = Board::parse_cells("XXOXXOOO ").unwrap();
+        assert!(matches!(
+            squares.validate(&board),
+            Err(CellsImpossibleError::OPlayAfterXWin)
+        ));
+
+        // every square has a corner of the board, and O can spoil them all
+        let (outcome, _) = RulesBoard::new(Board::new(3, 3), Rc::new(squares))
+            .solve()
+            .unwrap();
+        assert_eq!(outcome, Outcome::Draw);
+    }
+}
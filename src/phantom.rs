@@ -0,0 +1,248 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::{board_has_win, tracks, winning_line};
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::{check_cell, next_player};
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::score_lines;
+use crate::theme::Theme;
+
+#[derive(Debug)]
+pub enum PhantomMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    // the player has already found out the cell is taken
+    Known,
+}
+
+impl fmt::Display for PhantomMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhantomMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            PhantomMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a cell e.g. b2")
+            }
+            PhantomMoveError::GameOver => write!(f, "The game is over"),
+            PhantomMoveError::Known => write!(f, "You already know that cell is taken"),
+        }
+    }
+}
+
+// tic-tac-toe where neither player sees the other's marks. the referee
+// holds the real board and each player only their own view of it: their own
+// marks and any of the other's they have found by trying to play on them.
+// a player who tries a taken cell is shown the mark and tries again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phantom {
+    board: Board,
+    // what each player knows of the board, by player index
+    views: Vec<Board>,
+    pub player: Player,
+    // the cell the player to move has just found taken, if they did
+    pub revealed: Option<CellId>,
+    pub theme: Theme,
+}
+
+impl Phantom {
+    pub fn new(size: usize) -> Phantom {
+        Phantom {
+            board: Board::new(size, size),
+            views: vec![Board::new(size, size); 2],
+            player: Player::X,
+            revealed: None,
+            theme: Theme::default(),
+        }
+    }
+
+    // everything `player` may be told, the real board is only for the referee
+    pub fn view(&self, player: Player) -> &Board {
+        &self.views[player.idx()]
+    }
+}
+
+impl Position for Phantom {
+    type Move = CellId;
+    type MoveError = PhantomMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match board_has_win(&self.board) {
+            Cell::Player(p) => Some(Outcome::Win(p)),
+            Cell::Unmarked if self.board.is_full() => Some(Outcome::Draw),
+            Cell::Unmarked => None,
+        }
+    }
+
+    // any cell the player has not seen taken, whether or not it is free
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.view(self.player).unmarked_cell_ids().collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, PhantomMoveError> {
+        CellId::parse_for(input.trim(), self.board.num_rows, self.board.num_cols)
+            .map_err(PhantomMoveError::BadCell)
+    }
+
+    // only checked against what the player knows, or the check would give
+    // the other player's marks away
+    fn check_move(&self, mv: &CellId) -> Result<(), PhantomMoveError> {
+        if self.outcome().is_some() {
+            return Err(PhantomMoveError::GameOver);
+        }
+        if self.view(self.player).cell(mv) != Cell::Unmarked {
+            return Err(PhantomMoveError::Known);
+        }
+        Ok(())
+    }
+
+    // the referee either marks the cell or shows the mover whose it is
+    fn play(&mut self, mv: CellId) {
+        let idx = mv.to_idx(self.board.num_cols);
+        let mover = self.player.idx();
+        match check_cell(&self.board, &mv) {
+            Ok(()) => {
+                self.board.cells[idx] = Cell::Player(self.player);
+                self.views[mover].cells[idx] = Cell::Player(self.player);
+                self.player = next_player(self.player);
+                self.revealed = None;
+            }
+            Err(_) => {
+                self.views[mover].cells[idx] = self.board.cells[idx];
+                self.revealed = Some(mv);
+            }
+        }
+    }
+
+    fn move_help(&self) -> String {
+        "Type a cell e.g. b2, trying a taken one shows whose it is".to_string()
+    }
+
+    fn hides_moves(&self) -> bool {
+        true
+    }
+
+    // only the mover is told the cell they tried was taken
+    fn view_for(&self, player: Player) -> String {
+        if self.outcome().is_some() {
+            return self.to_string();
+        }
+        let revealed = self.revealed.filter(|_| player == self.player);
+        let mut view = format!(
+            "{}'s view\n{}",
+            player,
+            self.theme.render_board(self.view(player), &[], revealed)
+        );
+        if let Some(cell_id) = revealed {
+            view += &format!("{} is taken, try again\n", cell_id);
+        }
+        view
+    }
+
+    // the computer plays from its own view too: every line it has not seen
+    // blocked is worth more the more of it is already its own
+    fn computer_move(&self, _depth: usize) -> Option<CellId> {
+        let view = self.view(self.player);
+        let mine = Cell::Player(self.player);
+        let lines: Vec<Vec<usize>> = tracks(view, false)
+            .map(|t| t.cell_idxs().to_vec())
+            .filter(|line| {
+                line.iter()
+                    .all(|idx| view.cells[*idx] == mine || view.cells[*idx] == Cell::Unmarked)
+            })
+            .collect();
+        self.legal_moves().into_iter().max_by_key(|mv| {
+            let idx = mv.to_idx(view.num_cols);
+            // the lines through the move, as they would be with it played
+            let lines_through = lines.iter().filter(|line| line.contains(&idx)).map(|line| {
+                line.iter()
+                    .filter(|i| **i == idx || view.cells[**i] == mine)
+                    .map(|_| self.player)
+            });
+            score_lines(lines_through, self.player)
+        })
+    }
+}
+
+// the player to move sees only their own view, and everyone sees the real
+// board once the game is over
+impl fmt::Display for Phantom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.outcome().is_some() {
+            let highlighted = winning_line(&self.board).unwrap_or_default();
+            return write!(
+                f,
+                "{}",
+                self.theme.render_board(&self.board, &highlighted, None)
+            );
+        }
+        write!(f, "{}", self.view_for(self.player))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(phantom: &mut Phantom, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = phantom.parse_move(mv_str).unwrap();
+            phantom.check_move(&mv).unwrap();
+            phantom.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_views() {
+        let mut phantom = Phantom::new(3);
+        play_all(&mut phantom, "b2");
+        assert_eq!(phantom.view(Player::O).to_string(), "         ");
+        // O finds X's mark and has to go again
+        play_all(&mut phantom, "b2");
+        assert_eq!(phantom.player, Player::O);
+        assert_eq!(phantom.view(Player::O).to_string(), "    X    ");
+        assert!(phantom.to_string().contains("b2 is taken, try again"));
+        assert!(matches!(
+            phantom.check_move(&"b2".parse().unwrap()),
+            Err(PhantomMoveError::Known)
+        ));
+        play_all(&mut phantom, "a1");
+        assert_eq!(phantom.player, Player::X);
+        // X never learns of O's mark until running into it
+        assert_eq!(phantom.view(Player::X).to_string(), "    X    ");
+        assert!(phantom.legal_moves().contains(&"a1".parse().unwrap()));
+        assert!(phantom.to_string().starts_with("X's view\n"));
+    }
+
+    #[test]
+    fn test_outcome_on_the_real_board() {
+        let mut phantom = Phantom::new(3);
+        play_all(&mut phantom, "a1 b1 a2 b2 a3");
+        assert_eq!(phantom.outcome(), Some(Outcome::Win(Player::X)));
+        assert!(phantom.to_string().contains("1 |[X]| O |   |"));
+    }
+
+    #[test]
+    fn test_computer_uses_its_own_view() {
+        let mut phantom = Phantom::new(3);
+        play_all(&mut phantom, "a1 c3 a2");
+        // O cannot see X's threat, so builds on its own mark
+        let mv = phantom.computer_move(1).unwrap();
+        assert_ne!(mv, "a3".parse().unwrap());
+        play_all(&mut phantom, "b3");
+        // X goes for the line it knows is open
+        assert_eq!(phantom.computer_move(1), "a3".parse().ok());
+    }
+}
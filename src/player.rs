@@ -1,16 +1,92 @@
 use core::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum Player {
-    X,
-    O,
+// the most players a game can have, one per symbol
+pub const MAX_PLAYERS: usize = 4;
+
+// what each player's marks are written as, in turn order
+const SYMBOLS: [char; MAX_PLAYERS] = ['X', 'O', 'Δ', '□'];
+
+// players are numbered in turn order. most games only have X and O, which
+// can be matched on like enum variants
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Player(u8);
+
+impl Player {
+    pub const X: Player = Player(0);
+    pub const O: Player = Player(1);
+
+    pub fn nth(idx: usize) -> Player {
+        assert!(idx < MAX_PLAYERS, "there are only {} players", MAX_PLAYERS);
+        Player(idx as u8)
+    }
+
+    pub fn idx(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn symbol(self) -> char {
+        SYMBOLS[self.idx()]
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Player> {
+        SYMBOLS.iter().position(|s| *s == symbol).map(Player::nth)
+    }
+
+    // the first `num_players` players, in turn order
+    pub fn all(num_players: usize) -> impl Iterator<Item = Player> {
+        (0..num_players).map(Player::nth)
+    }
 }
 
 impl fmt::Display for Player {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Player::X => write!(f, "X"),
-            Player::O => write!(f, "O"),
+        write!(f, "{}", self.symbol())
+    }
+}
+
+// the symbol rather than the number, so errors and test failures read naturally
+impl fmt::Debug for Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownPlayer;
+
+impl FromStr for Player {
+    type Err = UnknownPlayer;
+    fn from_str(player_str: &str) -> Result<Self, Self::Err> {
+        let mut chars = player_str.chars();
+        match (chars.next(), chars.next()) {
+            // players can also be named by their place in the turn order
+            (Some(c @ '1'..='9'), None) => match c as usize - '1' as usize {
+                idx if idx < MAX_PLAYERS => Ok(Player::nth(idx)),
+                _ => Err(UnknownPlayer),
+            },
+            (Some(c), None) => Player::from_symbol(c.to_ascii_uppercase()).ok_or(UnknownPlayer),
+            _ => Err(UnknownPlayer),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_players() {
+        assert_eq!(Player::nth(0), Player::X);
+        assert_eq!(Player::O.idx(), 1);
+        let names: Vec<String> = Player::all(3).map(|p| p.to_string()).collect();
+        assert_eq!(names, ["X", "O", "Δ"]);
+        assert_eq!("o".parse::<Player>().unwrap(), Player::O);
+        assert_eq!("Δ".parse::<Player>().unwrap(), Player::nth(2));
+        assert!("xo".parse::<Player>().is_err());
+        assert!("z".parse::<Player>().is_err());
+        assert_eq!("3".parse::<Player>().unwrap(), Player::nth(2));
+        assert!("5".parse::<Player>().is_err());
+    }
+}
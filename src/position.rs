@@ -0,0 +1,81 @@
+use core::fmt;
+
+use crate::game_record::GameRecord;
+use crate::player::Player;
+use crate::ruleset::Outcome;
+use crate::search_position::search_position;
+
+// a game state for every mode, from a single `Board` played by a `Ruleset`
+// to several boards at once. `Display` draws the position for the players
+pub trait Position: Clone + fmt::Display {
+    type Move: Copy + PartialEq + fmt::Debug + fmt::Display;
+    type MoveError: fmt::Debug + fmt::Display;
+
+    fn player_to_move(&self) -> Player;
+
+    // how many players take turns, most modes are played by X and O
+    fn num_players(&self) -> usize {
+        2
+    }
+
+    // whether players are kept from seeing some of the position, in which
+    // case the other players' moves are not shown and cannot be taken back
+    fn hides_moves(&self) -> bool {
+        false
+    }
+
+    // the position as `player` is allowed to see it
+    fn view_for(&self, _player: Player) -> String {
+        self.to_string()
+    }
+
+    // the result once the game is over, None while it is still going
+    fn outcome(&self) -> Option<Outcome>;
+
+    // every move the player to move may make, none once the game is over
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    fn parse_move(&self, input: &str) -> Result<Self::Move, Self::MoveError>;
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), Self::MoveError>;
+
+    // makes a move that has already been checked
+    fn play(&mut self, mv: Self::Move);
+
+    // how to write a move, for the help text
+    fn move_help(&self) -> String;
+
+    // the move the computer makes, by default found by searching `depth` moves ahead
+    fn computer_move(&self, depth: usize) -> Option<Self::Move> {
+        search_position(self, depth)
+    }
+
+    // a guess at how good an unfinished position is for `player`,
+    // for when a search cannot look all the way to the end
+    fn evaluate(&self, _player: Player) -> i32 {
+        0
+    }
+
+    // the result with best play and a move that gets it, for modes small
+    // enough to solve exactly, None for the rest
+    fn solve(&self) -> Option<(Outcome, Option<Self::Move>)> {
+        None
+    }
+
+    // whether `solve` works for this mode at all, so that None from it means
+    // the board is too large rather than that the mode cannot be solved
+    fn solvable(&self) -> bool {
+        false
+    }
+
+    // the moves that won the game, named after the winner, e.g. a completed line
+    fn winning_moves(&self) -> Vec<Self::Move> {
+        vec![]
+    }
+
+    // the game played from this position with `moves`, as a record that can
+    // be saved and replayed, None for modes without records
+    fn record(&self, _moves: &[Self::Move]) -> Option<GameRecord> {
+        None
+    }
+}
@@ -0,0 +1,524 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::tracks;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::{score_lines, search_position};
+
+// every move goes in two cells, so there are too many moves to look far ahead
+const MAX_SEARCH_DEPTH: usize = 3;
+
+// a spooky mark in two cells, e.g. `a1 b2`, or the cell a mark that closed a
+// loop collapses into, e.g. `collapse a1`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QuantumMove {
+    Spooky(CellId, CellId),
+    Collapse(CellId),
+}
+
+impl fmt::Display for QuantumMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuantumMove::Spooky(first, second) => write!(f, "{} {}", first, second),
+            QuantumMove::Collapse(cell) => write!(f, "collapse {}", cell),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum QuantumMoveError {
+    BadCell(CellIdError),
+    WrongNumberOfCells(String),
+    SameCell,
+    Classical(CellId),
+    GameOver,
+    MustCollapse,
+    NothingToCollapse,
+    NotInCollapse(CellId),
+}
+
+impl fmt::Display for QuantumMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuantumMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            QuantumMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name two cells e.g. a1 b2")
+            }
+            QuantumMoveError::WrongNumberOfCells(input) => {
+                write!(f, "{:?} should be two cells e.g. a1 b2", input)
+            }
+            QuantumMoveError::SameCell => write!(f, "The two cells must be different"),
+            QuantumMoveError::Classical(cell) => write!(f, "{} already has a classical mark", cell),
+            QuantumMoveError::GameOver => write!(f, "The game is over"),
+            QuantumMoveError::MustCollapse => {
+                write!(
+                    f,
+                    "The last move closed a loop, so choose where it collapses first"
+                )
+            }
+            QuantumMoveError::NothingToCollapse => write!(f, "There is nothing to collapse"),
+            QuantumMoveError::NotInCollapse(cell) => {
+                write!(f, "The collapsing mark is not in {}", cell)
+            }
+        }
+    }
+}
+
+// each move puts a spooky mark in two cells, entangling them. once the
+// entanglements make a loop the other player picks which of its two cells the
+// newest mark collapses into, and every mark entangled with it is then forced
+// into one cell too. only classical marks make lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quantum {
+    // the two cells of every move so far. X makes the even numbered ones
+    // and O the odd ones, and they are written from 1 e.g. X1 O2 X3
+    pub moves: Vec<[usize; 2]>,
+    // the move that has collapsed into each cell
+    pub classical: Vec<Option<usize>>,
+    // the move that closed a loop and has to collapse before anyone moves again
+    pub collapsing: Option<usize>,
+    pub player: Player,
+}
+
+impl Default for Quantum {
+    fn default() -> Self {
+        Quantum {
+            moves: vec![],
+            classical: vec![None; Board::default().num_cells()],
+            collapsing: None,
+            player: Player::X,
+        }
+    }
+}
+
+impl Quantum {
+    pub fn mover(move_idx: usize) -> Player {
+        match move_idx % 2 {
+            0 => Player::X,
+            _ => Player::O,
+        }
+    }
+
+    // e.g. X3, or x3 while it is still spooky
+    pub fn mark_name(move_idx: usize, classical: bool) -> String {
+        let name = format!("{}{}", Quantum::mover(move_idx), move_idx + 1);
+        match classical {
+            true => name,
+            false => name.to_lowercase(),
+        }
+    }
+
+    fn is_collapsed(&self, move_idx: usize) -> bool {
+        self.classical.contains(&Some(move_idx))
+    }
+
+    // the moves in `cell` that have not collapsed yet
+    pub fn spooky_marks(&self, cell: usize) -> Vec<usize> {
+        (0..self.moves.len())
+            .filter(|m| self.moves[*m].contains(&cell) && !self.is_collapsed(*m))
+            .collect()
+    }
+
+    // whether spooky marks link the two cells, in which case another
+    // mark between them closes a loop
+    fn entangled(&self, from: usize, to: usize) -> bool {
+        let mut seen = vec![false; self.classical.len()];
+        let mut stack = vec![from];
+        while let Some(cell) = stack.pop() {
+            if cell == to {
+                return true;
+            }
+            if seen[cell] {
+                continue;
+            }
+            seen[cell] = true;
+            for move_idx in self.spooky_marks(cell) {
+                let [first, second] = self.moves[move_idx];
+                stack.push(if first == cell { second } else { first });
+            }
+        }
+        false
+    }
+
+    // makes `move_idx` classical in `cell`, then moves the other spooky marks
+    // there into their other cells, and so on
+    fn collapse(&mut self, move_idx: usize, cell: usize) {
+        let mut forced = vec![(move_idx, cell)];
+        while let Some((move_idx, cell)) = forced.pop() {
+            if self.is_collapsed(move_idx) || self.classical[cell].is_some() {
+                continue;
+            }
+            self.classical[cell] = Some(move_idx);
+            for other in self.spooky_marks(cell) {
+                let [first, second] = self.moves[other];
+                forced.push((other, if first == cell { second } else { first }));
+            }
+        }
+    }
+
+    fn lines() -> Vec<Vec<usize>> {
+        tracks(&Board::default(), false)
+            .map(|track| track.cell_idxs().to_vec())
+            .collect()
+    }
+
+    // the highest numbered mark in `player`'s earliest line, which settles who
+    // wins when one collapse makes lines for both players
+    pub fn earliest_line(&self, player: Player) -> Option<usize> {
+        Quantum::lines()
+            .iter()
+            .filter_map(|line| {
+                let marks: Option<Vec<usize>> =
+                    line.iter().map(|idx| self.classical[*idx]).collect();
+                marks.filter(|marks| marks.iter().all(|m| Quantum::mover(*m) == player))
+            })
+            .filter_map(|marks| marks.into_iter().max())
+            .min()
+    }
+
+    // the player with the earlier line scores a point, and the other player half
+    // a point if they made a line at the same time. counted in half points
+    pub fn half_points(&self, player: Player) -> u32 {
+        let theirs = self.earliest_line(next_player(player));
+        match self.earliest_line(player) {
+            Some(ours) if theirs.is_none_or(|theirs| ours < theirs) => 2,
+            Some(_) => 1,
+            None => 0,
+        }
+    }
+
+    fn cells_left(&self) -> Vec<usize> {
+        (0..self.classical.len())
+            .filter(|idx| self.classical[*idx].is_none())
+            .collect()
+    }
+
+    fn cell_id(idx: usize) -> CellId {
+        CellId::from_idx(idx, Board::default().num_cols)
+    }
+
+    fn idx(cell: CellId) -> usize {
+        cell.to_idx(Board::default().num_cols)
+    }
+}
+
+impl Position for Quantum {
+    type Move = QuantumMove;
+    type MoveError = QuantumMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    // once fewer than two cells are left there is nowhere to put a spooky mark
+    fn outcome(&self) -> Option<Outcome> {
+        if self.collapsing.is_some() {
+            return None;
+        }
+        match (self.earliest_line(Player::X), self.earliest_line(Player::O)) {
+            (Some(x), Some(o)) if o < x => Some(Outcome::Win(Player::O)),
+            (Some(_), _) => Some(Outcome::Win(Player::X)),
+            (None, Some(_)) => Some(Outcome::Win(Player::O)),
+            (None, None) if self.cells_left().len() < 2 => Some(Outcome::Draw),
+            (None, None) => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<QuantumMove> {
+        if let Some(move_idx) = self.collapsing {
+            return self.moves[move_idx]
+                .map(|cell| QuantumMove::Collapse(Quantum::cell_id(cell)))
+                .to_vec();
+        }
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        let cells = self.cells_left();
+        let mut moves = vec![];
+        for (k, first) in cells.iter().enumerate() {
+            for second in &cells[k + 1..] {
+                let (first, second) = (Quantum::cell_id(*first), Quantum::cell_id(*second));
+                moves.push(QuantumMove::Spooky(first, second));
+            }
+        }
+        moves
+    }
+
+    // two cells for a spooky mark, or one cell, with or without `collapse`
+    // before it, to choose where a mark collapses
+    fn parse_move(&self, input: &str) -> Result<QuantumMove, QuantumMoveError> {
+        let words: Vec<&str> = input
+            .split_whitespace()
+            .filter(|word| !word.eq_ignore_ascii_case("collapse"))
+            .collect();
+        let parse = |word: &str| {
+            let board = Board::default();
+            CellId::parse_for(word, board.num_rows, board.num_cols)
+                .map_err(QuantumMoveError::BadCell)
+        };
+        match words[..] {
+            [cell] => Ok(QuantumMove::Collapse(parse(cell)?)),
+            [first, second] => Ok(QuantumMove::Spooky(parse(first)?, parse(second)?)),
+            _ => Err(QuantumMoveError::WrongNumberOfCells(
+                input.trim().to_string(),
+            )),
+        }
+    }
+
+    fn check_move(&self, mv: &QuantumMove) -> Result<(), QuantumMoveError> {
+        match (mv, self.collapsing) {
+            (QuantumMove::Collapse(cell), Some(move_idx)) => {
+                if !self.moves[move_idx].contains(&Quantum::idx(*cell)) {
+                    return Err(QuantumMoveError::NotInCollapse(*cell));
+                }
+            }
+            (QuantumMove::Collapse(_), None) => return Err(QuantumMoveError::NothingToCollapse),
+            (QuantumMove::Spooky(_, _), Some(_)) => return Err(QuantumMoveError::MustCollapse),
+            (QuantumMove::Spooky(first, second), None) => {
+                if self.outcome().is_some() {
+                    return Err(QuantumMoveError::GameOver);
+                }
+                if first == second {
+                    return Err(QuantumMoveError::SameCell);
+                }
+                for cell in [first, second] {
+                    if self.classical[Quantum::idx(*cell)].is_some() {
+                        return Err(QuantumMoveError::Classical(*cell));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the player who chooses a collapse then makes their own move
+    fn play(&mut self, mv: QuantumMove) {
+        match mv {
+            QuantumMove::Spooky(first, second) => {
+                let (first, second) = (Quantum::idx(first), Quantum::idx(second));
+                if self.entangled(first, second) {
+                    self.collapsing = Some(self.moves.len());
+                }
+                self.moves.push([first, second]);
+                self.player = next_player(self.player);
+            }
+            QuantumMove::Collapse(cell) => {
+                if let Some(move_idx) = self.collapsing.take() {
+                    self.collapse(move_idx, Quantum::idx(cell));
+                }
+            }
+        }
+    }
+
+    fn move_help(&self) -> String {
+        match self.collapsing {
+            Some(move_idx) => {
+                let [first, second] = self.moves[move_idx].map(Quantum::cell_id);
+                format!(
+                    "Type where {} collapses, {} or {}",
+                    Quantum::mark_name(move_idx, true),
+                    first,
+                    second
+                )
+            }
+            None => "Type two cells for your spooky mark e.g. a1 b2".to_string(),
+        }
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<QuantumMove> {
+        search_position(self, depth.min(MAX_SEARCH_DEPTH))
+    }
+
+    // lines holding classical marks of only one player could still become theirs
+    fn evaluate(&self, player: Player) -> i32 {
+        let lines = Quantum::lines().into_iter().map(|line| {
+            line.into_iter()
+                .filter_map(|idx| self.classical[idx])
+                .map(Quantum::mover)
+        });
+        score_lines(lines, player)
+    }
+}
+
+// classical marks in capitals and spooky ones in lower case, then the
+// collapse waiting to be chosen or the scores, e.g.
+//
+//    a      b      c
+// 1  X3     .      x5
+// 2  .      x1 o2  x5
+// 3  .      .      o2
+impl fmt::Display for Quantum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let board = Board::default();
+        let texts: Vec<String> = (0..self.classical.len())
+            .map(|idx| match self.classical[idx] {
+                Some(move_idx) => Quantum::mark_name(move_idx, true),
+                None => {
+                    let marks: Vec<String> = self
+                        .spooky_marks(idx)
+                        .into_iter()
+                        .map(|m| Quantum::mark_name(m, false))
+                        .collect();
+                    match marks.is_empty() {
+                        true => ".".to_string(),
+                        false => marks.join(" "),
+                    }
+                }
+            })
+            .collect();
+        let width = texts.iter().map(|text| text.len()).max().unwrap_or(1);
+        let letters: Vec<String> = (0..board.num_cols)
+            .map(|column| format!("{:<width$}", (b'a' + column as u8) as char))
+            .collect();
+        writeln!(f, "   {}", letters.join("  ").trim_end())?;
+        for row in 0..board.num_rows {
+            let cells: Vec<String> = (0..board.num_cols)
+                .map(|column| {
+                    let idx = CellId { row, column }.to_idx(board.num_cols);
+                    format!("{:<width$}", texts[idx])
+                })
+                .collect();
+            writeln!(f, "{:<2} {}", row + 1, cells.join("  ").trim_end())?;
+        }
+        if let Some(move_idx) = self.collapsing {
+            let [first, second] = self.moves[move_idx].map(Quantum::cell_id);
+            writeln!(
+                f,
+                "{} closed a loop, {} chooses whether it collapses into {} or {}",
+                Quantum::mark_name(move_idx, true),
+                self.player,
+                first,
+                second
+            )?;
+        } else if self
+            .outcome()
+            .is_some_and(|outcome| outcome != Outcome::Draw)
+        {
+            let scores: Vec<String> = [Player::X, Player::O]
+                .iter()
+                .map(|player| {
+                    let points = match self.half_points(*player) {
+                        2 => "1",
+                        1 => "½",
+                        _ => "0",
+                    };
+                    format!("{} scores {}", player, points)
+                })
+                .collect();
+            writeln!(f, "{}", scores.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(quantum: &mut Quantum, moves: &str) {
+        for mv_str in moves.split(',') {
+            let mv = quantum.parse_move(mv_str).unwrap();
+            quantum.check_move(&mv).unwrap();
+            quantum.play(mv);
+        }
+    }
+
+    // both players finish a line in the same collapse
+    const SIMULTANEOUS: &str = "a1 b1, a3 b3, a1 b1, a1, a3 b3, a3, c1 c2, c3 c2, c3 c1";
+
+    #[test]
+    fn test_parse_move() {
+        let quantum = Quantum::default();
+        assert_eq!(quantum.parse_move(" A1  b2 ").unwrap().to_string(), "a1 b2");
+        assert_eq!(
+            quantum.parse_move("collapse c3").unwrap(),
+            QuantumMove::Collapse(CellId { row: 2, column: 2 })
+        );
+        assert!(matches!(
+            quantum.parse_move("a1 b2 c3"),
+            Err(QuantumMoveError::WrongNumberOfCells(_))
+        ));
+        assert!(matches!(
+            quantum.check_move(&quantum.parse_move("b2 b2").unwrap()),
+            Err(QuantumMoveError::SameCell)
+        ));
+        assert!(matches!(
+            quantum.check_move(&quantum.parse_move("b2").unwrap()),
+            Err(QuantumMoveError::NothingToCollapse)
+        ));
+    }
+
+    #[test]
+    fn test_loop_collapses() {
+        let mut quantum = Quantum::default();
+        play_all(&mut quantum, "a1 b2, b2 c3");
+        assert_eq!(quantum.collapsing, None);
+        assert_eq!(quantum.spooky_marks(4), [0, 1]);
+        assert_eq!(
+            quantum.to_string(),
+            "   a      b      c\n1  x1     .      .\n2  .      x1 o2  .\n3  .      .      o2\n"
+        );
+
+        // X3 closes a1-b2-c3, so O chooses where it goes before moving
+        play_all(&mut quantum, "c3 a1");
+        assert_eq!(quantum.collapsing, Some(2));
+        assert_eq!(quantum.player_to_move(), Player::O);
+        assert_eq!(quantum.legal_moves().len(), 2);
+        assert!(matches!(
+            quantum.check_move(&quantum.parse_move("a2 b3").unwrap()),
+            Err(QuantumMoveError::MustCollapse)
+        ));
+        assert!(matches!(
+            quantum.check_move(&quantum.parse_move("b2").unwrap()),
+            Err(QuantumMoveError::NotInCollapse(_))
+        ));
+        play_all(&mut quantum, "a1");
+        assert_eq!(quantum.player_to_move(), Player::O);
+        // X1 is pushed out of a1 into b2, and O2 out of b2 into c3
+        let classical: Vec<Option<usize>> = [0, 4, 8]
+            .iter()
+            .map(|idx| quantum.classical[*idx])
+            .collect();
+        assert_eq!(classical, [Some(2), Some(0), Some(1)]);
+        assert!(matches!(
+            quantum.check_move(&quantum.parse_move("a1 a2").unwrap()),
+            Err(QuantumMoveError::Classical(_))
+        ));
+    }
+
+    #[test]
+    fn test_simultaneous_lines() {
+        // X's row 1 is finished by X7 and O's row 3 by O6, so O's line is earlier
+        let mut quantum = Quantum::default();
+        play_all(&mut quantum, SIMULTANEOUS);
+        play_all(&mut quantum, "c1");
+        assert_eq!(quantum.outcome(), Some(Outcome::Win(Player::O)));
+        assert_eq!(quantum.half_points(Player::O), 2);
+        assert_eq!(quantum.half_points(Player::X), 1);
+        assert!(quantum.to_string().ends_with("X scores ½, O scores 1\n"));
+
+        // collapsing the other way only gives X a line
+        let mut quantum = Quantum::default();
+        play_all(&mut quantum, SIMULTANEOUS);
+        play_all(&mut quantum, "c3");
+        assert_eq!(quantum.outcome(), Some(Outcome::Win(Player::X)));
+        assert_eq!(quantum.half_points(Player::O), 0);
+    }
+
+    #[test]
+    fn test_computer_chooses_collapse() {
+        let mut quantum = Quantum::default();
+        play_all(&mut quantum, SIMULTANEOUS);
+        assert_eq!(
+            quantum.computer_move(2),
+            Some(QuantumMove::Collapse(CellId { row: 0, column: 2 }))
+        );
+    }
+}
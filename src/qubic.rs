@@ -0,0 +1,377 @@
+use core::fmt;
+use std::sync::OnceLock;
+
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::search_position;
+
+// the cube is SIZE layers of SIZE x SIZE boards
+const SIZE: usize = 4;
+const NUM_CELLS: usize = SIZE * SIZE * SIZE;
+
+// a cell in the cube, written `2/b3` with the layers numbered from 1
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct QubicCellId {
+    pub layer: usize,
+    pub cell: CellId,
+}
+
+impl QubicCellId {
+    pub fn to_idx(self) -> usize {
+        self.layer * SIZE * SIZE + self.cell.to_idx(SIZE)
+    }
+
+    pub fn from_idx(idx: usize) -> QubicCellId {
+        QubicCellId {
+            layer: idx / (SIZE * SIZE),
+            cell: CellId::from_idx(idx % (SIZE * SIZE), SIZE),
+        }
+    }
+}
+
+impl fmt::Display for QubicCellId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.layer + 1, self.cell)
+    }
+}
+
+#[derive(Debug)]
+pub enum QubicMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+}
+
+impl fmt::Display for QubicMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QubicMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            QubicMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a layer and a cell e.g. 2/b3")
+            }
+            QubicMoveError::GameOver => write!(f, "The game is over"),
+            QubicMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+// every straight line of SIZE cells through the cube: rows, columns and
+// diagonals within a layer, pillars and diagonals across the layers, and the
+// four space diagonals corner to corner, 76 in all for a 4x4x4 cube
+pub fn lines() -> &'static [Vec<usize>] {
+    static LINES: OnceLock<Vec<Vec<usize>>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        let mut lines = vec![];
+        let steps = [-1, 0, 1];
+        for dl in steps {
+            for dr in steps {
+                for dc in steps {
+                    // each line once, walking the same way along it
+                    let first_step = [dl, dr, dc].into_iter().find(|d| *d != 0);
+                    if first_step != Some(1) {
+                        continue;
+                    }
+                    for start in 0..NUM_CELLS {
+                        let QubicCellId { layer, cell } = QubicCellId::from_idx(start);
+                        let end = |pos: usize, d: i32| pos as i32 + d * (SIZE as i32 - 1);
+                        let ends = [end(layer, dl), end(cell.row, dr), end(cell.column, dc)];
+                        if ends.iter().any(|e| !(0..SIZE as i32).contains(e)) {
+                            continue;
+                        }
+                        let line = (0..SIZE as i32)
+                            .map(|k| {
+                                QubicCellId {
+                                    layer: (layer as i32 + k * dl) as usize,
+                                    cell: CellId {
+                                        row: (cell.row as i32 + k * dr) as usize,
+                                        column: (cell.column as i32 + k * dc) as usize,
+                                    },
+                                }
+                                .to_idx()
+                            })
+                            .collect();
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Qubic {
+    // layer by layer, then row by row
+    pub cells: Vec<Cell>,
+    pub player: Player,
+}
+
+impl Default for Qubic {
+    fn default() -> Self {
+        Qubic {
+            cells: vec![Cell::Unmarked; NUM_CELLS],
+            player: Player::X,
+        }
+    }
+}
+
+impl Qubic {
+    pub fn cell(&self, cell_id: QubicCellId) -> Cell {
+        self.cells[cell_id.to_idx()]
+    }
+
+    pub fn winning_line(&self) -> Option<&'static Vec<usize>> {
+        lines().iter().find(|line| {
+            let first = self.cells[line[0]];
+            first != Cell::Unmarked && line.iter().all(|idx| self.cells[*idx] == first)
+        })
+    }
+
+    // how many of `player`'s marks are on each line nobody else has marked
+    fn open_line_counts(&self, player: Player) -> impl Iterator<Item = usize> + '_ {
+        lines().iter().filter_map(move |line| {
+            let mut own = 0;
+            for idx in line {
+                match self.cells[*idx] {
+                    Cell::Player(p) if p == player => own += 1,
+                    Cell::Player(_) => return None,
+                    Cell::Unmarked => {}
+                }
+            }
+            Some(own)
+        })
+    }
+
+    // the cell that would complete a line for `player`, if there is one
+    fn completing_move(&self, player: Player) -> Option<QubicCellId> {
+        self.legal_moves().into_iter().find(|mv| {
+            let mut next = self.clone();
+            next.cells[mv.to_idx()] = Cell::Player(player);
+            next.winning_line().is_some()
+        })
+    }
+}
+
+impl Position for Qubic {
+    type Move = QubicCellId;
+    type MoveError = QubicMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winning_line() {
+            Some(line) => match self.cells[line[0]] {
+                Cell::Player(p) => Some(Outcome::Win(p)),
+                Cell::Unmarked => None,
+            },
+            None if self.cells.iter().all(|c| *c != Cell::Unmarked) => Some(Outcome::Draw),
+            None => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<QubicCellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        (0..NUM_CELLS)
+            .filter(|idx| self.cells[*idx] == Cell::Unmarked)
+            .map(QubicCellId::from_idx)
+            .collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<QubicCellId, QubicMoveError> {
+        let (layer_str, cell_str) = input
+            .trim()
+            .split_once('/')
+            .ok_or(QubicMoveError::BadCell(CellIdError::UnparseableInput))?;
+        let layer = match layer_str.trim().parse::<usize>() {
+            Ok(layer) if (1..=SIZE).contains(&layer) => layer - 1,
+            Ok(_) => return Err(QubicMoveError::BadCell(CellIdError::OutOfBounds)),
+            Err(_) => return Err(QubicMoveError::BadCell(CellIdError::UnparseableInput)),
+        };
+        let cell =
+            CellId::parse_for(cell_str.trim(), SIZE, SIZE).map_err(QubicMoveError::BadCell)?;
+        Ok(QubicCellId { layer, cell })
+    }
+
+    fn check_move(&self, mv: &QubicCellId) -> Result<(), QubicMoveError> {
+        if self.outcome().is_some() {
+            return Err(QubicMoveError::GameOver);
+        }
+        if self.cell(*mv) != Cell::Unmarked {
+            return Err(QubicMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: QubicCellId) {
+        self.cells[mv.to_idx()] = Cell::Player(self.player);
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        "Type a layer 1-4 and a cell a1-d4 on it e.g. 2/b3".to_string()
+    }
+
+    // win if possible, block if needed, otherwise search a couple of moves ahead,
+    // as the cube is too big to look far
+    fn computer_move(&self, depth: usize) -> Option<QubicCellId> {
+        if self.outcome().is_some() {
+            return None;
+        }
+        self.completing_move(self.player)
+            .or_else(|| self.completing_move(next_player(self.player)))
+            .or_else(|| search_position(self, depth.min(2)))
+    }
+
+    // lines with more of one player's marks and none of the other's are worth much more
+    fn evaluate(&self, player: Player) -> i32 {
+        let weight = |marks: usize| match marks {
+            0 => 0,
+            1 => 1,
+            2 => 10,
+            _ => 100,
+        };
+        let score = |p: Player| self.open_line_counts(p).map(weight).sum::<i32>();
+        score(player) - score(next_player(player))
+    }
+}
+
+// the layers are drawn side by side, e.g.
+//
+//    1          2          3          4
+//    a b c d    a b c d    a b c d    a b c d
+// 1  X . . .    . . . .    . . . .    . . . .
+// ...
+impl fmt::Display for Qubic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let gap = "    ";
+        let numbers: Vec<String> = (0..SIZE).map(|layer| format!("{:<7}", layer + 1)).collect();
+        writeln!(f, "   {}", numbers.join(gap).trim_end())?;
+        writeln!(f, "   {}", ["a b c d"; SIZE].join(gap))?;
+        for row in 0..SIZE {
+            let blocks: Vec<String> = (0..SIZE)
+                .map(|layer| {
+                    let glyphs: Vec<String> = (0..SIZE)
+                        .map(|column| {
+                            let cell_id = QubicCellId {
+                                layer,
+                                cell: CellId { row, column },
+                            };
+                            match self.cell(cell_id) {
+                                Cell::Player(p) => p.to_string(),
+                                Cell::Unmarked => ".".to_string(),
+                            }
+                        })
+                        .collect();
+                    glyphs.join(" ")
+                })
+                .collect();
+            writeln!(f, "{}  {}", row + 1, blocks.join(gap))?;
+        }
+        if let Some(line) = self.winning_line() {
+            let cells: Vec<String> = line
+                .iter()
+                .map(|idx| QubicCellId::from_idx(*idx).to_string())
+                .collect();
+            writeln!(f, "Line: {}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(qubic: &mut Qubic, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = qubic.parse_move(mv_str).unwrap();
+            qubic.check_move(&mv).unwrap();
+            qubic.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_76_lines() {
+        assert_eq!(lines().len(), 76);
+        for line in lines() {
+            assert_eq!(line.len(), SIZE);
+        }
+        // the four space diagonals
+        let space_diagonal = vec![0, 21, 42, 63];
+        assert!(lines().contains(&space_diagonal));
+        assert_eq!(
+            lines()
+                .iter()
+                .filter(|line| {
+                    let layers: Vec<usize> = line.iter().map(|idx| idx / 16).collect();
+                    let rows: Vec<usize> = line.iter().map(|idx| idx / 4 % 4).collect();
+                    let columns: Vec<usize> = line.iter().map(|idx| idx % 4).collect();
+                    [layers, rows, columns]
+                        .iter()
+                        .all(|coords| coords[0] != coords[1])
+                })
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_cell_ids() {
+        let qubic = Qubic::default();
+        let mv = qubic.parse_move("2/B3").unwrap();
+        assert_eq!(mv.layer, 1);
+        assert_eq!(mv.to_string(), "2/b3");
+        assert_eq!(QubicCellId::from_idx(mv.to_idx()), mv);
+        assert!(matches!(
+            qubic.parse_move("5/a1"),
+            Err(QubicMoveError::BadCell(CellIdError::OutOfBounds))
+        ));
+        assert!(qubic.parse_move("a1").is_err());
+    }
+
+    #[test]
+    fn test_space_diagonal_win() {
+        let mut qubic = Qubic::default();
+        play_all(&mut qubic, "1/a1 1/a2 2/b2 1/a3 3/c3 1/b3");
+        assert_eq!(qubic.outcome(), None);
+        play_all(&mut qubic, "4/d4");
+        assert_eq!(qubic.outcome(), Some(Outcome::Win(Player::X)));
+        assert!(qubic.to_string().ends_with("Line: 1/a1 2/b2 3/c3 4/d4\n"));
+        assert!(matches!(
+            qubic.check_move(&qubic.parse_move("4/a1").unwrap()),
+            Err(QubicMoveError::GameOver)
+        ));
+    }
+
+    #[test]
+    fn test_computer_wins_and_blocks() {
+        let mut qubic = Qubic::default();
+        play_all(&mut qubic, "1/a1 2/a1 1/b1 2/b1 1/c1");
+        // O must block d1 on layer 1
+        assert_eq!(qubic.computer_move(2), qubic.parse_move("1/d1").ok());
+        play_all(&mut qubic, "3/a1");
+        // X takes the win
+        assert_eq!(qubic.computer_move(2), qubic.parse_move("1/d1").ok());
+    }
+
+    #[test]
+    fn test_display() {
+        let mut qubic = Qubic::default();
+        play_all(&mut qubic, "2/b1");
+        let rendered = qubic.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "   1          2          3          4");
+        assert_eq!(lines[2], "1  . . . .    . X . .    . . . .    . . . .");
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::game_update::next_player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+// solves modes whose positions can come round again, where searching ahead
+// never bottoms out. every position reachable from `start` is labelled by
+// working back from the finished ones: a position is won if a move leads to
+// one lost for the other player, and lost once all of its moves lead to ones
+// they win. whatever is never labelled can be kept going forever, a draw.
+// `key` tells positions apart and `forget` clears any record of earlier
+// moves, so that reaching a position by another route finds the same one
+pub fn solve_with_loops<P: Position, K: Hash + Eq>(
+    start: &P,
+    key: impl Fn(&P) -> K,
+    forget: impl Fn(&mut P),
+) -> (Outcome, Option<P::Move>) {
+    let mut first = start.clone();
+    forget(&mut first);
+    let mut idxs = HashMap::from([(key(&first), 0)]);
+    let mut positions = vec![first];
+    let mut children: Vec<Vec<(P::Move, usize)>> = vec![];
+    let mut parents: Vec<Vec<usize>> = vec![vec![]];
+    while children.len() < positions.len() {
+        let idx = children.len();
+        let mut moves = vec![];
+        for mv in positions[idx].legal_moves() {
+            let mut next = positions[idx].clone();
+            next.play(mv);
+            forget(&mut next);
+            let child = *idxs.entry(key(&next)).or_insert_with(|| {
+                positions.push(next);
+                parents.push(vec![]);
+                positions.len() - 1
+            });
+            parents[child].push(idx);
+            moves.push((mv, child));
+        }
+        children.push(moves);
+    }
+
+    // whether the player to move wins, and in how many moves
+    let mut values: Vec<Option<(bool, usize)>> = vec![None; positions.len()];
+    let mut moves_left: Vec<usize> = children.iter().map(|moves| moves.len()).collect();
+    let mut labelled = VecDeque::new();
+    for (idx, position) in positions.iter().enumerate() {
+        let loser = next_player(position.player_to_move());
+        if children[idx].is_empty() && position.outcome() == Some(Outcome::Win(loser)) {
+            values[idx] = Some((false, 0));
+            labelled.push_back(idx);
+        }
+    }
+    // positions come off in the order they were labelled, so the first lost
+    // move found is the quickest win and the last won one the slowest loss
+    while let Some(idx) = labelled.pop_front() {
+        let Some((wins, moves)) = values[idx] else {
+            continue;
+        };
+        for parent in &parents[idx] {
+            if values[*parent].is_some() {
+                continue;
+            }
+            if !wins {
+                values[*parent] = Some((true, moves + 1));
+                labelled.push_back(*parent);
+            } else {
+                moves_left[*parent] -= 1;
+                if moves_left[*parent] == 0 {
+                    values[*parent] = Some((false, moves + 1));
+                    labelled.push_back(*parent);
+                }
+            }
+        }
+    }
+
+    // win as quickly as possible, lose as slowly as possible, or keep a draw going
+    let best = children[0]
+        .iter()
+        .max_by_key(|(_, child)| match values[*child] {
+            Some((false, moves)) => (2, -(moves as isize)),
+            None => (1, 0),
+            Some((true, moves)) => (0, moves as isize),
+        });
+    let player = start.player_to_move();
+    let outcome = match values[0] {
+        Some((true, _)) => Outcome::Win(player),
+        Some((false, _)) => Outcome::Win(next_player(player)),
+        None => Outcome::Draw,
+    };
+    (outcome, best.map(|(mv, _)| *mv))
+}
@@ -1,26 +1,26 @@
-use crate::board::{Board, NUM_CELLS, NUM_COLS as ROW_SIZE, NUM_ROWS as COL_SIZE};
-use crate::cell::Cell;
+use crate::board::Board;
 use crate::cell_iterator::CellIterator;
 
 pub struct RowIterator<'a> {
     count: usize,
-    cells: &'a [Cell; NUM_CELLS],
+    board: &'a Board,
 }
 
 impl<'a> RowIterator<'a> {
-    pub fn new(cells: &'a [Cell; NUM_CELLS]) -> RowIterator<'a> {
-        RowIterator { count: 0, cells }
+    pub fn new(board: &'a Board) -> RowIterator<'a> {
+        RowIterator { count: 0, board }
     }
 }
 
 impl<'a> Iterator for RowIterator<'a> {
     type Item = CellIterator<'a>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.count >= COL_SIZE {
+        if self.count >= self.board.num_rows {
             None
         } else {
-            let next_row_cell_idx = |row_idx: usize, count: usize| row_idx * ROW_SIZE + count;
-            let row_iterator = CellIterator::new(self.cells, self.count, next_row_cell_idx);
+            let row_size = self.board.num_cols;
+            let row_cell_idxs = (0..row_size).map(|count| self.count * row_size + count);
+            let row_iterator = CellIterator::new(&self.board.cells, row_cell_idxs.collect());
             self.count += 1;
             Some(row_iterator)
         }
@@ -28,16 +28,16 @@ impl<'a> Iterator for RowIterator<'a> {
 }
 
 impl Board {
-    pub fn rows(&self) -> RowIterator {
-        RowIterator::new(&self.cells)
+    pub fn rows(&self) -> RowIterator<'_> {
+        RowIterator::new(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::*;
     use crate::board::Board;
+    use crate::cell::Cell;
     use crate::player::Player;
 
     #[test]
@@ -50,18 +50,18 @@ mod tests {
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::X)));
-        assert!(matches!(row.next(), None));
+        assert!(row.next().is_none());
 
         let mut row = rows.next().unwrap();
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::O)));
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::O)));
-        assert!(matches!(row.next(), None));
+        assert!(row.next().is_none());
 
         let mut row = rows.next().unwrap();
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::X)));
         assert!(matches!(row.next().unwrap(), Cell::Player(Player::O)));
-        assert!(matches!(row.next(), None));
+        assert!(row.next().is_none());
     }
 }
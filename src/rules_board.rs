@@ -0,0 +1,271 @@
+use core::fmt;
+use std::rc::Rc;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::cell_input::InputScheme;
+use crate::game_record::GameRecord;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::{IllegalMove, Outcome, Ruleset};
+use crate::search_position::search_position;
+use crate::solve_position::{solve_position, Solvable};
+use crate::theme::Theme;
+
+// boards with more cells take too long to solve to the end
+pub const MAX_SOLVED_CELLS: usize = 16;
+
+#[derive(Debug)]
+pub enum BoardMoveError {
+    // a cell that could not be read, on a board with this many rows and columns
+    BadCell(CellIdError, usize, usize),
+    Occupied,
+    GameOver,
+}
+
+impl fmt::Display for BoardMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardMoveError::BadCell(CellIdError::OutOfBounds, _, _) => {
+                write!(f, "That cell is off the board")
+            }
+            BoardMoveError::BadCell(_, num_rows, num_cols) => write!(
+                f,
+                "Please use a-{} and 1-{} e.g. b2",
+                (b'a' + (num_cols - 1) as u8) as char,
+                num_rows
+            ),
+            BoardMoveError::Occupied => write!(f, "That cell is occupied"),
+            BoardMoveError::GameOver => write!(f, "The game is over"),
+        }
+    }
+}
+
+// a board and the rules it is played by, so every variant played by marking
+// the cells of one board is a `Position` like the other modes
+#[derive(Debug, Clone)]
+pub struct RulesBoard {
+    pub board: Board,
+    pub rules: Rc<dyn Ruleset>,
+    pub input_scheme: InputScheme,
+    pub theme: Theme,
+    pub last_move: Option<CellId>,
+}
+
+impl RulesBoard {
+    pub fn new(board: Board, rules: Rc<dyn Ruleset>) -> RulesBoard {
+        RulesBoard {
+            board,
+            rules,
+            input_scheme: InputScheme::default(),
+            theme: Theme::default(),
+            last_move: None,
+        }
+    }
+}
+
+impl fmt::Display for RulesBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted = self.rules.winning_line(&self.board).unwrap_or_default();
+        write!(
+            f,
+            "{}",
+            self.theme
+                .render_board(&self.board, &highlighted, self.last_move)
+        )
+    }
+}
+
+impl Position for RulesBoard {
+    type Move = CellId;
+    type MoveError = BoardMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.rules.player_to_move(&self.board)
+    }
+
+    fn num_players(&self) -> usize {
+        self.rules.num_players()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.rules.outcome(&self.board)
+    }
+
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.rules.legal_moves(&self.board)
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, BoardMoveError> {
+        let (num_rows, num_cols) = (self.board.num_rows, self.board.num_cols);
+        self.input_scheme
+            .parse(input, num_rows, num_cols)
+            .map_err(|err| BoardMoveError::BadCell(err, num_rows, num_cols))
+    }
+
+    fn check_move(&self, mv: &CellId) -> Result<(), BoardMoveError> {
+        self.rules
+            .check_move(&self.board, mv)
+            .map_err(|err| match err {
+                IllegalMove::Occupied => BoardMoveError::Occupied,
+                IllegalMove::GameOver => BoardMoveError::GameOver,
+            })
+    }
+
+    fn play(&mut self, mv: CellId) {
+        let player = self.player_to_move();
+        self.board.cells[mv.to_idx(self.board.num_cols)] = Cell::Player(player);
+        self.last_move = Some(mv);
+    }
+
+    fn move_help(&self) -> String {
+        self.input_scheme
+            .help(self.board.num_rows, self.board.num_cols)
+    }
+
+    // solved where the board is small enough, searched where it is not
+    fn computer_move(&self, depth: usize) -> Option<CellId> {
+        match self.solve() {
+            Some((_, best_move)) => best_move,
+            None => search_position(self, depth),
+        }
+    }
+
+    fn evaluate(&self, player: Player) -> i32 {
+        self.rules.evaluate(&self.board, player)
+    }
+
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<CellId>)> {
+        if self.board.num_cells() > MAX_SOLVED_CELLS {
+            return None;
+        }
+        Some(solve_position(self))
+    }
+
+    fn winning_moves(&self) -> Vec<CellId> {
+        self.rules
+            .winning_line(&self.board)
+            .unwrap_or_default()
+            .iter()
+            .map(|idx| CellId::from_idx(*idx, self.board.num_cols))
+            .collect()
+    }
+
+    fn record(&self, moves: &[CellId]) -> Option<GameRecord> {
+        Some(GameRecord {
+            variant: self.rules.variant(),
+            first: self.rules.first_player(),
+            patterns: self.rules.win_patterns(),
+            moves: moves.to_vec(),
+            ..GameRecord::new(self.board.num_rows)
+        })
+    }
+}
+
+// who is to move follows from the marks, so the cells are enough to tell boards apart
+impl Solvable for RulesBoard {
+    type Key = Vec<Cell>;
+
+    fn key(&self) -> Vec<Cell> {
+        self.board.cells.clone()
+    }
+
+    fn score(&self, player: Player) -> Option<i32> {
+        self.rules.score(&self.board, player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::misere::MisereRules;
+    use crate::ruleset::ClassicRules;
+
+    fn solve(board_str: &str, rules: Rc<dyn Ruleset>) -> Option<(Outcome, Option<CellId>)> {
+        let board = board_str.parse::<Board>().unwrap();
+        RulesBoard::new(board, rules).solve()
+    }
+
+    #[test]
+    fn test_empty_board_is_draw() {
+        let (outcome, best_move) = solve("         ", Rc::new(ClassicRules)).unwrap();
+        assert_eq!(outcome, Outcome::Draw);
+        assert!(best_move.is_some());
+    }
+
+    #[test]
+    fn test_takes_the_win() {
+        let (outcome, best_move) = solve("XX OO    ", Rc::new(ClassicRules)).unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        assert_eq!(best_move, "c1".parse::<CellId>().ok());
+    }
+
+    #[test]
+    fn test_blocks_the_win() {
+        let (outcome, best_move) = solve("XX  O    ", Rc::new(ClassicRules)).unwrap();
+        assert_eq!(outcome, Outcome::Draw);
+        assert_eq!(best_move, "c1".parse::<CellId>().ok());
+    }
+
+    #[test]
+    fn test_finished_board() {
+        let (outcome, best_move) = solve("XXXOO    ", Rc::new(ClassicRules)).unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        assert!(best_move.is_none());
+    }
+
+    #[test]
+    fn test_misere() {
+        let (outcome, _) = solve("         ", Rc::new(MisereRules)).unwrap();
+        assert_eq!(outcome, Outcome::Draw);
+
+        let (_, best_move) = solve("XX OO    ", Rc::new(MisereRules)).unwrap();
+        assert_ne!(best_move, "c1".parse::<CellId>().ok());
+
+        let (outcome, _) = solve("XXXOO    ", Rc::new(MisereRules)).unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::O));
+    }
+
+    #[test]
+    fn test_large_board_is_searched() {
+        let board = format!("XXXX OOOO{}", " ".repeat(16))
+            .parse::<Board>()
+            .unwrap();
+        let position = RulesBoard::new(board, Rc::new(ClassicRules));
+        assert!(position.solve().is_none());
+        let e1 = CellId::from_idx(4, position.board.num_cols);
+        assert_eq!(position.computer_move(2), Some(e1));
+    }
+
+    #[test]
+    fn test_moves() {
+        let board = "XXXOO    ".parse::<Board>().unwrap();
+        let position = RulesBoard::new(board, Rc::new(ClassicRules));
+        let a1 = "a1".parse::<CellId>().unwrap();
+        assert_eq!(position.parse_move("top left").unwrap(), a1);
+        assert!(matches!(
+            position.parse_move("d4"),
+            Err(BoardMoveError::BadCell(CellIdError::OutOfBounds, 3, 3))
+        ));
+        // the game is over before the cell is looked at
+        assert!(matches!(
+            position.check_move(&a1),
+            Err(BoardMoveError::GameOver)
+        ));
+        let line: Vec<String> = position
+            .winning_moves()
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        assert_eq!(line, ["a1", "b1", "c1"]);
+    }
+}
@@ -0,0 +1,256 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::{board_has_win, tracks, winning_line};
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::cells_are_valid::{cells_are_valid, CellsImpossibleError};
+use crate::game_update::{self, check_cell, next_in_turn};
+use crate::patterns::WinPatterns;
+use crate::player::Player;
+use crate::search_position::score_lines;
+use crate::solve_position::score_outcome;
+use crate::variant::Variant;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Player),
+    Draw,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Win(p) => write!(f, "{} wins", p),
+            Outcome::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum IllegalMove {
+    Occupied,
+    GameOver,
+}
+
+// everything that decides how a game is played on a board, so `Game`,
+// the solver and the validator work the same for every variant
+pub trait Ruleset: fmt::Debug {
+    // which variant this is, e.g. for saving records
+    fn variant(&self) -> Variant;
+
+    // the result once the game is over, None while it is still going
+    fn outcome(&self, board: &Board) -> Option<Outcome>;
+
+    // how many players take turns, most variants are played by X and O
+    fn num_players(&self) -> usize {
+        2
+    }
+
+    // who made the first move, the others are to move when they have fewer marks
+    fn first_player(&self) -> Player {
+        Player::X
+    }
+
+    fn player_to_move(&self, board: &Board) -> Player {
+        game_update::player_to_move(board, self.first_player(), self.num_players())
+    }
+
+    fn next_player(&self, player: Player) -> Player {
+        next_in_turn(player, self.num_players())
+    }
+
+    fn check_move(&self, board: &Board, cell_id: &CellId) -> Result<(), IllegalMove> {
+        if self.outcome(board).is_some() {
+            return Err(IllegalMove::GameOver);
+        }
+        check_cell(board, cell_id).map_err(|_| IllegalMove::Occupied)
+    }
+
+    // the moves open to the player to move, on a board where the game is still going
+    fn legal_moves(&self, board: &Board) -> Vec<CellId> {
+        board.unmarked_cell_ids().collect()
+    }
+
+    // the cells that decided the game, so they can be highlighted
+    fn winning_line(&self, board: &Board) -> Option<Vec<usize>> {
+        winning_line(board)
+    }
+
+    // the cells of every line that can win the game, for guessing who is ahead
+    fn lines(&self, board: &Board) -> Vec<Vec<usize>> {
+        tracks(board, false)
+            .map(|track| track.cell_idxs().to_vec())
+            .collect()
+    }
+
+    // any ways to win other than the usual lines, for saving records
+    fn win_patterns(&self) -> Option<WinPatterns> {
+        None
+    }
+
+    // whether a board could come up in a real game
+    fn validate(&self, board: &Board) -> Result<(), CellsImpossibleError> {
+        self.validate_from(board, self.first_player())
+    }
+
+    // whether a board could come up in a game that `first` started
+    fn validate_from(&self, board: &Board, first: Player) -> Result<(), CellsImpossibleError> {
+        cells_are_valid(board, first)
+    }
+
+    // how good a finished board is for `player`, wins are worth more
+    // the more cells are left, so quicker wins are preferred
+    fn score(&self, board: &Board, player: Player) -> Option<i32> {
+        score_outcome(self.outcome(board), board, player)
+    }
+
+    // a guess at how good an unfinished board is for `player`, for boards
+    // too large to solve
+    fn evaluate(&self, board: &Board, player: Player) -> i32 {
+        let marks = |line: Vec<usize>| {
+            line.into_iter().filter_map(|idx| match board.cells[idx] {
+                Cell::Player(p) => Some(p),
+                Cell::Unmarked => None,
+            })
+        };
+        score_lines(self.lines(board).into_iter().map(marks), player)
+    }
+}
+
+// completing a line wins
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ClassicRules;
+
+impl Ruleset for ClassicRules {
+    fn variant(&self) -> Variant {
+        Variant::Classic
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        match board_has_win(board) {
+            Cell::Player(p) => Some(Outcome::Win(p)),
+            Cell::Unmarked if board.is_full() => Some(Outcome::Draw),
+            Cell::Unmarked => None,
+        }
+    }
+}
+
+// `rules` with `first` making the first move
+pub fn with_first_player(rules: Box<dyn Ruleset>, first: Player) -> Box<dyn Ruleset> {
+    match first {
+        Player::X => rules,
+        _ => Box::new(FirstPlayer { first, rules }),
+    }
+}
+
+// any ruleset, with O making the first move instead
+#[derive(Debug)]
+pub struct FirstPlayer {
+    pub first: Player,
+    pub rules: Box<dyn Ruleset>,
+}
+
+impl Ruleset for FirstPlayer {
+    fn variant(&self) -> Variant {
+        self.rules.variant()
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        self.rules.outcome(board)
+    }
+
+    fn num_players(&self) -> usize {
+        self.rules.num_players()
+    }
+
+    fn first_player(&self) -> Player {
+        self.first
+    }
+
+    fn next_player(&self, player: Player) -> Player {
+        self.rules.next_player(player)
+    }
+
+    fn check_move(&self, board: &Board, cell_id: &CellId) -> Result<(), IllegalMove> {
+        self.rules.check_move(board, cell_id)
+    }
+
+    fn legal_moves(&self, board: &Board) -> Vec<CellId> {
+        self.rules.legal_moves(board)
+    }
+
+    fn winning_line(&self, board: &Board) -> Option<Vec<usize>> {
+        self.rules.winning_line(board)
+    }
+
+    fn lines(&self, board: &Board) -> Vec<Vec<usize>> {
+        self.rules.lines(board)
+    }
+
+    fn win_patterns(&self) -> Option<WinPatterns> {
+        self.rules.win_patterns()
+    }
+
+    fn validate_from(&self, board: &Board, first: Player) -> Result<(), CellsImpossibleError> {
+        self.rules.validate_from(board, first)
+    }
+
+    fn score(&self, board: &Board, player: Player) -> Option<i32> {
+        self.rules.score(board, player)
+    }
+
+    fn evaluate(&self, board: &Board, player: Player) -> i32 {
+        self.rules.evaluate(board, player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_classic_outcome() {
+        let board = "XXXOO    ".parse::<Board>().unwrap();
+        assert_eq!(ClassicRules.outcome(&board), Some(Outcome::Win(Player::X)));
+        assert_eq!(ClassicRules.score(&board, Player::X), Some(5));
+        assert_eq!(ClassicRules.score(&board, Player::O), Some(-5));
+
+        let board = "XOXXOXOXO".parse::<Board>().unwrap();
+        assert_eq!(ClassicRules.outcome(&board), Some(Outcome::Draw));
+        assert_eq!(ClassicRules.score(&board, Player::X), Some(0));
+
+        let board = "XX OO    ".parse::<Board>().unwrap();
+        assert_eq!(ClassicRules.outcome(&board), None);
+        assert_eq!(ClassicRules.score(&board, Player::X), None);
+    }
+
+    #[test]
+    fn test_classic_moves() {
+        let board = "XX OO    ".parse::<Board>().unwrap();
+        assert_eq!(ClassicRules.player_to_move(&board), Player::X);
+        assert_eq!(ClassicRules.legal_moves(&board).len(), 5);
+        let a1 = "a1".parse::<CellId>().unwrap();
+        let c1 = "c1".parse::<CellId>().unwrap();
+        assert_eq!(
+            ClassicRules.check_move(&board, &a1),
+            Err(IllegalMove::Occupied)
+        );
+        assert_eq!(ClassicRules.check_move(&board, &c1), Ok(()));
+
+        let board = "XXXOO    ".parse::<Board>().unwrap();
+        assert_eq!(
+            ClassicRules.check_move(&board, &c1),
+            Err(IllegalMove::GameOver)
+        );
+    }
+
+    #[test]
+    fn test_classic_evaluate() {
+        // X's a1 is on three lines and O's a2 on two, and they share column a
+        let board = "X  O     ".parse::<Board>().unwrap();
+        assert_eq!(ClassicRules.evaluate(&board, Player::X), 1);
+        assert_eq!(ClassicRules.evaluate(&board, Player::O), -1);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+// more than any `evaluate` should return, so finished games always count for more
+pub const WIN_SCORE: i32 = 1_000_000;
+
+// what a line holding `marks` of one player's marks and nobody else's is
+// worth to them, ten times more for each mark. long lines saturate rather
+// than overflow
+pub fn line_score(marks: usize) -> i32 {
+    10_i32.saturating_pow(marks.saturating_sub(1) as u32)
+}
+
+// how good `lines` look for `player`, given whose marks are in each line.
+// a line only one player has marks in could still become theirs, and the
+// total stays below a win however many lines there are
+pub fn score_lines<L: IntoIterator<Item = Player>>(
+    lines: impl IntoIterator<Item = L>,
+    player: Player,
+) -> i32 {
+    let mut score: i32 = 0;
+    for line in lines {
+        let mut marks = line.into_iter();
+        let Some(owner) = marks.next() else {
+            continue;
+        };
+        let mut count = 1;
+        let mut shared = false;
+        for p in marks {
+            shared |= p != owner;
+            count += 1;
+        }
+        match (shared, owner == player) {
+            (true, _) => {}
+            (false, true) => score = score.saturating_add(line_score(count)),
+            (false, false) => score = score.saturating_sub(line_score(count)),
+        }
+    }
+    score.clamp(1 - WIN_SCORE, WIN_SCORE - 1)
+}
+
+// scores are from the point of view of `player`, the player to move.
+// wins found with more depth left are quicker, so they are worth more
+fn negamax<P: Position>(position: &P, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+    let player = position.player_to_move();
+    if let Some(outcome) = position.outcome() {
+        return match outcome {
+            Outcome::Win(p) if p == player => WIN_SCORE + depth as i32,
+            Outcome::Win(_) => -WIN_SCORE - depth as i32,
+            Outcome::Draw => 0,
+        };
+    }
+    if depth == 0 {
+        return position.evaluate(player);
+    }
+    let mut best = i32::MIN + 1;
+    for mv in position.legal_moves() {
+        let score = score_move(position, player, mv, depth, alpha, beta);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+// the score of making `mv`, some modes let a player move more than once in a row
+fn score_move<P: Position>(
+    position: &P,
+    player: Player,
+    mv: P::Move,
+    depth: usize,
+    alpha: i32,
+    beta: i32,
+) -> i32 {
+    let mut next = position.clone();
+    next.play(mv);
+    if next.player_to_move() == player {
+        negamax(&next, depth - 1, alpha, beta)
+    } else {
+        -negamax(&next, depth - 1, -beta, -alpha)
+    }
+}
+
+// the best move found by looking `depth` moves ahead, None once the game is over
+pub fn search_position<P: Position>(position: &P, depth: usize) -> Option<P::Move> {
+    if position.outcome().is_some() {
+        return None;
+    }
+    let player = position.player_to_move();
+    let mut best: Option<(i32, P::Move)> = None;
+    for mv in position.legal_moves() {
+        let alpha = best.map_or(i32::MIN + 1, |(score, _)| score);
+        let score = score_move(position, player, mv, depth.max(1), alpha, i32::MAX);
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, mv));
+        }
+    }
+    best.map(|(_, mv)| mv)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::board::Board;
+    use crate::ultimate::{Ultimate, UltimateCellId};
+
+    #[test]
+    fn test_takes_the_win() {
+        // X has won A1 and B1, and is sent to C1 with two in a row there
+        let mut boards = vec![Board::default(); 9];
+        boards[2] = "XX OO    ".parse::<Board>().unwrap();
+        let ultimate = Ultimate {
+            boards,
+            meta: "XX OO    ".parse::<Board>().unwrap(),
+            next_board: "c1".parse().ok(),
+            ..Ultimate::default()
+        };
+        for depth in [1, 3] {
+            let best_move = search_position(&ultimate, depth);
+            assert_eq!(best_move, "C1/c1".parse::<UltimateCellId>().ok());
+        }
+    }
+
+    #[test]
+    fn test_score_lines() {
+        let lines = [
+            vec![Player::X, Player::X],
+            vec![Player::O],
+            vec![Player::X, Player::O],
+        ];
+        assert_eq!(score_lines(lines.clone(), Player::X), 9);
+        assert_eq!(score_lines(lines, Player::O), -9);
+        // a line long enough to overflow stays below a win
+        let long = [vec![Player::X; 40]];
+        assert_eq!(score_lines(long.clone(), Player::X), WIN_SCORE - 1);
+        assert_eq!(score_lines(long, Player::O), 1 - WIN_SCORE);
+        assert_eq!(line_score(40), i32::MAX);
+    }
+
+    #[test]
+    fn test_finished_game_has_no_move() {
+        let ultimate = Ultimate {
+            meta: "XXXOO    ".parse::<Board>().unwrap(),
+            ..Ultimate::default()
+        };
+        assert_eq!(search_position(&ultimate, 2), None);
+    }
+}
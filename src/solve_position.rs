@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::board::Board;
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+// a two player position small enough to search to the end of every game
+pub trait Solvable: Position {
+    type Key: Eq + Hash;
+
+    // positions with the same key play out the same, however they were reached
+    fn key(&self) -> Self::Key;
+
+    // how good a finished position is for `player`, None while the game is going
+    fn score(&self, player: Player) -> Option<i32>;
+
+    // the moves worth looking at, which can leave out ones that cannot matter
+    fn moves_to_solve(&self) -> Vec<Self::Move> {
+        self.legal_moves()
+    }
+}
+
+// wins are worth more the more cells are left, so quicker wins are preferred
+pub fn score_outcome(outcome: Option<Outcome>, board: &Board, player: Player) -> Option<i32> {
+    let weight = (board.num_cells() - board.num_marked() + 1) as i32;
+    outcome.map(|outcome| match outcome {
+        Outcome::Win(p) if p == player => weight,
+        Outcome::Win(_) => -weight,
+        Outcome::Draw => 0,
+    })
+}
+
+#[derive(Copy, Clone)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+type Table<K> = HashMap<K, (i32, Bound)>;
+
+// scores are from the point of view of the player to move
+fn negamax<P: Solvable>(
+    position: &P,
+    mut alpha: i32,
+    mut beta: i32,
+    table: &mut Table<P::Key>,
+) -> i32 {
+    let player = position.player_to_move();
+    if let Some(score) = position.score(player) {
+        return score;
+    }
+    let key = position.key();
+    let original_alpha = alpha;
+    if let Some(&(score, bound)) = table.get(&key) {
+        match bound {
+            Bound::Exact => return score,
+            Bound::Lower => alpha = alpha.max(score),
+            Bound::Upper => beta = beta.min(score),
+        }
+        if alpha >= beta {
+            return score;
+        }
+    }
+
+    let mut best = i32::MIN + 1;
+    for mv in position.moves_to_solve() {
+        let score = score_move(position, player, mv, alpha, beta, table);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    let bound = if best <= original_alpha {
+        Bound::Upper
+    } else if best >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    table.insert(key, (best, bound));
+    best
+}
+
+// the score of making `mv`, some modes let a player move more than once in a row
+fn score_move<P: Solvable>(
+    position: &P,
+    player: Player,
+    mv: P::Move,
+    alpha: i32,
+    beta: i32,
+    table: &mut Table<P::Key>,
+) -> i32 {
+    let mut next = position.clone();
+    next.play(mv);
+    if next.player_to_move() == player {
+        negamax(&next, alpha, beta, table)
+    } else {
+        -negamax(&next, -beta, -alpha, table)
+    }
+}
+
+// the result with best play and a move that gets it, no move once the game is over
+pub fn solve_position<P: Solvable>(position: &P) -> (Outcome, Option<P::Move>) {
+    if let Some(outcome) = position.outcome() {
+        return (outcome, None);
+    }
+    let player = position.player_to_move();
+    let mut table = Table::new();
+    let mut best: Option<(i32, P::Move)> = None;
+    for mv in position.moves_to_solve() {
+        let alpha = best.map_or(i32::MIN + 1, |(score, _)| score);
+        let score = score_move(position, player, mv, alpha, i32::MAX, &mut table);
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, mv));
+        }
+    }
+    match best {
+        None => (Outcome::Draw, None),
+        Some((score, mv)) => {
+            let outcome = match score {
+                s if s > 0 => Outcome::Win(player),
+                s if s < 0 => Outcome::Win(next_player(player)),
+                _ => Outcome::Draw,
+            };
+            (outcome, Some(mv))
+        }
+    }
+}
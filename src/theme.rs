@@ -0,0 +1,384 @@
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::player::{Player, MAX_PLAYERS};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnsiColor {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl AnsiColor {
+    fn code(self) -> u8 {
+        match self {
+            AnsiColor::Red => 31,
+            AnsiColor::Green => 32,
+            AnsiColor::Yellow => 33,
+            AnsiColor::Blue => 34,
+            AnsiColor::Magenta => 35,
+            AnsiColor::Cyan => 36,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownColor;
+
+impl FromStr for AnsiColor {
+    type Err = UnknownColor;
+    fn from_str(color_str: &str) -> Result<Self, Self::Err> {
+        match color_str {
+            "red" => Ok(AnsiColor::Red),
+            "green" => Ok(AnsiColor::Green),
+            "yellow" => Ok(AnsiColor::Yellow),
+            "blue" => Ok(AnsiColor::Blue),
+            "magenta" => Ok(AnsiColor::Magenta),
+            "cyan" => Ok(AnsiColor::Cyan),
+            _ => Err(UnknownColor),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Borders {
+    Ascii,
+    Unicode,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorChoice {
+    // colour only when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub x_glyph: String,
+    pub o_glyph: String,
+    // the glyphs and colours of any players after X and O, in turn order
+    pub other_glyphs: Vec<String>,
+    pub unmarked_glyph: String,
+    pub x_color: AnsiColor,
+    pub o_color: AnsiColor,
+    pub other_colors: Vec<AnsiColor>,
+    pub color: bool,
+    pub borders: Borders,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            x_glyph: "X".to_string(),
+            o_glyph: "O".to_string(),
+            other_glyphs: Player::all(MAX_PLAYERS)
+                .skip(2)
+                .map(|p| p.to_string())
+                .collect(),
+            unmarked_glyph: " ".to_string(),
+            x_color: AnsiColor::Red,
+            o_color: AnsiColor::Blue,
+            other_colors: vec![AnsiColor::Yellow, AnsiColor::Magenta],
+            color: false,
+            borders: Borders::Ascii,
+        }
+    }
+}
+
+// how many terminal columns a glyph takes up, emoji and east asian
+// characters take two and combining characters take none
+pub fn display_width(glyph: &str) -> usize {
+    glyph
+        .chars()
+        .map(|c| match c as u32 {
+            0x200D | 0xFE00..=0xFE0F | 0x0300..=0x036F => 0,
+            0x1100..=0x115F
+            | 0x231A..=0x231B
+            | 0x23E9..=0x23EC
+            | 0x25FD..=0x25FE
+            | 0x2614..=0x2615
+            | 0x2648..=0x2653
+            | 0x26AA..=0x26AB
+            | 0x26BD..=0x26BE
+            | 0x26C4..=0x26C5
+            | 0x26D4
+            | 0x26EA
+            | 0x26F2..=0x26F5
+            | 0x26FA
+            | 0x26FD
+            | 0x2705
+            | 0x270A..=0x270B
+            | 0x2728
+            | 0x274C
+            | 0x274E
+            | 0x2753..=0x2755
+            | 0x2757
+            | 0x2795..=0x2797
+            | 0x27B0
+            | 0x27BF
+            | 0x2B1B..=0x2B1C
+            | 0x2B50
+            | 0x2B55
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F900..=0x1FAFF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+pub fn pad(glyph: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        glyph,
+        " ".repeat(width - display_width(glyph).min(width))
+    )
+}
+
+impl Theme {
+    pub fn glyph(&self, cell: Cell) -> &str {
+        match cell {
+            Cell::Player(Player::X) => &self.x_glyph,
+            Cell::Player(Player::O) => &self.o_glyph,
+            Cell::Player(p) => self
+                .other_glyphs
+                .get(p.idx() - 2)
+                .map_or("?", |glyph| glyph.as_str()),
+            Cell::Unmarked => &self.unmarked_glyph,
+        }
+    }
+
+    pub fn color(&self, player: Player) -> Option<AnsiColor> {
+        match player {
+            Player::X => Some(self.x_color),
+            Player::O => Some(self.o_color),
+            _ => self.other_colors.get(player.idx() - 2).copied(),
+        }
+    }
+
+    // how many columns the widest glyph takes up
+    pub fn cell_width(&self) -> usize {
+        [&self.x_glyph, &self.o_glyph, &self.unmarked_glyph]
+            .into_iter()
+            .chain(&self.other_glyphs)
+            .map(|glyph| display_width(glyph))
+            .max()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    // a border line for cells `width` wide, `left`, `middle` and `right` are
+    // the corner pieces
+    fn border(
+        &self,
+        num_cols: usize,
+        width: usize,
+        (left, middle, right): (&str, &str, &str),
+    ) -> String {
+        match self.borders {
+            Borders::Ascii => format!("  {}\n", "-".repeat((width + 1) * num_cols + 1)),
+            Borders::Unicode => {
+                let segments = vec!["─".repeat(width); num_cols];
+                format!("  {}{}{}\n", left, segments.join(middle), right)
+            }
+        }
+    }
+
+    // without colour, cells are picked out by brackets instead when
+    // `bracketed` leaves room for them: [X] on the line and (X) the last move
+    fn styled(&self, cell: Cell, highlight_line: bool, last_move: bool, bracketed: bool) -> String {
+        let glyph = pad(self.glyph(cell), self.cell_width());
+        if !self.color {
+            let (open, close) = match (bracketed, highlight_line, last_move) {
+                (false, _, _) => return glyph,
+                (true, true, _) => ("[", "]"),
+                (true, false, true) => ("(", ")"),
+                (true, false, false) => (" ", " "),
+            };
+            return format!("{}{}{}", open, glyph, close);
+        }
+        let mut codes: Vec<String> = vec![];
+        if highlight_line {
+            codes.push("1".to_string());
+            codes.push(AnsiColor::Green.code().to_string());
+        } else if let Cell::Player(p) = cell {
+            codes.extend(self.color(p).map(|color| color.code().to_string()));
+        }
+        if last_move {
+            codes.push("4".to_string());
+        }
+        match codes.len() {
+            0 => glyph,
+            _ => format!("\x1b[{}m{}\x1b[0m", codes.join(";"), glyph),
+        }
+    }
+
+    // `highlighted` are the indices of cells to pick out, e.g. a winning line
+    pub fn render_board(
+        &self,
+        board: &Board,
+        highlighted: &[usize],
+        last_move: Option<CellId>,
+    ) -> String {
+        // room for brackets is always left, so the grid keeps its width once
+        // something is picked out
+        let bracketed = !self.color;
+        let width = self.cell_width() + if bracketed { 2 } else { 0 };
+        let vertical = match self.borders {
+            Borders::Ascii => "|",
+            Borders::Unicode => "│",
+        };
+        let last_move_idx = last_move.map(|cell_id| cell_id.to_idx(board.num_cols));
+
+        let mut out: Vec<String> = Vec::new();
+        out.push("  ".to_string());
+        for column_idx in 0..board.num_cols {
+            let column_char = ((column_idx as u8 + b'a') as char).to_string();
+            let column_label = if bracketed {
+                format!(" {}", column_char)
+            } else {
+                column_char
+            };
+            out.push(format!(" {}", pad(&column_label, width)));
+        }
+        out.push("\n".to_string());
+
+        out.push(self.border(board.num_cols, width, ("┌", "┬", "┐")));
+        for (row_idx, row) in board.rows().enumerate() {
+            if row_idx > 0 {
+                out.push(self.border(board.num_cols, width, ("├", "┼", "┤")));
+            }
+            out.push(format!("{:<2}{}", row_idx + 1, vertical));
+            let cell_idxs = row.cell_idxs().to_vec();
+            for (cell, idx) in row.zip(cell_idxs) {
+                let styled = self.styled(
+                    *cell,
+                    highlighted.contains(&idx),
+                    last_move_idx == Some(idx),
+                    bracketed,
+                );
+                out.push(format!("{}{}", styled, vertical));
+            }
+            out.push("\n".to_string());
+        }
+        out.push(self.border(board.num_cols, width, ("└", "┴", "┘")));
+        out.join("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_plain_theme() {
+        let board = "X   O    ".parse::<Board>().unwrap();
+        let expected = "    a   b   c \n  -------------\n1 | X |   |   |\n  -------------\n2 |   | O |   |\n  -------------\n3 |   |   |   |\n  -------------\n";
+        assert_eq!(Theme::default().render_board(&board, &[], None), expected);
+    }
+
+    #[test]
+    fn test_unicode_borders() {
+        let theme = Theme {
+            borders: Borders::Unicode,
+            ..Theme::default()
+        };
+        let board = "X   O    ".parse::<Board>().unwrap();
+        let expected = "    a   b   c \n  ┌───┬───┬───┐\n1 │ X │   │   │\n  ├───┼───┼───┤\n2 │   │ O │   │\n  ├───┼───┼───┤\n3 │   │   │   │\n  └───┴───┴───┘\n";
+        assert_eq!(theme.render_board(&board, &[], None), expected);
+    }
+
+    #[test]
+    fn test_wide_glyphs_are_padded() {
+        assert_eq!(display_width("X"), 1);
+        assert_eq!(display_width("❌"), 2);
+        assert_eq!(display_width("⭕"), 2);
+        assert_eq!(display_width("❤\u{fe0f}"), 1);
+        let theme = Theme {
+            x_glyph: "❌".to_string(),
+            o_glyph: "⭕".to_string(),
+            ..Theme::default()
+        };
+        let board = "X   O    ".parse::<Board>().unwrap();
+        let rendered = theme.render_board(&board, &[], None);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "    a    b    c  ");
+        assert_eq!(lines[1], "  ----------------");
+        assert_eq!(lines[2], "1 | ❌ |    |    |");
+        assert_eq!(lines[4], "2 |    | ⭕ |    |");
+    }
+
+    #[test]
+    fn test_brackets_without_color() {
+        // the grid is as wide before anything is picked out as after
+        let empty = Theme::default().render_board(&Board::default(), &[], None);
+        assert_eq!(empty.lines().next(), Some("    a   b   c "));
+
+        let board = "XXXOO    ".parse::<Board>().unwrap();
+        let last_move = "b2".parse::<CellId>().ok();
+        let rendered = Theme::default().render_board(&board, &[0, 1, 2], last_move);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "    a   b   c ");
+        assert_eq!(lines[1], "  -------------");
+        assert_eq!(lines[2], "1 |[X]|[X]|[X]|");
+        assert_eq!(lines[4], "2 | O |(O)|   |");
+    }
+
+    #[test]
+    fn test_more_players() {
+        let theme = Theme {
+            color: true,
+            ..Theme::default()
+        };
+        let board = Board::parse_cells("XOΔ□").unwrap();
+        let rendered = theme.render_board(&board, &[], None);
+        let second_row = rendered.lines().nth(4).unwrap();
+        assert_eq!(second_row, "2 |\x1b[33mΔ\x1b[0m|\x1b[35m□\x1b[0m|");
+    }
+
+    #[test]
+    fn test_colors_and_highlights() {
+        let theme = Theme {
+            color: true,
+            ..Theme::default()
+        };
+        let board = "XXXOO    ".parse::<Board>().unwrap();
+        let last_move = "c1".parse::<CellId>().ok();
+        let rendered = theme.render_board(&board, &[0, 1, 2], last_move);
+        let first_row = rendered.lines().nth(2).unwrap();
+        assert_eq!(
+            first_row,
+            "1 |\x1b[1;32mX\x1b[0m|\x1b[1;32mX\x1b[0m|\x1b[1;32;4mX\x1b[0m|"
+        );
+        let second_row = rendered.lines().nth(4).unwrap();
+        assert_eq!(second_row, "2 |\x1b[34mO\x1b[0m|\x1b[34mO\x1b[0m| |");
+    }
+}
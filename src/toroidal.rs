@@ -0,0 +1,73 @@
+use crate::board::Board;
+use crate::board_has_win::{board_has_win_on_torus, tracks, winning_line_on_torus};
+use crate::cell::Cell;
+use crate::cells_are_valid::{cells_are_valid_on_torus, CellsImpossibleError};
+use crate::player::Player;
+use crate::ruleset::{Outcome, Ruleset};
+use crate::variant::Variant;
+
+// the board's edges join up like a torus, so diagonals carry on from the
+// other side and there are more lines to complete
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ToroidalRules;
+
+impl Ruleset for ToroidalRules {
+    fn variant(&self) -> Variant {
+        Variant::Toroidal
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        match board_has_win_on_torus(board) {
+            Cell::Player(p) => Some(Outcome::Win(p)),
+            Cell::Unmarked if board.is_full() => Some(Outcome::Draw),
+            Cell::Unmarked => None,
+        }
+    }
+
+    fn winning_line(&self, board: &Board) -> Option<Vec<usize>> {
+        winning_line_on_torus(board)
+    }
+
+    fn lines(&self, board: &Board) -> Vec<Vec<usize>> {
+        tracks(board, true)
+            .map(|track| track.cell_idxs().to_vec())
+            .collect()
+    }
+
+    fn validate_from(&self, board: &Board, first: Player) -> Result<(), CellsImpossibleError> {
+        cells_are_valid_on_torus(board, first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::rc::Rc;
+
+    use crate::position::Position;
+    use crate::rules_board::RulesBoard;
+
+    #[test]
+    fn test_toroidal_outcome() {
+        let board = " X OOXX  ".parse::<Board>().unwrap();
+        assert_eq!(ToroidalRules.outcome(&board), Some(Outcome::Win(Player::X)));
+        assert_eq!(ToroidalRules.winning_line(&board), Some(vec![1, 5, 6]));
+        assert!(ToroidalRules.validate(&board).is_ok());
+
+        // a wrapped diagonal on a bigger board, b1 c2 d3 a4
+        let board = " X  OOX O  X    ".parse::<Board>().unwrap();
+        assert_eq!(ToroidalRules.outcome(&board), None);
+        let board = " X  OOX O  XX   ".parse::<Board>().unwrap();
+        assert_eq!(ToroidalRules.outcome(&board), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn test_solve_on_torus() {
+        // with every cell on four lines, the first player cannot be stopped
+        let (outcome, _) = RulesBoard::new(Board::default(), Rc::new(ToroidalRules))
+            .solve()
+            .unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+    }
+}
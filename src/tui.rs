@@ -0,0 +1,348 @@
+use std::io::{self, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
+    MouseEventKind,
+};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+
+use crate::board::Board;
+use crate::cell::Cell;
+use crate::cell_id::CellId;
+use crate::game::Game;
+use crate::position::Position;
+use crate::rules_board::RulesBoard;
+use crate::ruleset::Outcome;
+use crate::theme::{pad, AnsiColor, Theme};
+
+// where the top left corner of the board is drawn
+const BOARD_LEFT: u16 = 0;
+const BOARD_TOP: u16 = 0;
+// each row has a border line below it
+const CELL_HEIGHT: u16 = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+fn move_cursor(cursor: CellId, direction: Direction, board: &Board) -> CellId {
+    let CellId { row, column } = cursor;
+    match direction {
+        Direction::Up => CellId {
+            row: row.saturating_sub(1),
+            column,
+        },
+        Direction::Down => CellId {
+            row: (row + 1).min(board.num_rows - 1),
+            column,
+        },
+        Direction::Left => CellId {
+            row,
+            column: column.saturating_sub(1),
+        },
+        Direction::Right => CellId {
+            row,
+            column: (column + 1).min(board.num_cols - 1),
+        },
+    }
+}
+
+// each cell is drawn as `| X `, with room for the widest glyph
+fn cell_width(theme: &Theme) -> u16 {
+    theme.cell_width() as u16 + 3
+}
+
+// the screen position of the glyph in a cell
+fn cell_position(cell_id: CellId, width: u16) -> (u16, u16) {
+    (
+        BOARD_LEFT + 4 + width * cell_id.column as u16,
+        BOARD_TOP + 2 + CELL_HEIGHT * cell_id.row as u16,
+    )
+}
+
+// the cell under a mouse click, if the click landed inside one
+fn cell_at(x: u16, y: u16, board: &Board, width: u16) -> Option<CellId> {
+    let x = x.checked_sub(BOARD_LEFT + 3)?;
+    let y = y.checked_sub(BOARD_TOP + 2)?;
+    if x % width == width - 1 || y % CELL_HEIGHT != 0 {
+        // a border
+        return None;
+    }
+    let cell_id = CellId {
+        row: (y / CELL_HEIGHT) as usize,
+        column: (x / width) as usize,
+    };
+    (cell_id.row < board.num_rows && cell_id.column < board.num_cols).then_some(cell_id)
+}
+
+fn terminal_color(color: AnsiColor) -> Color {
+    match color {
+        AnsiColor::Red => Color::DarkRed,
+        AnsiColor::Green => Color::DarkGreen,
+        AnsiColor::Yellow => Color::DarkYellow,
+        AnsiColor::Blue => Color::DarkBlue,
+        AnsiColor::Magenta => Color::DarkMagenta,
+        AnsiColor::Cyan => Color::DarkCyan,
+    }
+}
+
+struct Tui {
+    cursor: CellId,
+    message: String,
+}
+
+impl Tui {
+    fn draw<W: Write>(&self, game: &Game<RulesBoard>, out: &mut W) -> io::Result<()> {
+        let position = &game.position;
+        let board = &position.board;
+        let theme = &position.theme;
+        let width = cell_width(theme);
+        let highlighted = position.rules.winning_line(board).unwrap_or_default();
+        let segment = format!("{}+", "-".repeat(width as usize - 1));
+        let border = format!("  +{}", segment.repeat(board.num_cols));
+        queue!(out, Clear(ClearType::All), MoveTo(BOARD_LEFT, BOARD_TOP))?;
+        for column_idx in 0..board.num_cols {
+            let (x, _) = cell_position(
+                CellId {
+                    row: 0,
+                    column: column_idx,
+                },
+                width,
+            );
+            let column_char = (b'a' + column_idx as u8) as char;
+            queue!(out, MoveTo(x, BOARD_TOP), Print(column_char))?;
+        }
+        queue!(out, MoveTo(BOARD_LEFT, BOARD_TOP + 1), Print(&border))?;
+
+        for row_idx in 0..board.num_rows {
+            let y = BOARD_TOP + 2 + CELL_HEIGHT * row_idx as u16;
+            queue!(
+                out,
+                MoveTo(BOARD_LEFT, y),
+                Print(format!("{:<2}|", row_idx + 1))
+            )?;
+            for column_idx in 0..board.num_cols {
+                let cell_id = CellId {
+                    row: row_idx,
+                    column: column_idx,
+                };
+                let (x, _) = cell_position(cell_id, width);
+                let cell = board.cell(&cell_id);
+                if cell_id == self.cursor {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                // the winning line is bold either way, and green with colour on
+                if highlighted.contains(&cell_id.to_idx(board.num_cols)) {
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                    if theme.color {
+                        queue!(out, SetForegroundColor(Color::DarkGreen))?;
+                    }
+                } else if let Cell::Player(p) = cell {
+                    if let Some(color) = theme.color(p).filter(|_| theme.color) {
+                        queue!(out, SetForegroundColor(terminal_color(color)))?;
+                    }
+                }
+                let glyph = pad(theme.glyph(cell), theme.cell_width());
+                queue!(
+                    out,
+                    MoveTo(x - 1, y),
+                    Print(format!(" {} ", glyph)),
+                    SetAttribute(Attribute::Reset),
+                    ResetColor,
+                    Print("|")
+                )?;
+            }
+            queue!(out, MoveTo(BOARD_LEFT, y + 1), Print(&border))?;
+        }
+
+        let status_y = BOARD_TOP + 3 + CELL_HEIGHT * board.num_rows as u16;
+        let status = match position.outcome() {
+            Some(Outcome::Win(p)) => format!("Winner! {}", p),
+            Some(Outcome::Draw) => "Draw.".to_string(),
+            None => format!("Next Turn: {}", position.player_to_move()),
+        };
+        queue!(
+            out,
+            MoveTo(BOARD_LEFT, status_y),
+            Print(status),
+            MoveTo(BOARD_LEFT, status_y + 1),
+            Print(&self.message),
+            MoveTo(BOARD_LEFT, status_y + 2),
+            Print("arrows/wasd move, enter/space/click plays, u undo, p swap, h hint, q quit")
+        )?;
+
+        // the move history goes in a pane to the right of the board
+        let pane_x = BOARD_LEFT + 8 + width * board.num_cols as u16;
+        queue!(out, MoveTo(pane_x, BOARD_TOP), Print("Moves"))?;
+        let (_, height) = crossterm::terminal::size()?;
+        let num_shown = height.saturating_sub(BOARD_TOP + 2) as usize;
+        let first_shown = game.history.len().saturating_sub(num_shown);
+        for (move_idx, (before, cell_id)) in game.history.iter().enumerate().skip(first_shown) {
+            let y = BOARD_TOP + 1 + (move_idx - first_shown) as u16;
+            let player = before.player_to_move();
+            queue!(
+                out,
+                MoveTo(pane_x, y),
+                Print(format!("{:>3}. {} {}", move_idx + 1, player, cell_id))
+            )?;
+        }
+        out.flush()
+    }
+
+    fn play(&mut self, game: &mut Game<RulesBoard>, cell_id: CellId) {
+        if game.position.check_move(&cell_id).is_ok() {
+            game.update(cell_id);
+            self.message.clear();
+        } else {
+            self.message = "That cell is occupied".to_string();
+        }
+    }
+
+    // returns false once the player wants to leave
+    fn handle<W: Write>(&mut self, game: &mut Game<RulesBoard>, out: &mut W) -> io::Result<bool> {
+        if game.computer_wants_swap() {
+            game.swap();
+            self.message = "Sides swapped".to_string();
+            return Ok(true);
+        }
+        let game_over = game.position.outcome().is_some();
+        let player = game.position.player_to_move();
+        if !game_over && game.computer_players.contains(&player) {
+            if let Some(cell_id) = game.position.computer_move(game.depth) {
+                game.update(cell_id);
+                return Ok(true);
+            }
+        }
+        match read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                let direction = match key.code {
+                    KeyCode::Up | KeyCode::Char('w') => Some(Direction::Up),
+                    KeyCode::Down | KeyCode::Char('s') => Some(Direction::Down),
+                    KeyCode::Left | KeyCode::Char('a') => Some(Direction::Left),
+                    KeyCode::Right | KeyCode::Char('d') => Some(Direction::Right),
+                    _ => None,
+                };
+                if let Some(direction) = direction {
+                    self.cursor = move_cursor(self.cursor, direction, &game.position.board);
+                    return Ok(true);
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                    KeyCode::Enter | KeyCode::Char(' ') if !game_over => {
+                        self.play(game, self.cursor)
+                    }
+                    KeyCode::Char('u') => {
+                        if game.undo() {
+                            self.message.clear();
+                        } else {
+                            self.message = "Nothing to undo".to_string();
+                        }
+                    }
+                    KeyCode::Char('p') => {
+                        self.message = match game.swap() {
+                            true => "Sides swapped".to_string(),
+                            false => "You can only swap straight after the first move".to_string(),
+                        };
+                    }
+                    KeyCode::Char('h') if !game_over => {
+                        if let Some(cell_id) = game.position.computer_move(game.depth) {
+                            self.cursor = cell_id;
+                            self.message = format!("Hint: {}", cell_id);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                let width = cell_width(&game.position.theme);
+                if let Some(cell_id) = cell_at(mouse.column, mouse.row, &game.position.board, width)
+                {
+                    self.cursor = cell_id;
+                    if !game_over {
+                        self.play(game, cell_id);
+                    }
+                }
+            }
+            Event::Resize(_, _) => execute!(out, Clear(ClearType::All))?,
+            _ => {}
+        }
+        Ok(true)
+    }
+}
+
+pub fn run_tui(game: &mut Game<RulesBoard>) -> io::Result<()> {
+    let mut out = io::stdout();
+    enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, EnableMouseCapture, Hide)?;
+
+    let mut tui = Tui {
+        cursor: CellId {
+            row: game.position.board.num_rows / 2,
+            column: game.position.board.num_cols / 2,
+        },
+        message: String::new(),
+    };
+    let result = (|| loop {
+        tui.draw(game, &mut out)?;
+        if !tui.handle(game, &mut out)? {
+            return Ok(());
+        }
+    })();
+
+    // put the terminal back even if drawing failed
+    execute!(out, Show, DisableMouseCapture, LeaveAlternateScreen)?;
+    disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_move_cursor() {
+        let board = Board::default();
+        let corner = CellId { row: 0, column: 0 };
+        assert_eq!(move_cursor(corner, Direction::Up, &board), corner);
+        assert_eq!(move_cursor(corner, Direction::Left, &board), corner);
+        let cursor = move_cursor(corner, Direction::Right, &board);
+        let cursor = move_cursor(cursor, Direction::Down, &board);
+        assert_eq!(cursor, CellId { row: 1, column: 1 });
+        let cursor = move_cursor(cursor, Direction::Down, &board);
+        let cursor = move_cursor(cursor, Direction::Down, &board);
+        assert_eq!(cursor, CellId { row: 2, column: 1 });
+    }
+
+    #[test]
+    fn test_cell_at_matches_cell_position() {
+        let board = Board::new(4, 4);
+        // wide glyphs make for wider cells
+        let wide = Theme {
+            x_glyph: "❌".to_string(),
+            ..Theme::default()
+        };
+        for width in [cell_width(&Theme::default()), cell_width(&wide)] {
+            for idx in 0..board.num_cells() {
+                let cell_id = CellId::from_idx(idx, board.num_cols);
+                let (x, y) = cell_position(cell_id, width);
+                assert_eq!(cell_at(x, y, &board, width), Some(cell_id));
+                assert_eq!(cell_at(x - 1, y, &board, width), Some(cell_id));
+                assert_eq!(cell_at(x + width - 3, y, &board, width), Some(cell_id));
+                assert_eq!(cell_at(x + width - 2, y, &board, width), None);
+                assert_eq!(cell_at(x, y + 1, &board, width), None);
+            }
+            assert_eq!(cell_at(0, 0, &board, width), None);
+            assert_eq!(cell_at(100, 2, &board, width), None);
+        }
+    }
+}
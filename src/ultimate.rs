@@ -0,0 +1,398 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::board_has_win::board_has_win;
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+
+// both the meta board and the small boards are 3x3
+const SIZE: usize = 3;
+
+// a cell on one of the small boards, written `B2/a1` with the small board first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UltimateCellId {
+    pub board: CellId,
+    pub cell: CellId,
+}
+
+impl fmt::Display for UltimateCellId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.board.to_string().to_uppercase(), self.cell)
+    }
+}
+
+impl FromStr for UltimateCellId {
+    type Err = CellIdError;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (board_str, cell_str) = input.trim().split_once('/').ok_or(match input.trim() {
+            "" => CellIdError::Empty,
+            _ => CellIdError::UnparseableInput,
+        })?;
+        Ok(UltimateCellId {
+            board: CellId::parse_for(board_str.trim(), SIZE, SIZE)?,
+            cell: CellId::parse_for(cell_str.trim(), SIZE, SIZE)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum UltimateMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    // the move has to go on the board the last move sent it to
+    WrongBoard(CellId),
+    // the small board is already won or full
+    BoardClosed(CellId),
+    Occupied,
+}
+
+impl fmt::Display for UltimateMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UltimateMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            UltimateMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a board and a cell e.g. B2/a1")
+            }
+            UltimateMoveError::GameOver => write!(f, "The game is over"),
+            UltimateMoveError::WrongBoard(board) => write!(
+                f,
+                "You have to play on board {}",
+                board.to_string().to_uppercase()
+            ),
+            UltimateMoveError::BoardClosed(board) => write!(
+                f,
+                "Board {} is already finished",
+                board.to_string().to_uppercase()
+            ),
+            UltimateMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ultimate {
+    // the small boards, in the same order as the cells of `meta`
+    pub boards: Vec<Board>,
+    // who has won each small board
+    pub meta: Board,
+    pub player: Player,
+    // where the last move sent the next one, None to play on any open board
+    pub next_board: Option<CellId>,
+}
+
+impl Default for Ultimate {
+    fn default() -> Self {
+        Ultimate {
+            boards: vec![Board::default(); SIZE * SIZE],
+            meta: Board::default(),
+            player: Player::X,
+            next_board: None,
+        }
+    }
+}
+
+// how many lines `player` could still complete with one more mark
+fn threats(board: &Board, player: Player) -> i32 {
+    let tracks = board.rows().chain(board.columns()).chain(board.diagonals());
+    tracks
+        .map(|track| track.copied().collect::<Vec<Cell>>())
+        .filter(|cells| {
+            let own = cells.iter().filter(|c| **c == Cell::Player(player)).count();
+            let empty = cells.iter().filter(|c| **c == Cell::Unmarked).count();
+            own == cells.len() - 1 && empty == 1
+        })
+        .count() as i32
+}
+
+impl Ultimate {
+    // a board is open until it has been won or filled
+    pub fn is_open(&self, board: CellId) -> bool {
+        self.meta.cell(&board) == Cell::Unmarked && !self.boards[board.to_idx(SIZE)].is_full()
+    }
+
+    pub fn open_boards(&self) -> Vec<CellId> {
+        (0..SIZE * SIZE)
+            .map(|idx| CellId::from_idx(idx, SIZE))
+            .filter(|board| self.is_open(*board))
+            .collect()
+    }
+
+    // the boards the player to move may play on
+    pub fn playable_boards(&self) -> Vec<CellId> {
+        match self.next_board {
+            Some(board) => vec![board],
+            None => self.open_boards(),
+        }
+    }
+
+    fn cell_glyph(&self, board: CellId, cell: CellId) -> String {
+        match self.boards[board.to_idx(SIZE)].cell(&cell) {
+            Cell::Player(p) => p.to_string(),
+            Cell::Unmarked => ".".to_string(),
+        }
+    }
+}
+
+impl Position for Ultimate {
+    type Move = UltimateCellId;
+    type MoveError = UltimateMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match board_has_win(&self.meta) {
+            Cell::Player(p) => Some(Outcome::Win(p)),
+            Cell::Unmarked if self.open_boards().is_empty() => Some(Outcome::Draw),
+            Cell::Unmarked => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<UltimateCellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.playable_boards()
+            .into_iter()
+            .flat_map(|board| {
+                self.boards[board.to_idx(SIZE)]
+                    .unmarked_cell_ids()
+                    .map(move |cell| UltimateCellId { board, cell })
+                    .collect::<Vec<UltimateCellId>>()
+            })
+            .collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<UltimateCellId, UltimateMoveError> {
+        input.parse().map_err(UltimateMoveError::BadCell)
+    }
+
+    fn check_move(&self, mv: &UltimateCellId) -> Result<(), UltimateMoveError> {
+        if self.outcome().is_some() {
+            return Err(UltimateMoveError::GameOver);
+        }
+        if let Some(board) = self.next_board {
+            if board != mv.board {
+                return Err(UltimateMoveError::WrongBoard(board));
+            }
+        }
+        if !self.is_open(mv.board) {
+            return Err(UltimateMoveError::BoardClosed(mv.board));
+        }
+        if self.boards[mv.board.to_idx(SIZE)].cell(&mv.cell) != Cell::Unmarked {
+            return Err(UltimateMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: UltimateCellId) {
+        let board = &mut self.boards[mv.board.to_idx(SIZE)];
+        board.cells[mv.cell.to_idx(SIZE)] = Cell::Player(self.player);
+        if board_has_win(board) == Cell::Player(self.player) {
+            self.meta.cells[mv.board.to_idx(SIZE)] = Cell::Player(self.player);
+        }
+        // the cell picked sends the opponent to the matching board,
+        // unless that one is finished and they may go anywhere
+        self.next_board = self.is_open(mv.cell).then_some(mv.cell);
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        "Type a board A1-C3 and a cell a1-c3 on it e.g. B2/a1".to_string()
+    }
+
+    fn evaluate(&self, player: Player) -> i32 {
+        let opponent = next_player(player);
+        let won = |p: Player| {
+            self.meta
+                .cells
+                .iter()
+                .filter(|c| **c == Cell::Player(p))
+                .count() as i32
+        };
+        let small_threats = |p: Player| {
+            self.open_boards()
+                .iter()
+                .map(|board| threats(&self.boards[board.to_idx(SIZE)], p))
+                .sum::<i32>()
+        };
+        100 * (threats(&self.meta, player) - threats(&self.meta, opponent))
+            + 20 * (won(player) - won(opponent))
+            + small_threats(player)
+            - small_threats(opponent)
+    }
+}
+
+// the small boards are drawn in place, e.g.
+//
+//        A       B       C
+//      a b c   a b c   a b c
+//   1  X . . | . . . | . . .
+// 1 2  . O . | . . . | . . .
+//   3  . . . | . . . | . . .
+//      ------+-------+------
+impl fmt::Display for Ultimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns: Vec<String> = (0..SIZE)
+            .map(|column| format!("  {}  ", (b'A' + column as u8) as char))
+            .collect();
+        writeln!(f, "     {}", columns.join("   ").trim_end())?;
+        writeln!(f, "     {}", ["a b c"; SIZE].join("   "))?;
+        for meta_row in 0..SIZE {
+            if meta_row > 0 {
+                writeln!(f, "     {}", ["-----"; SIZE].join("-+-"))?;
+            }
+            for row in 0..SIZE {
+                let label = match row {
+                    1 => (meta_row + 1).to_string(),
+                    _ => " ".to_string(),
+                };
+                let blocks: Vec<String> = (0..SIZE)
+                    .map(|meta_column| {
+                        let board = CellId {
+                            row: meta_row,
+                            column: meta_column,
+                        };
+                        let glyphs: Vec<String> = (0..SIZE)
+                            .map(|column| self.cell_glyph(board, CellId { row, column }))
+                            .collect();
+                        glyphs.join(" ")
+                    })
+                    .collect();
+                writeln!(f, "{} {}  {}", label, row + 1, blocks.join(" | "))?;
+            }
+        }
+
+        let won: Vec<String> = (0..SIZE * SIZE)
+            .filter_map(|idx| match self.meta.cells[idx] {
+                Cell::Player(p) => Some(format!(
+                    "{} {}",
+                    CellId::from_idx(idx, SIZE).to_string().to_uppercase(),
+                    p
+                )),
+                Cell::Unmarked => None,
+            })
+            .collect();
+        if !won.is_empty() {
+            writeln!(f, "Won boards: {}", won.join(", "))?;
+        }
+        if self.outcome().is_none() {
+            match self.next_board {
+                Some(board) => writeln!(f, "Play on board {}", board.to_string().to_uppercase())?,
+                None => writeln!(f, "Play on any open board")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // replays a list of moves from the start, checking each one
+    fn from_moves(moves: &[UltimateCellId]) -> Result<Ultimate, (usize, UltimateMoveError)> {
+        let mut ultimate = Ultimate::default();
+        for (move_idx, mv) in moves.iter().enumerate() {
+            ultimate.check_move(mv).map_err(|err| (move_idx, err))?;
+            ultimate.play(*mv);
+        }
+        Ok(ultimate)
+    }
+
+    fn moves(moves_str: &str) -> Vec<UltimateCellId> {
+        moves_str
+            .split_whitespace()
+            .map(|m| m.parse::<UltimateCellId>().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_cell_id_to_from_string() {
+        let mv = "B2/a1".parse::<UltimateCellId>().unwrap();
+        assert_eq!(mv.board, CellId { row: 1, column: 1 });
+        assert_eq!(mv.cell, CellId { row: 0, column: 0 });
+        assert_eq!(mv.to_string(), "B2/a1");
+        assert_eq!("b2 / 1A".parse::<UltimateCellId>().unwrap(), mv);
+        assert!(matches!(
+            "B2".parse::<UltimateCellId>(),
+            Err(CellIdError::UnparseableInput)
+        ));
+        assert!(matches!(
+            "D2/a1".parse::<UltimateCellId>(),
+            Err(CellIdError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn test_send_constraint() {
+        let ultimate = from_moves(&moves("B2/a1")).unwrap();
+        assert_eq!(ultimate.next_board, "a1".parse::<CellId>().ok());
+        assert_eq!(ultimate.legal_moves().len(), 9);
+        assert!(matches!(
+            ultimate.check_move(&"B2/b2".parse().unwrap()),
+            Err(UltimateMoveError::WrongBoard(_))
+        ));
+        assert!(ultimate.check_move(&"A1/b2".parse().unwrap()).is_ok());
+
+        let result = from_moves(&moves("B2/a1 A1/b2 B2/a1"));
+        assert!(matches!(result, Err((2, UltimateMoveError::Occupied))));
+    }
+
+    #[test]
+    fn test_won_board_frees_the_next_move() {
+        // X takes the middle column of A1, being sent back there each time
+        let ultimate = from_moves(&moves("A1/b1 B1/a1 A1/b2 B2/a1 A1/b3")).unwrap();
+        let a1 = CellId { row: 0, column: 0 };
+        assert_eq!(ultimate.meta.cell(&a1), Cell::Player(Player::X));
+        assert_eq!(ultimate.next_board, "b3".parse::<CellId>().ok());
+
+        // O then sends X to A1, which is closed, so X may play anywhere open
+        let ultimate = from_moves(&moves("A1/b1 B1/a1 A1/b2 B2/a1 A1/b3 B3/a1")).unwrap();
+        assert_eq!(ultimate.next_board, None);
+        assert!(matches!(
+            ultimate.check_move(&"A1/a2".parse().unwrap()),
+            Err(UltimateMoveError::BoardClosed(_))
+        ));
+        let num_open_cells: usize = ultimate
+            .open_boards()
+            .iter()
+            .map(|b| ultimate.boards[b.to_idx(SIZE)].unmarked_cell_ids().count())
+            .sum();
+        assert_eq!(ultimate.legal_moves().len(), num_open_cells);
+    }
+
+    #[test]
+    fn test_meta_win() {
+        let mut ultimate = Ultimate {
+            meta: "XX OO    ".parse::<Board>().unwrap(),
+            ..Ultimate::default()
+        };
+        assert_eq!(ultimate.outcome(), None);
+        ultimate.meta.cells[2] = Cell::Player(Player::X);
+        assert_eq!(ultimate.outcome(), Some(Outcome::Win(Player::X)));
+        assert!(ultimate.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let ultimate = from_moves(&moves("B2/a1")).unwrap();
+        let rendered = ultimate.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "       A       B       C");
+        assert_eq!(lines[1], "     a b c   a b c   a b c");
+        assert_eq!(lines[5], "     ------+-------+------");
+        assert_eq!(lines[6], "  1  . . . | X . . | . . .");
+        assert_eq!(lines[7], "2 2  . . . | . . . | . . .");
+        assert_eq!(lines[13], "Play on board A1");
+    }
+}
@@ -0,0 +1,277 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::{board_has_win, winning_line};
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::retrograde::solve_with_loops;
+use crate::ruleset::Outcome;
+use crate::theme::Theme;
+
+// how many marks each player keeps on the board
+pub const KEEP: usize = 3;
+
+// the same marks, of the same ages, with the same player to move this many times is a draw
+const REPETITIONS: usize = 3;
+
+#[derive(Debug)]
+pub enum VanishingMoveError {
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+}
+
+impl fmt::Display for VanishingMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VanishingMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            VanishingMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a cell e.g. b2")
+            }
+            VanishingMoveError::GameOver => write!(f, "The game is over"),
+            VanishingMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+// each player only keeps their three newest marks, so placing a fourth
+// takes their oldest one off the board. the `Board` only says who holds a
+// cell, so the marks are also kept in the order they were made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vanishing {
+    pub board: Board,
+    pub player: Player,
+    // each player's marks, oldest first
+    pub x_marks: Vec<CellId>,
+    pub o_marks: Vec<CellId>,
+    // the key of every position so far, to spot repetition
+    pub seen: Vec<u32>,
+    pub theme: Theme,
+}
+
+impl Default for Vanishing {
+    fn default() -> Self {
+        let mut vanishing = Vanishing {
+            board: Board::default(),
+            player: Player::X,
+            x_marks: vec![],
+            o_marks: vec![],
+            seen: vec![],
+            theme: Theme::default(),
+        };
+        vanishing.seen.push(vanishing.key());
+        vanishing
+    }
+}
+
+impl Vanishing {
+    pub fn marks(&self, player: Player) -> &[CellId] {
+        match player {
+            Player::X => &self.x_marks,
+            _ => &self.o_marks,
+        }
+    }
+
+    fn marks_mut(&mut self, player: Player) -> &mut Vec<CellId> {
+        match player {
+            Player::X => &mut self.x_marks,
+            _ => &mut self.o_marks,
+        }
+    }
+
+    // the mark `player` loses when they next place one, if they have a full set
+    pub fn vanishes_next(&self, player: Player) -> Option<CellId> {
+        let marks = self.marks(player);
+        match marks.len() {
+            KEEP => marks.first().copied(),
+            _ => None,
+        }
+    }
+
+    // each player's marks in order, a cell number from 1 in four bits each,
+    // and who is to move
+    fn key(&self) -> u32 {
+        let marks = |marks: &[CellId]| {
+            marks.iter().fold(0, |key, cell_id| {
+                key << 4 | (cell_id.to_idx(self.board.num_cols) as u32 + 1)
+            })
+        };
+        let key = marks(&self.x_marks) << (4 * KEEP) | marks(&self.o_marks);
+        key << 1 | (self.player == Player::O) as u32
+    }
+}
+
+impl Position for Vanishing {
+    type Move = CellId;
+    type MoveError = VanishingMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    // there are never enough marks to fill the board, so the only draw is by repetition
+    fn outcome(&self) -> Option<Outcome> {
+        if let Cell::Player(p) = board_has_win(&self.board) {
+            return Some(Outcome::Win(p));
+        }
+        let key = self.key();
+        if self.seen.iter().filter(|seen| **seen == key).count() >= REPETITIONS {
+            return Some(Outcome::Draw);
+        }
+        None
+    }
+
+    fn legal_moves(&self) -> Vec<CellId> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board.unmarked_cell_ids().collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<CellId, VanishingMoveError> {
+        CellId::parse_for(input.trim(), self.board.num_rows, self.board.num_cols)
+            .map_err(VanishingMoveError::BadCell)
+    }
+
+    // the oldest mark is still there while choosing, so it cannot be replaced in place
+    fn check_move(&self, mv: &CellId) -> Result<(), VanishingMoveError> {
+        if self.outcome().is_some() {
+            return Err(VanishingMoveError::GameOver);
+        }
+        if self.board.cell(mv) != Cell::Unmarked {
+            return Err(VanishingMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: CellId) {
+        let player = self.player;
+        if let Some(oldest) = self.vanishes_next(player) {
+            self.board.cells[oldest.to_idx(self.board.num_cols)] = Cell::Unmarked;
+            self.marks_mut(player).remove(0);
+        }
+        self.board.cells[mv.to_idx(self.board.num_cols)] = Cell::Player(player);
+        self.marks_mut(player).push(mv);
+        self.player = next_player(player);
+        self.seen.push(self.key());
+    }
+
+    fn move_help(&self) -> String {
+        format!(
+            "Type a cell e.g. b2, your oldest mark goes once you have {}",
+            KEEP
+        )
+    }
+
+    fn computer_move(&self, _depth: usize) -> Option<CellId> {
+        self.solve().and_then(|(_, best_move)| best_move)
+    }
+
+    // marks come and go, so the same position can come round again
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<CellId>)> {
+        if let Some(outcome) = self.outcome() {
+            return Some((outcome, None));
+        }
+        Some(solve_with_loops(self, Vanishing::key, |vanishing| {
+            vanishing.seen.clear()
+        }))
+    }
+}
+
+// the board with the mark about to vanish highlighted, and named below it
+impl fmt::Display for Vanishing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vanishes_next = self.vanishes_next(self.player);
+        let highlighted = match (winning_line(&self.board), vanishes_next) {
+            (Some(line), _) => line,
+            (None, Some(cell_id)) => vec![cell_id.to_idx(self.board.num_cols)],
+            (None, None) => vec![],
+        };
+        write!(
+            f,
+            "{}",
+            self.theme.render_board(&self.board, &highlighted, None)
+        )?;
+        if let (None, Some(cell_id)) = (self.outcome(), vanishes_next) {
+            writeln!(f, "{}'s mark on {} vanishes next", self.player, cell_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(vanishing: &mut Vanishing, moves: &str) {
+        for mv_str in moves.split_whitespace() {
+            let mv = vanishing.parse_move(mv_str).unwrap();
+            vanishing.check_move(&mv).unwrap();
+            vanishing.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_oldest_mark_vanishes() {
+        let mut vanishing = Vanishing::default();
+        play_all(&mut vanishing, "a1 b2 c1 b1 a3");
+        assert_eq!(vanishing.vanishes_next(Player::X), "a1".parse().ok());
+        assert_eq!(vanishing.vanishes_next(Player::O), None);
+        play_all(&mut vanishing, "c2");
+        assert!(vanishing
+            .to_string()
+            .ends_with("X's mark on a1 vanishes next\n"));
+        assert!(matches!(
+            vanishing.check_move(&"a1".parse().unwrap()),
+            Err(VanishingMoveError::Occupied)
+        ));
+
+        // a2 would finish column a, but X's fourth mark takes a1 away
+        play_all(&mut vanishing, "a2");
+        assert_eq!(vanishing.board.cell(&"a1".parse().unwrap()), Cell::Unmarked);
+        assert_eq!(
+            vanishing.marks(Player::X),
+            ["c1", "a3", "a2"].map(|c| c.parse().unwrap())
+        );
+        assert_eq!(vanishing.outcome(), None);
+        assert_eq!(vanishing.vanishes_next(Player::O), "b2".parse().ok());
+    }
+
+    #[test]
+    fn test_repetition_draws() {
+        let mut vanishing = Vanishing::default();
+        // each player's marks go round the same four cells
+        let cycle = "a1 c1 b1 c2 a2 a3 b2 b3";
+        play_all(&mut vanishing, cycle);
+        play_all(&mut vanishing, cycle);
+        play_all(&mut vanishing, "a1 c1 b1 c2 a2");
+        assert_eq!(vanishing.outcome(), None);
+        // the position after O's a3 comes round for the third time
+        play_all(&mut vanishing, "a3");
+        assert_eq!(vanishing.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn test_solve() {
+        // c1 threatens both b1 and c2
+        let mut vanishing = Vanishing::default();
+        play_all(&mut vanishing, "a1 b2 c3 a3");
+        let (outcome, best_move) = vanishing.solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        assert_eq!(best_move, "c1".parse().ok());
+
+        // unlike the classic game, the first player wins
+        let (outcome, _) = Vanishing::default().solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+    }
+}
@@ -0,0 +1,76 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::misere::MisereRules;
+use crate::player::Player;
+use crate::ruleset::{with_first_player, ClassicRules, Ruleset};
+use crate::toroidal::ToroidalRules;
+
+// the rulesets that can be picked by name
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    Classic,
+    Misere,
+    Toroidal,
+}
+
+impl Variant {
+    pub fn ruleset(self) -> Box<dyn Ruleset> {
+        match self {
+            Variant::Classic => Box::new(ClassicRules),
+            Variant::Misere => Box::new(MisereRules),
+            Variant::Toroidal => Box::new(ToroidalRules),
+        }
+    }
+
+    // the same rules with `first` making the first move
+    pub fn ruleset_for(self, first: Player) -> Box<dyn Ruleset> {
+        with_first_player(self.ruleset(), first)
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Classic => write!(f, "classic"),
+            Variant::Misere => write!(f, "misere"),
+            Variant::Toroidal => write!(f, "toroidal"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownVariant;
+
+impl FromStr for Variant {
+    type Err = UnknownVariant;
+    fn from_str(variant_str: &str) -> Result<Self, Self::Err> {
+        match variant_str {
+            "classic" => Ok(Variant::Classic),
+            "misere" | "misère" => Ok(Variant::Misere),
+            "toroidal" | "torus" => Ok(Variant::Toroidal),
+            _ => Err(UnknownVariant),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_variant_names() {
+        for variant in [Variant::Classic, Variant::Misere, Variant::Toroidal] {
+            assert_eq!(variant.to_string().parse::<Variant>().unwrap(), variant);
+            assert_eq!(variant.ruleset().variant(), variant);
+        }
+        assert_eq!("misère".parse::<Variant>().unwrap(), Variant::Misere);
+        assert!("backwards".parse::<Variant>().is_err());
+
+        let rules = Variant::Toroidal.ruleset_for(Player::O);
+        assert_eq!(rules.variant(), Variant::Toroidal);
+        assert_eq!(rules.player_to_move(&Default::default()), Player::O);
+    }
+}
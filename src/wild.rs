@@ -0,0 +1,285 @@
+use core::fmt;
+
+use crate::board::Board;
+use crate::board_has_win::{board_has_win, winning_line};
+use crate::cell::Cell;
+use crate::cell_id::{CellId, CellIdError};
+use crate::game_update::next_player;
+use crate::player::Player;
+use crate::position::Position;
+use crate::ruleset::Outcome;
+use crate::search_position::search_position;
+use crate::solve_position::{score_outcome, solve_position, Solvable};
+use crate::theme::Theme;
+
+// bigger boards have too many positions to solve exactly
+const MAX_SOLVED_CELLS: usize = 9;
+
+// a mark either player may draw. it is not whoever draws it, so it is
+// kept apart from `Player` and only stored as one's cell on the board
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mark {
+    X,
+    O,
+}
+
+impl Mark {
+    pub const ALL: [Mark; 2] = [Mark::X, Mark::O];
+
+    // the cell holding this mark, drawn with the glyph of the player of the same name
+    pub fn cell(self) -> Cell {
+        match self {
+            Mark::X => Cell::Player(Player::X),
+            Mark::O => Cell::Player(Player::O),
+        }
+    }
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mark::X => write!(f, "X"),
+            Mark::O => write!(f, "O"),
+        }
+    }
+}
+
+// either player may draw either mark, so a move names both, e.g. `X b2`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WildMove {
+    pub mark: Mark,
+    pub cell: CellId,
+}
+
+impl fmt::Display for WildMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.mark, self.cell)
+    }
+}
+
+#[derive(Debug)]
+pub enum WildMoveError {
+    BadMark(String),
+    BadCell(CellIdError),
+    GameOver,
+    Occupied,
+}
+
+impl fmt::Display for WildMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WildMoveError::BadMark(input) => {
+                write!(f, "{:?} does not start with X or O e.g. X b2", input)
+            }
+            WildMoveError::BadCell(CellIdError::OutOfBounds) => {
+                write!(f, "That cell is off the board")
+            }
+            WildMoveError::BadCell(CellIdError::Empty | CellIdError::UnparseableInput) => {
+                write!(f, "Please name a mark and a cell e.g. X b2")
+            }
+            WildMoveError::GameOver => write!(f, "The game is over"),
+            WildMoveError::Occupied => write!(f, "That cell is occupied"),
+        }
+    }
+}
+
+// a mark then a cell on a board of the given size, with or without a space between them
+pub fn parse_mark_move(
+    input: &str,
+    num_rows: usize,
+    num_cols: usize,
+) -> Result<WildMove, WildMoveError> {
+    let input = input.trim();
+    let mut chars = input.chars();
+    let mark = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('X') => Mark::X,
+        Some('O') => Mark::O,
+        _ => return Err(WildMoveError::BadMark(input.to_string())),
+    };
+    let cell = CellId::parse_for(chars.as_str().trim(), num_rows, num_cols)
+        .map_err(WildMoveError::BadCell)?;
+    Ok(WildMove { mark, cell })
+}
+
+// the board's cells hold marks rather than who made them, and whoever
+// completes a line of either mark wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wild {
+    pub board: Board,
+    pub player: Player,
+    pub theme: Theme,
+}
+
+impl Default for Wild {
+    fn default() -> Self {
+        Wild::new(3)
+    }
+}
+
+impl Wild {
+    pub fn new(size: usize) -> Wild {
+        Wild {
+            board: Board::new(size, size),
+            player: Player::X,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Position for Wild {
+    type Move = WildMove;
+    type MoveError = WildMoveError;
+
+    fn player_to_move(&self) -> Player {
+        self.player
+    }
+
+    // the game stops as soon as a line is made, so it was made by the last player
+    fn outcome(&self) -> Option<Outcome> {
+        match board_has_win(&self.board) {
+            Cell::Player(_) => Some(Outcome::Win(next_player(self.player))),
+            Cell::Unmarked if self.board.is_full() => Some(Outcome::Draw),
+            Cell::Unmarked => None,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<WildMove> {
+        if self.outcome().is_some() {
+            return vec![];
+        }
+        self.board
+            .unmarked_cell_ids()
+            .flat_map(|cell| Mark::ALL.map(|mark| WildMove { mark, cell }))
+            .collect()
+    }
+
+    fn parse_move(&self, input: &str) -> Result<WildMove, WildMoveError> {
+        parse_mark_move(input, self.board.num_rows, self.board.num_cols)
+    }
+
+    fn check_move(&self, mv: &WildMove) -> Result<(), WildMoveError> {
+        if self.outcome().is_some() {
+            return Err(WildMoveError::GameOver);
+        }
+        if self.board.cell(&mv.cell) != Cell::Unmarked {
+            return Err(WildMoveError::Occupied);
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, mv: WildMove) {
+        let idx = mv.cell.to_idx(self.board.num_cols);
+        self.board.cells[idx] = mv.mark.cell();
+        self.player = next_player(self.player);
+    }
+
+    fn move_help(&self) -> String {
+        "Type the mark you want, X or O, then a cell e.g. X b2".to_string()
+    }
+
+    fn computer_move(&self, depth: usize) -> Option<WildMove> {
+        match self.solve() {
+            Some((_, best_move)) => best_move,
+            None => search_position(self, depth),
+        }
+    }
+
+    fn solvable(&self) -> bool {
+        true
+    }
+
+    fn solve(&self) -> Option<(Outcome, Option<WildMove>)> {
+        if self.board.num_cells() > MAX_SOLVED_CELLS {
+            return None;
+        }
+        Some(solve_position(self))
+    }
+}
+
+// either player may draw either mark, but who is to move still follows from
+// how many cells are marked
+impl Solvable for Wild {
+    type Key = Vec<Cell>;
+
+    fn key(&self) -> Vec<Cell> {
+        self.board.cells.clone()
+    }
+
+    fn score(&self, player: Player) -> Option<i32> {
+        score_outcome(self.outcome(), &self.board, player)
+    }
+}
+
+impl fmt::Display for Wild {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highlighted = winning_line(&self.board).unwrap_or_default();
+        write!(
+            f,
+            "{}",
+            self.theme.render_board(&self.board, &highlighted, None)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn play_all(wild: &mut Wild, moves: &str) {
+        for mv_str in moves.split(',') {
+            let mv = wild.parse_move(mv_str).unwrap();
+            wild.check_move(&mv).unwrap();
+            wild.play(mv);
+        }
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let wild = Wild::default();
+        let mv = wild.parse_move("o B2").unwrap();
+        assert_eq!(mv.mark, Mark::O);
+        assert_eq!(mv.to_string(), "O b2");
+        assert_eq!(wild.parse_move("Xa1").unwrap().to_string(), "X a1");
+        assert!(matches!(
+            wild.parse_move("b2"),
+            Err(WildMoveError::BadMark(_))
+        ));
+        assert!(matches!(
+            wild.parse_move("X d1"),
+            Err(WildMoveError::BadCell(CellIdError::OutOfBounds))
+        ));
+    }
+
+    #[test]
+    fn test_either_mark_wins() {
+        // O completes a line of Xs, so O wins
+        let mut wild = Wild::default();
+        play_all(&mut wild, "X a1, O c3, X b1");
+        assert_eq!(wild.outcome(), None);
+        play_all(&mut wild, "X c1");
+        assert_eq!(wild.outcome(), Some(Outcome::Win(Player::O)));
+        assert!(matches!(
+            wild.check_move(&wild.parse_move("O b2").unwrap()),
+            Err(WildMoveError::GameOver)
+        ));
+    }
+
+    #[test]
+    fn test_solve() {
+        // the first player wins, starting in the centre
+        let wild = Wild::default();
+        let (outcome, best_move) = wild.solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::X));
+        assert_eq!(best_move.map(|mv| mv.cell), "b2".parse().ok());
+
+        // two of a mark in a line with the third cell empty is an easy win
+        let mut wild = Wild::default();
+        play_all(&mut wild, "O a1, X c3, O b1");
+        let (outcome, best_move) = wild.solve().unwrap();
+        assert_eq!(outcome, Outcome::Win(Player::O));
+        assert_eq!(best_move.map(|mv| mv.to_string()), Some("O c1".to_string()));
+
+        assert_eq!(Wild::new(4).solve(), None);
+    }
+}